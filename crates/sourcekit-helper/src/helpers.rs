@@ -21,9 +21,15 @@ pub fn get_index_store_path(cache_path: &String, config_filepath: &PathBuf) -> S
     index_store_path
 }
 
-/// Try to get .compile filepath
+/// Try to get compile database filepath, `.compile` unless set in `.xbase.json`
 pub fn get_compile_filepath(url: &Url) -> Option<PathBuf> {
-    url.join(".compile")
+    let name = read_to_string(Path::new(url.path()).join(".xbase.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|config| config.get("compile_database")?.as_str().map(String::from))
+        .unwrap_or_else(|| ".compile".into());
+
+    url.join(&name)
         .ok()?
         .path()
         .pipe(PathBuf::from)
@@ -9,8 +9,46 @@ local M = {}
 
 M.expect_second_run = false
 
+--- Time without any message (including pings) after which the daemon is considered dead
+local PING_GRACE_MS = 90 * 1000
+
+--- Client ids broadcasts identify listeners by, keeping a single listener per id. The editor
+--- listens and registers projects (see server.lua) as its pid. Tailing listens as its pid offset
+--- by TAIL_ID_OFFSET, so that it doesn't replace the editor listener of the same broadcast.
+local CLIENT_ID = vim.loop.os_getpid()
+local TAIL_ID_OFFSET = 1000000
+
+--- Last time a message was received, keyed by project root
+M.last_seen = {}
+
+--- Warn once if no message was received from the daemon within grace window
+local function start_liveness_check(root)
+  local timer = vim.loop.new_timer()
+  timer:start(PING_GRACE_MS, PING_GRACE_MS / 3, function()
+    if vim.loop.now() - (M.last_seen[root] or 0) > PING_GRACE_MS then
+      timer:stop()
+      timer:close()
+      vim.schedule(function()
+        notify("daemon isn't responding, restart nvim or re-register the project", "Warn")
+      end)
+    end
+  end)
+end
+
+--- Replace quickfix list with one without diagnostics of a target, as it's being rebuilt
+local function clear_diagnostics(target)
+  local items = vim.tbl_filter(function(item)
+    return item.user_data ~= target
+  end, vim.fn.getqflist())
+  vim.fn.setqflist({}, "r", { title = "XBase", items = items })
+end
+
 local function task_set(args)
+  if tkind.is_build(args.kind) or tkind.is_test(args.kind) then
+    clear_diagnostics(args.target)
+  end
   M.has_task = true
+  M.progress = nil
   local running, _ = tkind:prefix(args.kind)
   args.prefix = running
   vim.g.xbase_ctask = args
@@ -36,6 +74,9 @@ local function task_update(args)
   if level ~= "Debug" and level ~= "Trace" then
     content = string.gsub(content, "%[" .. target .. "%]%s", "")
     local line = string.format("[%s] %s: %s", target, prefix, content)
+    if M.progress then
+      line = string.format("[%s] %s (%s): %s", target, prefix, M.progress, content)
+    end
 
     if tkind.is_run(kind) then
       local icon = config.statusline.device_running.icon
@@ -47,6 +88,75 @@ local function task_update(args)
   end
 end
 
+local function task_progress(args)
+  local ctask = vim.g.xbase_ctask
+  if not ctask or ctask.target ~= args.task then
+    return
+  end
+
+  local progress
+  if args.total then
+    -- total is estimated from previous successful builds
+    progress = ("~%d%%"):format(math.floor(args.current * 100 / args.total))
+  else
+    progress = ("%d compiled"):format(args.current)
+  end
+
+  M.progress = progress
+  vim.g.xbase_ctask_line = string.format("[%s] %s (%s)", ctask.target, ctask.prefix, progress)
+end
+
+local qf_type = { Error = "E", Warning = "W", Note = "N" }
+
+local function add_diagnostic(args)
+  local diagnostic = args.diagnostic
+  local items = {
+    {
+      filename = diagnostic.file,
+      lnum = diagnostic.line or 0,
+      col = diagnostic.column or 0,
+      type = qf_type[diagnostic.severity],
+      text = ("[%s] %s"):format(args.target, diagnostic.message),
+      user_data = args.target,
+    },
+  }
+
+  for _, note in ipairs(diagnostic.notes or {}) do
+    table.insert(items, { text = note, type = "N", user_data = args.target })
+  end
+
+  vim.fn.setqflist({}, "a", { title = "XBase", items = items })
+end
+
+local function test_result(args)
+  local level = args.failed > 0 and "Error" or "Info"
+  local line = ("[%s] %d passed, %d failed (%.3fs)"):format(
+    args.suite,
+    args.passed,
+    args.failed,
+    args.duration
+  )
+  logger.log(line, level)
+end
+
+local function build_timing_summary(args)
+  logger.log(("[%s] Build Timing Summary"):format(args.target), "Info")
+  for _, phase in ipairs(args.phases) do
+    local line = ("[%s] %s (%d tasks) %.3fs"):format(args.target, phase.phase, phase.tasks, phase.duration)
+    logger.log(line, "Info")
+  end
+end
+
+local function coverage(args)
+  local format = function(name, cov)
+    return ("[%s] %s %.2f%% (%d/%d lines)"):format(args.target, name, cov.percent, cov.lines_covered, cov.lines_total)
+  end
+  logger.log(format("Coverage", args), "Info")
+  for _, file in ipairs(args.files or {}) do
+    logger.log(format(file.path, file), "Info")
+  end
+end
+
 local function task_finish(args)
   M.has_task = false
   vim.g.xbase_ctask = vim.tbl_extend("force", vim.g.xbase_ctask, args)
@@ -88,10 +198,49 @@ local function task_finish(args)
   end)
 end
 
+--- Get socket read callback calling cb with each complete line, as messages may span chunks
+local function line_reader(cb)
+  local buffer = ""
+  return function(chunk)
+    buffer = buffer .. chunk
+    local lines = vim.split(buffer, "\n", { plain = true })
+    -- Last line is incomplete, or empty if chunk ended with a newline
+    buffer = table.remove(lines)
+    for _, line in ipairs(lines) do
+      if #line > 0 then
+        cb(line)
+      end
+    end
+  end
+end
+
+---Tail messages of several project broadcasts at once, e.g. from `server.list_broadcasts`
+---@param broadcasts table[] broadcasts with root and address
+---@param cb fun(root: string, message: table) called with each message and its project root
+---@return XBaseSocket[] sockets to close to stop tailing
+function M.tail(broadcasts, cb)
+  local id = CLIENT_ID + TAIL_ID_OFFSET
+
+  return vim.tbl_map(function(broadcast)
+    local root = broadcast.root
+    local socket = socket:connect(broadcast.address)
+    socket._socket:write(string.format("%s\n", id))
+    socket:read_start(line_reader(function(line)
+      local ok, item = pcall(vim.json.decode, line)
+      if ok and not msg.is_ping(item.type) then
+        cb(root, item)
+      end
+    end))
+    return socket
+  end, broadcasts)
+end
+
 function M.start(root, address)
   local socket = socket:connect(address)
+  M.last_seen[root] = vim.loop.now()
+  start_liveness_check(root)
 
-  socket._socket:write(string.format("%s\n", vim.loop.os_getpid()), function(err)
+  socket._socket:write(string.format("%s\n", CLIENT_ID), function(err)
     if err then
       print(socket._stream_error or err)
     end
@@ -111,6 +260,11 @@ function M.start(root, address)
       end
 
       local type, args = item.type, item.args
+      M.last_seen[root] = vim.loop.now()
+
+      if msg.is_ping(type) then
+        return
+      end
 
       vim.schedule(function()
         if msg.task_is_update_current(type) then
@@ -125,6 +279,26 @@ function M.start(root, address)
           return task_finish(args)
         end
 
+        if msg.is_progress(type) then
+          return task_progress(args)
+        end
+
+        if msg.is_diagnostic(type) then
+          return add_diagnostic(args)
+        end
+
+        if msg.is_test_result(type) then
+          return test_result(args)
+        end
+
+        if msg.is_build_timing_summary(type) then
+          return build_timing_summary(args)
+        end
+
+        if msg.is_coverage(type) then
+          return coverage(args)
+        end
+
         if msg.is_notify(type) then
           notify(args.content, args.level)
           if string.find(args.content, "Registered") ~= nil then
@@ -149,12 +323,22 @@ function M.start(root, address)
           return logger.log(args.content, args.level)
         end
 
+        if msg.is_run_session_started(type) or msg.is_run_session_ended(type) then
+          local sessions = require("xbase.state").run_sessions
+          local key = ("%s:%s"):format(args.target, args.device or "Bin")
+          sessions[root] = sessions[root] or {}
+          sessions[root][key] = msg.is_run_session_started(type) and args or nil
+          return
+        end
+
         if msg.is_set_state(type) then
           local key, value = args.key, args.value
           if key == "runners" then
             require("xbase.state").runners = value
           elseif key == "projectInfo" then
             require("xbase.state").project_info[root] = value
+          elseif key == "watchPaused" then
+            require("xbase.state").watch_paused[root] = value
           end
           return
         end
@@ -36,6 +36,10 @@ local defaults = {
     watchOS = {}, -- all available devices
     tvOS = {}, -- all available devices
   },
+  --- Whether to bring Simulator.app to front after running on a simulator
+  focus_simulator = false,
+  --- Times to relaunch a running app if it crashes, e.g. on flaky simulator startups (max 5)
+  relaunch_on_crash = 0,
   --- Log buffer configurations
   log_buffer = {
     --- Whether toggling the buffer should auto focus to it?
@@ -1,6 +1,7 @@
 local M = {}
 local server = require "xbase.server"
 local state = require "xbase.state"
+local config = require("xbase.config").values
 
 ---@alias XBaseCommand
 ---| '"Run"'
@@ -34,6 +35,8 @@ end)()
 ---@field settings XBaseSettings project build settings
 ---@field device XBaseDeviceLookup | nil device to run with
 ---@field operation string operation to run "Watch" | "Stop" | "Once"
+---@field env table<string, string> | nil environment variables to run with
+---@field args string[] | nil arguments to run with
 
 ---Run command in server
 ---@param entry XBaseSelectEntry
@@ -45,6 +48,11 @@ M.run_command = function(entry)
       settings = entry.settings,
       operation = entry.operation,
       device = entry.device,
+      env = entry.env,
+      args = entry.args,
+      id = vim.loop.os_getpid(),
+      focus_simulator = config.focus_simulator,
+      relaunch_on_crash = config.relaunch_on_crash,
     },
   }
 end
@@ -56,12 +64,13 @@ end
 ---@param target string the name of the target
 ---@param configuration string the name configuration
 ---@param device XBaseDeviceLookup? the device to include
+---@param kind string? "Target" or "Scheme"
 ---@return XBaseSelectEntry
-local create_entry = function(root, picker, command, target, configuration, device)
+local create_entry = function(root, picker, command, target, configuration, device, kind)
   local item = {
     root = root,
     command = command,
-    settings = { target = target, configuration = configuration },
+    settings = { target = target, configuration = configuration, scheme = kind == "Scheme" and target or nil },
     operation = "Once",
     device = command == M.Command.Run and device or nil,
   }
@@ -83,13 +92,13 @@ end
 ---@param commands XBaseCommand[]
 ---@param targets table
 ---@param include_devices boolean whether to include devices
----@param exec fun(command: string, target: string, configuration: string, device: XBaseDeviceLookup|nil)
+---@param exec fun(command: string, target: string, configuration: string, device: XBaseDeviceLookup|nil, kind: string|nil)
 local iterate = function(commands, targets, include_devices, exec)
   for _, command in ipairs(commands) do
     for target, info in pairs(targets) do
       local devices = (command == Run and include_devices) and state.devices(info.platform) or nil
       for _, configuration in ipairs(info.configurations) do
-        exec(command, target, configuration, devices)
+        exec(command, target, configuration, devices, info.kind)
       end
     end
   end
@@ -109,15 +118,15 @@ M.generate_entries = function(root, picker_name)
     error "No targets found"
   end
 
-  iterate(commands, project_info.targets, include_devices, function(command, target, configuration, devices)
+  iterate(commands, project_info.targets, include_devices, function(command, target, configuration, devices, kind)
     if devices then
       local entries = vim.tbl_map(function(device)
-        return create_entry(root, picker_name, command, target, configuration, device)
+        return create_entry(root, picker_name, command, target, configuration, device, kind)
       end, devices)
 
       vim.list_extend(results, entries)
     else
-      table.insert(results, create_entry(root, picker_name, command, target, configuration))
+      table.insert(results, create_entry(root, picker_name, command, target, configuration, nil, kind))
     end
   end)
 
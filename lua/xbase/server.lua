@@ -53,7 +53,11 @@ function M.request(req, on_response)
       vim.schedule(function()
         local res = vim.json.decode(chunk)
         if res.error then
-          notify.error(string.format("%s %s", res.error.kind, res.error.msg))
+          if res.error.code == "generator_missing" then
+            notify.error(string.format("%s, install it (e.g. with homebrew) and try again", res.error.msg))
+          else
+            notify.error(string.format("%s %s", res.error.kind, res.error.msg))
+          end
           return
         else
           if on_response then
@@ -67,22 +71,45 @@ function M.request(req, on_response)
   end)
 end
 
----Check whether the vim instance should be registered to xbase server.
----@param root string: current working directory
+---Check whether a directory is a project root
+---@param root string
 ---@return boolean
-function M.should_register(root)
+local function is_project_root(root)
   if uv.fs_stat(root .. "/project.yml") then
     return true
   elseif uv.fs_stat(root .. "/Project.swift") then
     return true
   elseif uv.fs_stat(root .. "/Package.swift") then
     return true
+  elseif uv.fs_stat(root .. "/Podfile") then
+    return true
+  elseif uv.fs_stat(root .. "/WORKSPACE") or uv.fs_stat(root .. "/WORKSPACE.bazel") or uv.fs_stat(root .. "/MODULE.bazel") then
+    return true
   elseif vim.fn.glob(root .. "/*.xcodeproj"):len() ~= 0 then
     return true
   end
   return false
 end
 
+---Check whether the vim instance should be registered to xbase server.
+---The server resolves roots within a project subdirectory to the project root.
+---@param root string: current working directory
+---@return boolean
+function M.should_register(root)
+  local home = uv.os_homedir()
+  local dir = root
+  while true do
+    if is_project_root(dir) then
+      return true
+    end
+    local parent = vim.fn.fnamemodify(dir, ":h")
+    if dir == home or parent == dir then
+      return false
+    end
+    dir = parent
+  end
+end
+
 ---Register given root and return true if the root is registered
 ---@param root string
 ---@return boolean
@@ -95,12 +122,191 @@ function M.register(root)
   require("xbase.logger").setup()
 
   local req = { method = "register", args = { id = id, root = root } }
-  M.request(req, function(broadcast_address)
-    broadcast.start(root, broadcast_address)
+  M.request(req, function(state)
+    broadcast.start(root, state.address)
+    require("xbase.state").watch_paused[root] = state.paused
     M.roots[root] = true
   end)
 end
 
+---Clean build products of a given target, deep clean also removes build cache and compile database
+---@param root string
+---@param settings XBaseSettings
+---@param deep boolean|nil
+function M.clean(root, settings, deep)
+  M.request { method = "clean", args = { root = root, settings = settings, deep = deep or false } }
+end
+
+---Build multiple targets one at a time with the same settings, or all targets if targets is nil
+---@param root string
+---@param settings XBaseSettings
+---@param targets string[]|nil
+function M.build_targets(root, settings, targets)
+  M.request {
+    method = "build",
+    args = { root = root, settings = settings, operation = "Once", targets = targets or {}, all = targets == nil },
+  }
+end
+
+---Build a given target, reporting time spent in each build phase once finished
+---@param root string
+---@param settings XBaseSettings
+function M.build_timing_summary(root, settings)
+  M.request {
+    method = "build",
+    args = { root = root, settings = settings, operation = "Once", timing_summary = true },
+  }
+end
+
+---Log the command a build of a given target would run, without running it
+---@param root string
+---@param settings XBaseSettings
+---@param device table|nil device to build for
+function M.build_dry_run(root, settings, device)
+  M.request {
+    method = "build",
+    args = { root = root, settings = settings, operation = "Once", dry_run = true, device = device },
+  }
+end
+
+---Build a given target on every file change, launching it after each successful build
+---@param root string
+---@param settings XBaseSettings
+---@param device table|nil device to build for and run on
+function M.watch_and_run(root, settings, device)
+  M.request {
+    method = "build",
+    args = { root = root, settings = settings, operation = "Watch", device = device, run = true },
+  }
+end
+
+---Run tests of a target/scheme, optionally only the given tests
+---@param root string
+---@param settings XBaseSettings
+---@param device table|nil
+---@param only_testing string[]|nil test identifiers, e.g. "AppTests/AppTests/testFoo"
+function M.test(root, settings, device, only_testing)
+  M.request {
+    method = "test",
+    args = { root = root, settings = settings, device = device, only_testing = only_testing or {} },
+  }
+end
+
+---Run tests of a target/scheme reporting line coverage of tested targets, slower than M.test
+---@param root string
+---@param settings XBaseSettings
+---@param device table|nil
+---@param files boolean|nil whether to report coverage of each source file
+function M.test_coverage(root, settings, device, files)
+  M.request {
+    method = "test",
+    args = { root = root, settings = settings, device = device, coverage = true, coverage_files = files or false },
+  }
+end
+
+---Get simulators and connected devices compatible with project targets
+---@param root string
+---@param cb fun(devices: XBaseDeviceInfo[])
+---@param target string|nil only list devices that can run a given target
+function M.get_devices(root, cb, target)
+  M.request({ method = "get_devices", args = { root = root, target = target } }, cb)
+end
+
+---Get project targets, schemes and configurations
+---@param root string
+---@param cb fun(info: XBaseProjectDetails)
+function M.get_project_info(root, cb)
+  M.request({ method = "get_project_info", args = { root = root } }, cb)
+end
+
+---Regenerate project, regardless of file changes
+---@param root string
+function M.generate(root)
+  M.request { method = "generate", args = { root = root } }
+end
+
+---Regenerate compile commands without building
+---@param root string
+function M.generate_compile_commands(root)
+  M.request { method = "generate_compile_commands", args = { root = root } }
+end
+
+---Get daemon uptime and registered projects clients, watchers, run sessions and sockets health
+---@param cb fun(status: table)
+function M.status(cb)
+  M.request({ method = "status", args = vim.empty_dict() }, cb)
+end
+
+---Get compile command of a source file, e.g. to debug indexing
+---@param root string
+---@param file string path of the source file
+---@param cb fun(command: table)
+function M.get_compile_command(root, file, cb)
+  M.request({ method = "get_compile_command", args = { root = root, file = file } }, cb)
+end
+
+---Get build cache and compile database paths and sizes, e.g. to see what a deep clean would remove
+---@param root string
+---@param cb fun(info: table)
+function M.get_cache_info(root, cb)
+  M.request({ method = "get_cache_info", args = { root = root } }, cb)
+end
+
+---Move a registered project to a new root, e.g. after renaming its directory
+---@param old_root string
+---@param new_root string
+---@param cb fun(state: table)
+function M.relocate(old_root, new_root, cb)
+  M.request({ method = "relocate", args = { old_root = old_root, new_root = new_root } }, cb)
+end
+
+---List broadcast sockets of all registered projects, e.g. to tail them with `broadcast.tail`
+---@param cb fun(broadcasts: table[])
+function M.list_broadcasts(cb)
+  M.request({ method = "list_broadcasts", args = vim.empty_dict() }, cb)
+end
+
+---Pause acting on file changes of a given root, e.g. during a rebase
+---@param root string
+function M.pause(root)
+  M.request { method = "pause", args = { root = root } }
+end
+
+---Resume acting on file changes of a given root
+---@param root string
+---@param catch_up boolean|nil whether to process the last change made while paused
+function M.resume(root, catch_up)
+  M.request { method = "resume", args = { root = root, catch_up = catch_up or false } }
+end
+
+---Start or stop watching a given target, without building other targets
+---@param root string
+---@param target string
+---@param enable boolean
+function M.watch_target(root, target, enable)
+  M.request { method = "watch_target", args = { root = root, target = target, enable = enable } }
+end
+
+---Write recent messages of a given root, e.g. a failed build log, to a plain text file
+---@param root string
+---@param path string file path, relative to root unless absolute
+function M.export_log(root, path)
+  M.request { method = "export_log", args = { root = root, path = path } }
+end
+
+---Stop a watched build or a running app with a given key
+---@param root string
+---@param key string build or run request key, e.g. a key in project_info.watchlist
+function M.stop(root, key)
+  M.request { method = "stop", args = { root = root, key = key } }
+end
+
+---Rewrite buildServer.json with current build server path and project languages
+---@param root string
+function M.regenerate_build_server(root)
+  M.request { method = "regenerate_build_server", args = { root = root } }
+end
+
 ---Drop a given root or drop all tracked roots if root is nil
 ---@param root string
 function M.drop(root)
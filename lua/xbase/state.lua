@@ -1,6 +1,11 @@
+---@class XBaseRunSessionKey
+---@field target string The name of the target
+---@field device string|nil The udid of the device, nil when running a binary
+
 ---@class XBaseDeviceLookup
 ---@field id string The id of the device
 ---@field name string The name of the device
+---@field physical boolean Whether the device is a connected physical device
 
 local M = {
   --- Devices index by platform
@@ -8,6 +13,12 @@ local M = {
   runners = nil,
   ---@type table<string, table>
   project_info = {},
+  --- Whether acting on file changes is paused, index by root
+  ---@type table<string, boolean>
+  watch_paused = {},
+  --- Active run sessions index by root and then "target:device"
+  ---@type table<string, table<string, XBaseRunSessionKey>>
+  run_sessions = {},
 }
 
 local assert_devices = function(available_devices, devices, device_filter)
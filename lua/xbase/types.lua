@@ -3,7 +3,33 @@ local M = {}
 ---@class XBaseSettings
 ---@field target string target to build
 ---@field configuration string | nil configuration to build with (default debug)
----@field schema string | nil schema to build with
+---@field scheme string | nil scheme to build with
+---@field sdk string | nil sdk to build with, e.g. "iphoneos" (default derived from target platform)
+---@field destination string | nil destination to build for, e.g. "platform=iOS Simulator,name=iPhone 14"
+---@field xcconfig string | nil xcconfig file to override build settings with, relative to project root
+---@field overrides string[][] | nil build settings to override, e.g. { { "ONLY_ACTIVE_ARCH", "YES" } }
+
+---@class XBaseDeviceInfo
+---@field udid string device udid
+---@field name string device name
+---@field runtime string simulator runtime identifier, empty for physical devices
+---@field state string device state, e.g. "Booted", "Shutdown" or "Connected"
+---@field platform string device platform
+---@field physical boolean whether the device is a physical device
+
+---@class XBaseTargetInfo
+---@field platform string target platform
+---@field configurations string[] target build configurations
+---@field kind string "Target" or "Scheme"
+---@field watching boolean whether the target is built or ran on file changes
+
+---@class XBaseProjectDetails
+---@field name string project name
+---@field kind string "Xcodeproj", "XcodeGen", "Tuist", "Swift" or "Bazel"
+---@field targets table<string, XBaseTargetInfo> targets and workspace schemes
+---@field schemes string[] workspace schemes
+---@field configurations string[] build configurations available across targets
+---@field watchlist string[] watched build and run requests keys
 
 M.TaskKind = {
   is_build = function(kind)
@@ -18,6 +44,18 @@ M.TaskKind = {
   is_run = function(kind)
     return kind == "Run"
   end,
+  is_clean = function(kind)
+    return kind == "Clean"
+  end,
+  is_install = function(kind)
+    return kind == "Install"
+  end,
+  is_test = function(kind)
+    return kind == "Test"
+  end,
+  is_hook = function(kind)
+    return kind == "Hook"
+  end,
   prefix = function(self, kind)
     if self.is_compile(kind) then
       return "Compiling", "Compiled"
@@ -27,6 +65,14 @@ M.TaskKind = {
       return "Building", "Built"
     elseif self.is_run(kind) then
       return "Running", "Running"
+    elseif self.is_clean(kind) then
+      return "Cleaning", "Cleaned"
+    elseif self.is_install(kind) then
+      return "Installing", "Installed"
+    elseif self.is_test(kind) then
+      return "Testing", "Tested"
+    elseif self.is_hook(kind) then
+      return "Preparing", "Prepared"
     end
   end,
 }
@@ -65,12 +111,42 @@ M.Message = {
   task_is_finish_current = function(ty)
     return ty == "FinishCurrentTask"
   end,
+  task_is_started = function(ty)
+    return ty == "TaskStarted"
+  end,
+  task_is_finished = function(ty)
+    return ty == "TaskFinished"
+  end,
   is_set_watching = function(ty)
     return ty == "SetWatching"
   end,
   is_set_state = function(ty)
     return ty == "SetState"
   end,
+  is_progress = function(ty)
+    return ty == "Progress" or ty == "IndeterminateProgress"
+  end,
+  is_diagnostic = function(ty)
+    return ty == "Diagnostic"
+  end,
+  is_test_result = function(ty)
+    return ty == "TestResult"
+  end,
+  is_build_timing_summary = function(ty)
+    return ty == "BuildTimingSummary"
+  end,
+  is_coverage = function(ty)
+    return ty == "Coverage"
+  end,
+  is_run_session_started = function(ty)
+    return ty == "RunSessionStarted"
+  end,
+  is_run_session_ended = function(ty)
+    return ty == "RunSessionEnded"
+  end,
+  is_ping = function(ty)
+    return ty == "Ping"
+  end,
 }
 
 return M
@@ -30,18 +30,56 @@ fn gen_ts_types_file(path: PathBuf) {
     options.root_namespace = None;
     options.header = None;
 
-    type Requests = (Request, RunRequest, RegisterRequest, DropRequest);
-    type Responses = (Response, ServerError);
+    type Requests = (
+        Request,
+        RunRequest,
+        RegisterRequest,
+        DropRequest,
+        CleanRequest,
+        GetDevicesRequest,
+        StopRequest,
+        TestRequest,
+        RegenerateBuildServerRequest,
+        GetProjectInfoRequest,
+        GenerateRequest,
+        GenerateCompileCommandsRequest,
+        StatusRequest,
+        PauseRequest,
+        ResumeRequest,
+        WatchTargetRequest,
+        ExportLogRequest,
+        ListBroadcastsRequest,
+        GetCompileCommandRequest,
+        GetCacheInfoRequest,
+        RelocateRequest,
+    );
+    type Responses = (Response, ServerError, ErrorCode);
     type Transports = (
         ProjectInfo,
         TargetInfo,
+        BuildableKind,
+        ProjectKind,
+        ProjectDetails,
+        RegisterResponse,
+        StatusResponse,
+        BroadcastInfo,
+        CacheInfo,
+        CachePath,
         Runners,
         Operation,
         BuildSettings,
         DeviceLookup,
+        DeviceInfo,
         State,
     );
-    type Messages = (Message, ContentLevel, TaskKind, TaskStatus);
+    type Messages = (
+        Message,
+        ContentLevel,
+        TaskKind,
+        TaskStatus,
+        RunSessionKey,
+        Framing,
+    );
     type API = (Messages, Transports, Responses, Requests);
 
     write_definition_file::<_, API>(&mut buf, options).unwrap();
@@ -0,0 +1,126 @@
+use super::{ContentLevel, Framing, Message};
+use futures::Stream;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::sync::watch;
+
+/// Connection state of a [`BroadcastClient`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Connecting for the first time
+    Connecting,
+    /// Connected and receiving messages
+    Connected,
+    /// Connection dropped, retrying after a delay
+    Reconnecting { attempt: u32, delay: Duration },
+}
+
+/// Client of a project broadcast socket, reconnecting with backoff when the socket drops
+/// (e.g. while the daemon restarts)
+#[derive(Debug)]
+pub struct BroadcastClient {
+    address: PathBuf,
+    id: u32,
+    min_level: Option<ContentLevel>,
+    min_delay: Duration,
+    max_delay: Duration,
+    state: watch::Sender<ConnectionState>,
+}
+
+impl BroadcastClient {
+    /// Create client for a broadcast socket address, identifying as client with given id
+    pub fn new<P: Into<PathBuf>>(address: P, id: u32) -> Self {
+        let (state, _) = watch::channel(ConnectionState::Connecting);
+        Self {
+            address: address.into(),
+            id,
+            min_level: None,
+            min_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            state,
+        }
+    }
+
+    /// Only receive messages at or above given level
+    pub fn min_level(mut self, level: ContentLevel) -> Self {
+        self.min_level = Some(level);
+        self
+    }
+
+    /// Set minimum and maximum delay between reconnect attempts
+    pub fn backoff(mut self, min_delay: Duration, max_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self.max_delay = max_delay.max(min_delay);
+        self
+    }
+
+    /// Watch connection state changes, e.g. to show "reconnecting…"
+    pub fn state(&self) -> watch::Receiver<ConnectionState> {
+        self.state.subscribe()
+    }
+
+    /// Stream of broadcast messages, never ending as long as the stream is polled
+    pub fn messages(self) -> impl Stream<Item = Message> {
+        async_stream::stream! {
+            let mut attempt = 0;
+            loop {
+                if let Ok(mut stream) = self.connect().await {
+                    attempt = 0;
+                    self.state.send_replace(ConnectionState::Connected);
+
+                    let mut buffer = vec![];
+                    let mut chunk = vec![0; 8 * 1024];
+                    while let Ok(read) = stream.read(&mut chunk).await {
+                        if read == 0 {
+                            break;
+                        }
+                        buffer.extend_from_slice(&chunk[..read]);
+                        while let Some(payload) = Framing::Newline.decode(&mut buffer) {
+                            let line = String::from_utf8_lossy(&payload);
+                            if line.trim().is_empty() {
+                                continue;
+                            }
+                            match serde_json::from_str::<Message>(&line) {
+                                Ok(message) => yield message,
+                                Err(err) => tracing::debug!("Skipping message: {err}: {line}"),
+                            }
+                        }
+                    }
+                }
+
+                attempt += 1;
+                let delay = self.delay(attempt);
+                tracing::debug!("Reconnecting to {:?} in {delay:?}", self.address);
+                self.state.send_replace(ConnectionState::Reconnecting { attempt, delay });
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+
+    /// Connect to socket and send handshake
+    async fn connect(&self) -> std::io::Result<UnixStream> {
+        let mut stream = UnixStream::connect(&self.address).await?;
+        let handshake = match self.min_level {
+            Some(ref level) => {
+                let level = format!("{level:?}").to_lowercase();
+                serde_json::json!({ "id": self.id, "min_level": level }).to_string()
+            }
+            None => self.id.to_string(),
+        };
+        stream
+            .write_all(format!("{handshake}\n").as_bytes())
+            .await?;
+        Ok(stream)
+    }
+
+    /// Delay before a given reconnect attempt, doubling up to max delay
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.min_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+}
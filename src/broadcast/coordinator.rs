@@ -0,0 +1,127 @@
+use super::TaskOutcome;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc::{channel, Receiver};
+use tokio::sync::{watch, Mutex};
+use xclog::XCCompileCommand;
+
+/// Compile commands logged by a build
+pub type LoggedCommands = Arc<Mutex<Vec<XCCompileCommand>>>;
+
+/// Coordinates builds with compile database updates triggered by the same file change.
+///
+/// Builds log compile commands of the sources they recompile, so a build in flight supersedes
+/// running another xcodebuild against the same DerivedData only to update compile commands.
+/// Instead, compile commands are taken from the builds: [`BuildCoordinator::wait`] resolves only
+/// once every tracked build of the given targets has finished, so the compile database is never
+/// updated while one of them is still running.
+#[derive(Debug, Default)]
+pub struct BuildCoordinator {
+    /// Last started build of each target
+    builds: std::sync::Mutex<HashMap<String, TrackedBuild>>,
+}
+
+#[derive(Debug)]
+struct TrackedBuild {
+    commands: LoggedCommands,
+    /// Whether the build succeeded, None while in flight
+    success: watch::Receiver<Option<bool>>,
+}
+
+impl BuildCoordinator {
+    /// Track build of a target logging compile commands to `commands`, forwarding its outcome
+    pub fn track(
+        &self,
+        target: &str,
+        commands: LoggedCommands,
+        mut outcome: Receiver<TaskOutcome>,
+    ) -> Receiver<TaskOutcome> {
+        let (send_success, success) = watch::channel(None);
+        let (send_outcome, recv_outcome) = channel(1);
+        let build = TrackedBuild { commands, success };
+
+        self.builds
+            .lock()
+            .unwrap()
+            .insert(target.to_string(), build);
+
+        tokio::spawn(async move {
+            let outcome = outcome.recv().await;
+            let success = outcome.as_ref().map_or(false, |outcome| outcome.success);
+            send_success.send(Some(success)).ok();
+            if let Some(outcome) = outcome {
+                send_outcome.send(outcome).await.ok();
+            }
+        });
+
+        recv_outcome
+    }
+
+    /// Wait for tracked builds of the given targets to finish, taking compile commands logged by
+    /// the ones that succeeded.
+    ///
+    /// Returns None if none of the targets has a tracked build, e.g. Swift packages.
+    pub async fn wait(&self, targets: &[String]) -> Option<Vec<Value>> {
+        let builds = {
+            let mut builds = self.builds.lock().unwrap();
+            targets
+                .iter()
+                .flat_map(|target| builds.remove(target))
+                .collect::<Vec<_>>()
+        };
+
+        if builds.is_empty() {
+            return None;
+        }
+
+        let mut commands = vec![];
+        for mut build in builds {
+            let success = loop {
+                let current = *build.success.borrow();
+                if let Some(success) = current {
+                    break success;
+                }
+                if build.success.changed().await.is_err() {
+                    break false;
+                }
+            };
+
+            if success {
+                let logged = build.commands.lock().await;
+                commands.extend(logged.iter().flat_map(serde_json::to_value));
+            }
+        }
+
+        Some(commands)
+    }
+}
+
+#[test]
+fn test_wait_for_tracked_builds() {
+    use std::time::Duration;
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let coordinator = BuildCoordinator::default();
+        let targets = vec!["App".to_string(), "Widget".to_string()];
+
+        // Both a build and a run watcher of the same change start builds
+        let (send_app, recv_app) = channel(1);
+        let (send_widget, recv_widget) = channel(1);
+        let _app = coordinator.track("App", Default::default(), recv_app);
+        let _widget = coordinator.track("Widget", Default::default(), recv_widget);
+
+        send_app.send(TaskOutcome::default()).await.unwrap();
+        let wait = coordinator.wait(&targets);
+        tokio::pin!(wait);
+        let pending = tokio::time::timeout(Duration::from_millis(50), &mut wait).await;
+        assert!(pending.is_err(), "resolved before all builds finished");
+
+        send_widget.send(TaskOutcome::default()).await.unwrap();
+        assert_eq!(wait.await, Some(vec![]));
+
+        // Builds are taken by the first wait
+        assert_eq!(coordinator.wait(&targets).await, None);
+    });
+}
@@ -0,0 +1,154 @@
+use super::Message;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use typescript_type_def::TypeDef;
+
+/// Line coverage of a source file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeDef)]
+pub struct FileCoverage {
+    pub path: String,
+    pub lines_covered: u32,
+    pub lines_total: u32,
+    /// Covered lines percentage
+    pub percent: f64,
+}
+
+/// `xcrun xccov view --report --json` output, only the parts used
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    targets: Vec<ReportTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportTarget {
+    /// Product name, e.g. `App.app`
+    name: String,
+    covered_lines: u32,
+    executable_lines: u32,
+    #[serde(default)]
+    files: Vec<ReportFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportFile {
+    path: String,
+    covered_lines: u32,
+    executable_lines: u32,
+}
+
+fn percent(covered: u32, total: u32) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (covered as f64 * 10000.0 / total as f64).round() / 100.0
+}
+
+/// Convert xccov json report to coverage messages, one per covered target.
+///
+/// Test bundles are skipped, files coverage is only included if `files` is true.
+pub fn parse_coverage_report(json: &str, files: bool) -> Result<Vec<Message>> {
+    let report: Report =
+        serde_json::from_str(json).map_err(|e| Error::Unexpected(format!("xccov: {e}")))?;
+
+    let messages = report
+        .targets
+        .into_iter()
+        .filter(|target| !target.name.ends_with(".xctest") && target.executable_lines > 0)
+        .map(|target| Message::Coverage {
+            target: match target.name.rsplit_once('.') {
+                Some((name, _)) => name.to_string(),
+                None => target.name.clone(),
+            },
+            lines_covered: target.covered_lines,
+            lines_total: target.executable_lines,
+            percent: percent(target.covered_lines, target.executable_lines),
+            files: target
+                .files
+                .into_iter()
+                .filter(|_| files)
+                .map(|file| FileCoverage {
+                    percent: percent(file.covered_lines, file.executable_lines),
+                    path: file.path,
+                    lines_covered: file.covered_lines,
+                    lines_total: file.executable_lines,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(messages)
+}
+
+/// Read coverage of a test run result bundle
+pub async fn read_coverage_report(result_bundle: &Path, files: bool) -> Result<Vec<Message>> {
+    let output = tokio::process::Command::new("xcrun")
+        .envs(crate::xcodebuild::envs())
+        .args(["xccov", "view", "--report", "--json"])
+        .arg(result_bundle)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Unexpected(format!(
+            "xccov failed: {}",
+            stderr.trim()
+        )));
+    }
+
+    parse_coverage_report(&String::from_utf8_lossy(&output.stdout), files)
+}
+
+#[test]
+fn test_parse_coverage_report() {
+    let json = r#"{
+      "coveredLines": 30, "executableLines": 60, "lineCoverage": 0.5,
+      "targets": [
+        {
+          "name": "App.app", "coveredLines": 20, "executableLines": 30, "lineCoverage": 0.666,
+          "files": [
+            { "name": "A.swift", "path": "/tmp/App/A.swift", "coveredLines": 20,
+              "executableLines": 20, "lineCoverage": 1.0, "functions": [] },
+            { "name": "B.swift", "path": "/tmp/App/B.swift", "coveredLines": 0,
+              "executableLines": 10, "lineCoverage": 0.0, "functions": [] }
+          ]
+        },
+        { "name": "AppTests.xctest", "coveredLines": 10, "executableLines": 30, "files": [] }
+      ]
+    }"#;
+
+    let messages = parse_coverage_report(json, false).unwrap();
+    assert_eq!(messages.len(), 1);
+    match &messages[0] {
+        Message::Coverage {
+            target,
+            lines_covered,
+            lines_total,
+            percent,
+            files,
+        } => {
+            assert_eq!(target, "App");
+            assert_eq!((*lines_covered, *lines_total, *percent), (20, 30, 66.67));
+            assert!(files.is_empty());
+        }
+        message => panic!("Unexpected {message:?}"),
+    }
+
+    let messages = parse_coverage_report(json, true).unwrap();
+    match &messages[0] {
+        Message::Coverage { files, .. } => assert_eq!(
+            files[1],
+            FileCoverage {
+                path: "/tmp/App/B.swift".into(),
+                lines_covered: 0,
+                lines_total: 10,
+                percent: 0.0,
+            }
+        ),
+        message => panic!("Unexpected {message:?}"),
+    }
+}
@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use typescript_type_def::TypeDef;
+
+/// Marker xcodebuild prints when a build fails, sometimes while still exiting with 0
+pub const BUILD_FAILED_MARKER: &str = "** BUILD FAILED **";
+
+/// Severity of a diagnostic reported in build output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TypeDef)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Default for Severity {
+    fn default() -> Self {
+        Self::Error
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// Error, warning or note reported by xcodebuild/swiftc/clang in build output
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, TypeDef)]
+pub struct BuildDiagnostic {
+    /// File the diagnostic is reported for
+    pub file: Option<String>,
+    /// Line number in file
+    pub line: Option<u32>,
+    /// Column number in line
+    pub column: Option<u32>,
+    /// Diagnostic message
+    pub message: String,
+    /// Diagnostic severity
+    #[serde(default)]
+    pub severity: Severity,
+    /// Notes attached to the diagnostic, formatted as `file:line:column: note: message`
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl BuildDiagnostic {
+    /// Whether the diagnostic is about code signing (e.g. missing team or provisioning profile)
+    pub fn is_code_signing(&self) -> bool {
+        let message = self.message.to_lowercase();
+        [
+            "code sign",
+            "signing for",
+            "provisioning profile",
+            "development team",
+        ]
+        .iter()
+        .any(|marker| message.contains(marker))
+    }
+
+    /// Try to parse an error line, either `file:line:column: error: message` or `error: message`
+    pub fn parse(content: &str) -> Option<Self> {
+        Self::parse_with_severity(content, Severity::Error)
+    }
+
+    /// Try to parse an error, warning or note line
+    pub fn parse_any(content: &str) -> Option<Self> {
+        [Severity::Error, Severity::Warning, Severity::Note]
+            .into_iter()
+            .find_map(|severity| Self::parse_with_severity(content, severity))
+    }
+
+    fn parse_with_severity(content: &str, severity: Severity) -> Option<Self> {
+        let content = content.trim();
+        let (infix, prefix) = (format!(": {severity}: "), format!("{severity}: "));
+        let (location, message) = content
+            .split_once(&infix)
+            .or_else(|| content.strip_prefix(&prefix).map(|m| ("", m)))?;
+
+        let mut diagnostic = Self {
+            message: message.trim().to_string(),
+            severity,
+            ..Self::default()
+        };
+
+        if location.is_empty() {
+            return Some(diagnostic);
+        }
+
+        match location.rsplitn(3, ':').collect::<Vec<_>>().as_slice() {
+            [column, line, file] => match (line.parse(), column.parse()) {
+                (Ok(line), Ok(column)) => {
+                    diagnostic.file = Some(file.to_string());
+                    diagnostic.line = Some(line);
+                    diagnostic.column = Some(column);
+                }
+                _ => diagnostic.file = Some(location.to_string()),
+            },
+            [line, file] => match line.parse() {
+                Ok(line) => {
+                    diagnostic.file = Some(file.to_string());
+                    diagnostic.line = Some(line);
+                }
+                _ => diagnostic.file = Some(location.to_string()),
+            },
+            _ => diagnostic.file = Some(location.to_string()),
+        };
+
+        Some(diagnostic)
+    }
+}
+
+impl Display for BuildDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(ref file) = self.file {
+            write!(f, "{file}:")?;
+            if let Some(line) = self.line {
+                write!(f, "{line}:")?;
+            }
+            if let Some(column) = self.column {
+                write!(f, "{column}:")?;
+            }
+            write!(f, " ")?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Get content of a source excerpt line printed below diagnostics by Swift 5.10+ (e.g. SwiftPM
+/// builds), after its line number gutter, e.g. `let x = foo` of `3 | let x = foo`
+pub(crate) fn source_excerpt(line: &str) -> Option<&str> {
+    let (gutter, content) = line.split_once(" |")?;
+    gutter
+        .trim()
+        .chars()
+        .all(|c| c.is_ascii_digit())
+        .then(|| content)
+}
+
+/// Get note of a source excerpt annotation, e.g. `did you mean 'bar'?` of
+/// ``   `- note: did you mean 'bar'?``
+fn excerpt_note(content: &str) -> Option<&str> {
+    content
+        .trim_start()
+        .trim_start_matches(|c| c == '`' || c == '|')
+        .strip_prefix("- note: ")
+}
+
+/// Parser of diagnostics spanning multiple lines of build output, i.e. followed by notes
+#[derive(Debug, Default)]
+pub struct DiagnosticParser {
+    pending: Option<BuildDiagnostic>,
+    /// Number of errors fed so far
+    errors: u32,
+    /// Number of warnings fed so far
+    warnings: u32,
+}
+
+impl DiagnosticParser {
+    /// Feed a line of output, returns previous diagnostic once it's complete
+    pub fn feed(&mut self, line: &str) -> Option<BuildDiagnostic> {
+        // Annotations repeating the diagnostic itself are skipped, only notes are kept
+        if let Some(note) = source_excerpt(line).and_then(excerpt_note) {
+            if let Some(pending) = self.pending.as_mut() {
+                pending.notes.push(format!("note: {}", note.trim()));
+            }
+            return None;
+        }
+
+        let diagnostic = BuildDiagnostic::parse_any(line)?;
+
+        match diagnostic.severity {
+            Severity::Note => {
+                if let Some(pending) = self.pending.as_mut() {
+                    pending.notes.push(diagnostic.to_string());
+                }
+                return None;
+            }
+            Severity::Warning => self.warnings += 1,
+            Severity::Error => self.errors += 1,
+        }
+
+        self.pending.replace(diagnostic)
+    }
+
+    /// Number of errors fed so far
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Number of warnings fed so far
+    pub fn warnings(&self) -> u32 {
+        self.warnings
+    }
+
+    /// Get last diagnostic, once output has ended
+    pub fn finish(&mut self) -> Option<BuildDiagnostic> {
+        self.pending.take()
+    }
+}
+
+#[test]
+fn test_parse_build_diagnostic() {
+    let diagnostic = BuildDiagnostic::parse(
+        "/tmp/Demo/Sources/App.swift:12:5: error: cannot find 'foo' in scope",
+    );
+    assert_eq!(
+        diagnostic,
+        Some(BuildDiagnostic {
+            file: Some("/tmp/Demo/Sources/App.swift".into()),
+            line: Some(12),
+            column: Some(5),
+            message: "cannot find 'foo' in scope".into(),
+            ..Default::default()
+        })
+    );
+
+    let diagnostic = BuildDiagnostic::parse("error: unable to attach DB").unwrap();
+    assert_eq!(diagnostic.file, None);
+    assert_eq!(diagnostic.message, "unable to attach DB");
+
+    assert_eq!(BuildDiagnostic::parse("CompileSwift normal arm64"), None);
+}
+
+#[test]
+fn test_parse_diagnostics_with_notes() {
+    let output = [
+        "/tmp/Demo/Sources/App.swift:12:5: warning: variable 'x' was never used",
+        "    let x = 1",
+        "        ^",
+        "/tmp/Demo/main.m:4:10: error: use of undeclared identifier 'foo'",
+        "/tmp/Demo/Foo.h:2:6: note: did you mean 'bar'?",
+        "/tmp/Demo/Sources/App.swift:20:1: error: expected '}' in struct",
+    ];
+
+    let mut parser = DiagnosticParser::default();
+    let mut diagnostics = output
+        .iter()
+        .flat_map(|line| parser.feed(line))
+        .collect::<Vec<_>>();
+    diagnostics.extend(parser.finish());
+
+    assert_eq!(diagnostics.len(), 3);
+    assert_eq!(parser.warnings(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].line, Some(12));
+    assert_eq!(diagnostics[1].file.as_deref(), Some("/tmp/Demo/main.m"));
+    assert_eq!(diagnostics[1].column, Some(10));
+    assert_eq!(
+        diagnostics[1].notes,
+        vec!["/tmp/Demo/Foo.h:2:6: note: did you mean 'bar'?"]
+    );
+    assert_eq!(diagnostics[2].message, "expected '}' in struct");
+}
+
+#[test]
+fn test_parse_swiftpm_diagnostics() {
+    let output = [
+        "[3/5] Compiling Demo main.swift",
+        "/tmp/Demo/Sources/Demo/main.swift:3:9: error: cannot find 'foo' in scope",
+        "1 | import Foundation",
+        "2 | ",
+        "3 | let x = foo",
+        "  |         |- error: cannot find 'foo' in scope",
+        "  |         `- note: did you mean 'food'?",
+        "/tmp/Demo/Sources/Demo/main.swift:5:5: warning: initialization of 'y' was never used",
+        "[4/5] Compiling Demo Kit.swift",
+    ];
+
+    let mut parser = DiagnosticParser::default();
+    let mut diagnostics = output
+        .iter()
+        .flat_map(|line| parser.feed(line))
+        .collect::<Vec<_>>();
+    diagnostics.extend(parser.finish());
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!((parser.errors(), parser.warnings()), (1, 1));
+    assert_eq!(diagnostics[0].column, Some(9));
+    assert_eq!(diagnostics[0].notes, vec!["note: did you mean 'food'?"]);
+    assert_eq!(diagnostics[1].severity, Severity::Warning);
+    assert_eq!(source_excerpt("3 | let x = foo"), Some(" let x = foo"));
+    assert_eq!(source_excerpt("error: a | b"), None);
+}
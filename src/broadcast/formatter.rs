@@ -0,0 +1,230 @@
+use super::{diagnostic::source_excerpt, ContentLevel};
+
+/// Prefixes, lowercased, of warnings XcodeGen prints to stderr while still generating the project,
+/// e.g. about deprecated or unknown spec options
+const XCODEGEN_WARNING_PREFIXES: [&str; 4] =
+    ["warning", "⚠️", "spec validation warning", "unknown option"];
+
+/// Strategy used to format consumed process output before it is broadcasted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formatter {
+    /// Pass output as is
+    Raw,
+    /// Collapse xcodebuild build phases into concise summaries (xcbeautify-like)
+    Pretty,
+    /// Pass output as is, reporting known XcodeGen warnings printed to stderr as warnings
+    XcodeGen,
+    /// Pass output as is, reporting source excerpts SwiftPM prints below diagnostics as info
+    SwiftPM,
+}
+
+impl Formatter {
+    /// Whether only a non-zero exit code fails the process, rather than errors in its output too,
+    /// as xcodebuild may report errors while exiting with 0
+    pub fn trusts_exit_code(&self) -> bool {
+        *self == Self::XcodeGen
+    }
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::Raw
+    }
+}
+
+/// Formatting state of a single consumed process
+#[derive(Debug, Default)]
+pub struct FormatterState {
+    formatter: Formatter,
+    /// Whether the current line is part of a build phase command invocation
+    in_phase: bool,
+}
+
+impl FormatterState {
+    pub fn new(formatter: Formatter) -> Self {
+        Self {
+            formatter,
+            in_phase: false,
+        }
+    }
+
+    /// Get level to broadcast a line of stderr output with
+    pub fn stderr_level(&self, line: &str) -> ContentLevel {
+        let line = line.trim().to_lowercase();
+        let is_warning = || {
+            XCODEGEN_WARNING_PREFIXES
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+        };
+        match self.formatter {
+            Formatter::XcodeGen if is_warning() => ContentLevel::Warn,
+            _ => ContentLevel::Error,
+        }
+    }
+
+    /// Whether a line of output is a source excerpt printed below a diagnostic, rather than the
+    /// diagnostic itself, e.g. ``  |         `- error: cannot find 'foo' in scope``
+    pub fn is_source_excerpt(&self, line: &str) -> bool {
+        self.formatter == Formatter::SwiftPM && source_excerpt(line).is_some()
+    }
+
+    /// Format a line of output, returns `None` if the line should be skipped
+    pub fn format(&mut self, line: String) -> Option<String> {
+        if self.formatter != Formatter::Pretty {
+            return Some(line);
+        }
+
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            self.in_phase = false;
+            return None;
+        }
+
+        if trimmed.contains(": error:") || trimmed.contains(": warning:") {
+            self.in_phase = false;
+            return Some(trimmed.to_string());
+        }
+
+        if let Some(summary) = Self::summarize_phase(trimmed) {
+            self.in_phase = true;
+            return summary;
+        }
+
+        // Phase details (`cd ..`, `export ..`, compiler invocations) are indented
+        if self.in_phase && line.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        self.in_phase = false;
+
+        match trimmed {
+            "** BUILD SUCCEEDED **" => Some("Build Succeeded".into()),
+            "** BUILD FAILED **" => Some("Build Failed".into()),
+            "** CLEAN SUCCEEDED **" => Some("Clean Succeeded".into()),
+            _ => Some(trimmed.to_string()),
+        }
+    }
+
+    /// Summarize a build phase line, returns `Some(None)` for phases that should be hidden
+    fn summarize_phase(line: &str) -> Option<Option<String>> {
+        let (phase, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let target = Self::target_name(rest);
+        let file_name = |path: &str| {
+            path.rsplit('/')
+                .next()
+                .unwrap_or(path)
+                .trim_end_matches('\'')
+                .to_string()
+        };
+
+        let summary = match phase {
+            "CompileSwift" => {
+                let path = rest.split_whitespace().nth(2)?;
+                format!("Compiling {}", file_name(path))
+            }
+            "CompileC" => {
+                let path = rest.split_whitespace().nth(1)?;
+                format!("Compiling {}", file_name(path))
+            }
+            "Ld" => {
+                let path = rest.split_whitespace().next()?;
+                format!("Linking {}", file_name(path))
+            }
+            "CodeSign" => {
+                let path = rest.split_whitespace().next()?;
+                format!("Signing {}", file_name(path))
+            }
+            "ProcessInfoPlistFile" => {
+                let path = rest.split_whitespace().next()?;
+                format!("Processing {}", file_name(path))
+            }
+            "CompileSwiftSources"
+            | "SwiftDriver"
+            | "SwiftMergeGeneratedHeaders"
+            | "SwiftEmitModule"
+            | "EmitSwiftModule"
+            | "MergeSwiftModule"
+            | "CreateBuildDirectory"
+            | "WriteAuxiliaryFile"
+            | "MkDir"
+            | "Touch"
+            | "ProcessProductPackaging"
+            | "RegisterExecutionPolicyException"
+            | "Validate"
+            | "CopySwiftLibs" => return Some(None),
+            _ => return None,
+        };
+
+        Some(Some(match target {
+            Some(target) => format!("[{target}] {summary}"),
+            None => summary,
+        }))
+    }
+
+    /// Get target name from `(in target 'Name' from project 'Project')`
+    fn target_name(line: &str) -> Option<&str> {
+        let (_, rest) = line.split_once("(in target '")?;
+        rest.split('\'').next()
+    }
+}
+
+#[test]
+fn test_pretty_formatter() {
+    let mut state = FormatterState::new(Formatter::Pretty);
+    let lines = [
+        "CompileSwift normal arm64 /tmp/Demo/Sources/App.swift (in target 'Demo' from project 'Demo')",
+        "    cd /tmp/Demo",
+        "    /Applications/Xcode.app/Contents/Developer/usr/bin/swiftc -c /tmp/Demo/Sources/App.swift",
+        "",
+        "CompileC /tmp/build/Kit.o /tmp/Demo/Sources/Kit.m normal arm64 objective-c com.apple.compilers.llvm.clang.1_0.compiler (in target 'Kit' from project 'Demo')",
+        "/tmp/Demo/Sources/App.swift:3:1: warning: unused variable",
+        "SwiftDriver Demo normal arm64 com.apple.xcode.tools.swift.compiler (in target 'Demo' from project 'Demo')",
+        "Ld /tmp/build/Demo.app/Demo normal (in target 'Demo' from project 'Demo')",
+        "CodeSign /tmp/build/Demo.app (in target 'Demo' from project 'Demo')",
+        "** BUILD SUCCEEDED **",
+    ];
+
+    let output = lines
+        .into_iter()
+        .flat_map(|line| state.format(line.to_string()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        output,
+        vec![
+            "[Demo] Compiling App.swift",
+            "[Kit] Compiling Kit.m",
+            "/tmp/Demo/Sources/App.swift:3:1: warning: unused variable",
+            "[Demo] Linking Demo",
+            "[Demo] Signing Demo.app",
+            "Build Succeeded",
+        ]
+    );
+
+    let mut state = FormatterState::new(Formatter::Raw);
+    assert_eq!(
+        state.format("    cd /tmp".into()),
+        Some("    cd /tmp".into())
+    );
+}
+
+#[test]
+fn test_xcodegen_stderr_level() {
+    let state = FormatterState::new(Formatter::XcodeGen);
+    let warning = "⚠️  Spec validation warnings: target Demo has an unknown option";
+    assert_eq!(state.stderr_level(warning), ContentLevel::Warn);
+    assert_eq!(
+        state.stderr_level("Warning: settingGroups is deprecated"),
+        ContentLevel::Warn
+    );
+    assert_eq!(
+        state.stderr_level("Spec validation error: missing target"),
+        ContentLevel::Error
+    );
+    assert_eq!(
+        FormatterState::new(Formatter::Raw).stderr_level(warning),
+        ContentLevel::Error
+    );
+    assert!(Formatter::XcodeGen.trusts_exit_code() && !Formatter::Pretty.trusts_exit_code());
+}
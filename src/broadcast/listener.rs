@@ -0,0 +1,109 @@
+use super::{ContentLevel, Framing, Message};
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixStream;
+
+/// Client connected to the broadcast socket
+#[derive(Debug)]
+pub struct Listener {
+    stream: UnixStream,
+    /// Minimum level of messages the client wants to receive
+    min_level: Option<ContentLevel>,
+    /// How messages are framed for the client
+    framing: Framing,
+}
+
+/// First line sent by clients on connect.
+///
+/// Either a plain client id (e.g. `1234`) or a json object
+/// (e.g. `{"id":1234,"min_level":"warn","framing":"length"}`)
+#[derive(Debug, PartialEq, Eq)]
+pub struct Handshake {
+    pub id: u32,
+    pub min_level: Option<ContentLevel>,
+    pub framing: Framing,
+}
+
+impl Handshake {
+    /// Parse handshake line
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        if let Ok(id) = line.parse::<u32>() {
+            return Ok(Self {
+                id,
+                min_level: None,
+                framing: Framing::default(),
+            });
+        }
+
+        #[derive(Deserialize)]
+        struct Raw {
+            id: u32,
+            #[serde(default)]
+            min_level: Option<String>,
+            #[serde(default)]
+            framing: Framing,
+        }
+
+        let raw = serde_json::from_str::<Raw>(line).map_err(|e| e.to_string())?;
+        let min_level = match raw.min_level {
+            Some(level) => Some(level.parse::<ContentLevel>()?),
+            None => None,
+        };
+
+        Ok(Self {
+            id: raw.id,
+            min_level,
+            framing: raw.framing,
+        })
+    }
+}
+
+impl Listener {
+    pub fn new(stream: UnixStream, handshake: &Handshake) -> Self {
+        Self {
+            stream,
+            min_level: handshake.min_level.clone(),
+            framing: handshake.framing,
+        }
+    }
+
+    /// Whether the client wants to receive a given message
+    pub fn accepts(&self, message: &Message) -> bool {
+        match (&self.min_level, message.level()) {
+            (Some(min_level), Some(level)) => level >= min_level,
+            _ => true,
+        }
+    }
+
+    /// Write a serialized message to the client, framed as negotiated
+    pub async fn write(&mut self, value: &str) -> std::io::Result<()> {
+        self.stream.write_all(&self.framing.encode(value)).await?;
+        self.stream.flush().await
+    }
+}
+
+#[test]
+fn test_parse_handshake() {
+    assert_eq!(
+        Handshake::parse("1234\n"),
+        Ok(Handshake {
+            id: 1234,
+            min_level: None,
+            framing: Framing::Newline,
+        })
+    );
+    assert_eq!(
+        Handshake::parse(r#"{"id":1234,"min_level":"warn"}"#),
+        Ok(Handshake {
+            id: 1234,
+            min_level: Some(ContentLevel::Warn),
+            framing: Framing::Newline,
+        })
+    );
+    assert_eq!(
+        Handshake::parse(r#"{"id":1234,"framing":"length"}"#).map(|h| h.framing),
+        Ok(Framing::Length)
+    );
+    assert!(Handshake::parse(r#"{"id":1234,"min_level":"loud"}"#).is_err());
+}
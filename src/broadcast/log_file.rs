@@ -0,0 +1,84 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Append-only file messages are persisted to, rotated once it grows past a max size
+#[derive(Debug)]
+pub struct LogFile {
+    /// Current log file path, previous one is kept with `.1` suffix
+    path: PathBuf,
+    /// Opened file, None until first write or after a failure
+    file: Option<File>,
+    /// Size of current log file
+    size: u64,
+    /// Size after which the log file is rotated
+    max_size: u64,
+}
+
+impl LogFile {
+    pub fn new(path: PathBuf, max_size: u64) -> Self {
+        Self {
+            path,
+            file: None,
+            size: 0,
+            max_size,
+        }
+    }
+
+    /// Get path of previous, rotated, log file
+    pub fn rotated_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".1");
+        path.with_file_name(name)
+    }
+
+    /// Append a line, errors are logged and never propagated
+    pub fn write(&mut self, line: &str) {
+        if let Err(err) = self.try_write(line) {
+            tracing::warn!("Failed to write to {:?}: {err}", self.path);
+            self.file = None;
+        }
+    }
+
+    fn try_write(&mut self, line: &str) -> std::io::Result<()> {
+        if self.file.is_some() && self.size + line.len() as u64 > self.max_size {
+            self.file = None;
+            std::fs::rename(&self.path, Self::rotated_path(&self.path))?;
+        }
+
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?;
+                self.size = file.metadata()?.len();
+                self.file.insert(file)
+            }
+        };
+
+        file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_log_file_rotation() {
+    let root = std::env::temp_dir().join(format!("xbase-log-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let path = root.join("project.log");
+
+    let mut log = LogFile::new(path.clone(), 10);
+    log.write("123456\n");
+    log.write("abcdef\n");
+    log.write("ghi\n");
+
+    let rotated = LogFile::rotated_path(&path);
+    assert_eq!(std::fs::read_to_string(&rotated).unwrap(), "123456\n");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "abcdef\nghi\n");
+
+    std::fs::remove_dir_all(root).ok();
+}
@@ -1,18 +1,23 @@
-use crate::{BuildSettings, ProjectInfo, Runners};
+use crate::{
+    BuildDiagnostic, BuildPhaseTiming, BuildSettings, FileCoverage, ProjectInfo, RunSessionKey,
+    Runners,
+};
 use serde::{Deserialize, Serialize};
 use typescript_type_def::TypeDef;
 
 /// State usesd to set client state
-#[derive(Debug, Serialize, TypeDef)]
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
 #[serde(tag = "key", content = "value")]
 #[serde(rename_all = "camelCase")]
 pub enum State {
     Runners(Runners),
     ProjectInfo(ProjectInfo),
+    /// Whether acting on file changes is paused
+    WatchPaused(bool),
 }
 
 /// Representation of Messages that clients needs to process
-#[derive(Debug, Serialize, TypeDef)]
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
 #[serde(tag = "type", content = "args")]
 pub enum Message {
     /// Notify use with a message
@@ -36,12 +41,27 @@ pub enum Message {
     },
     /// Update Current Task
     UpdateCurrentTask {
+        /// Content with ANSI escape codes stripped
         content: String,
         level: ContentLevel,
+        /// Content as emitted by the task process, only set if it had ANSI escape codes (colors)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ansi: Option<String>,
     },
     FinishCurrentTask {
         status: TaskStatus,
     },
+    /// Task is about to start, sent before spawning its process
+    TaskStarted {
+        task: TaskKind,
+        target: String,
+    },
+    /// Task started with [`Message::TaskStarted`] finished
+    TaskFinished {
+        task: TaskKind,
+        target: String,
+        outcome: TaskStatus,
+    },
     /// Notify client that something is being watched
     SetWatching {
         watching: bool,
@@ -49,11 +69,116 @@ pub enum Message {
     },
     /// Notification to client to update a state with the given value
     SetState(State),
+    /// Progress of current task compile units
+    Progress {
+        task: String,
+        current: u32,
+        /// Estimated total, i.e. most compile units seen in previous successful builds of the task
+        total: u32,
+    },
+    /// Progress of current task when the total number of compile units is unknown
+    IndeterminateProgress {
+        task: String,
+        current: u32,
+    },
+    /// Error, warning or note reported while building a target
+    Diagnostic {
+        target: String,
+        diagnostic: BuildDiagnostic,
+    },
+    /// Summary of a test suite run
+    TestResult {
+        suite: String,
+        passed: u32,
+        failed: u32,
+        /// Duration in seconds
+        duration: f64,
+    },
+    /// Time spent in each build phase, reported by builds ran with timing summary
+    BuildTimingSummary {
+        target: String,
+        phases: Vec<BuildPhaseTiming>,
+    },
+    /// Line coverage of a target, reported by test runs with coverage enabled
+    Coverage {
+        target: String,
+        lines_covered: u32,
+        lines_total: u32,
+        /// Covered lines percentage
+        percent: f64,
+        /// Coverage of target source files, only if requested
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        files: Vec<FileCoverage>,
+    },
+    /// Run session started
+    RunSessionStarted(RunSessionKey),
+    /// Run session ended, either exited or stopped
+    RunSessionEnded(RunSessionKey),
+    /// Sent when no other message was sent within ping interval, safe to ignore.
+    ///
+    /// Clients not receiving any message for longer may assume the daemon is dead.
+    Ping,
     /// Internal!
     #[serde(skip)]
     Disconnect,
 }
 
+/// How messages are framed on the broadcast socket, negotiated by client handshake.
+///
+/// - `newline` (default): each message is a json object followed by `\n`.
+/// - `length`: each message is a 4-byte big-endian payload length followed by the json payload,
+///   so clients can read exact frames without splitting lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+#[serde(rename_all = "lowercase")]
+pub enum Framing {
+    Newline,
+    Length,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self::Newline
+    }
+}
+
+impl Framing {
+    /// Encode a serialized message into a frame
+    pub fn encode(&self, payload: &str) -> Vec<u8> {
+        let payload = payload.as_bytes();
+        let mut frame = Vec::with_capacity(payload.len() + 4);
+        match self {
+            Self::Newline => {
+                frame.extend_from_slice(payload);
+                frame.push(b'\n');
+            }
+            Self::Length => {
+                frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+                frame.extend_from_slice(payload);
+            }
+        }
+        frame
+    }
+
+    /// Take the first complete frame payload out of buffer, if any
+    pub fn decode(&self, buf: &mut Vec<u8>) -> Option<Vec<u8>> {
+        match self {
+            Self::Newline => {
+                let end = buf.iter().position(|b| *b == b'\n')?;
+                let mut payload = buf.drain(..=end).collect::<Vec<_>>();
+                payload.pop();
+                Some(payload)
+            }
+            Self::Length => {
+                let len = u32::from_be_bytes(buf.get(..4)?.try_into().ok()?) as usize;
+                if buf.len() < len + 4 {
+                    return None;
+                }
+                Some(buf.drain(..len + 4).skip(4).collect())
+            }
+        }
+    }
+}
+
 /// What kind of task is currently under progress?
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TypeDef)]
 pub enum TaskKind {
@@ -65,6 +190,14 @@ pub enum TaskKind {
     Compile,
     /// Generate xcodeproj
     Generate,
+    /// Clean build products
+    Clean,
+    /// Install dependencies (i.e. CocoaPods)
+    Install,
+    /// Run tests
+    Test,
+    /// Run project load hook (`on_load`)
+    Hook,
 }
 
 /// What the status of task is currently under progress?
@@ -93,6 +226,61 @@ pub enum ContentLevel {
     Error,
 }
 
+impl Message {
+    /// Get message content level, if any
+    pub fn level(&self) -> Option<&ContentLevel> {
+        match self {
+            Self::Notify { level, .. }
+            | Self::Log { level, .. }
+            | Self::UpdateCurrentTask { level, .. } => Some(level),
+            _ => None,
+        }
+    }
+
+    /// Render replayable messages as plain text, with ANSI escape codes stripped
+    pub fn to_plain_text(&self) -> Option<String> {
+        let text = match self {
+            Self::Log { content, level } | Self::UpdateCurrentTask { content, level, .. } => {
+                format!("[{level:?}] {content}")
+            }
+            Self::SetCurrentTask {
+                kind,
+                target,
+                status,
+            } => format!("[{kind:?}] {target}: {status:?}"),
+            Self::FinishCurrentTask { status } => format!("[Finished] {status:?}"),
+            _ => return None,
+        };
+        Some(crate::util::fmt::strip_ansi(text).0)
+    }
+
+    /// Whether the message should be replayed to clients connecting later
+    pub fn is_replayable(&self) -> bool {
+        matches!(
+            self,
+            Self::Log { .. }
+                | Self::SetCurrentTask { .. }
+                | Self::UpdateCurrentTask { .. }
+                | Self::FinishCurrentTask { .. }
+        )
+    }
+}
+
+impl std::str::FromStr for ContentLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "trace" => Ok(Self::Trace),
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            _ => Err(format!("Unknown content level: `{s}`")),
+        }
+    }
+}
+
 impl From<String> for Message {
     fn from(value: String) -> Self {
         Self::Notify {
@@ -110,3 +298,33 @@ impl From<&str> for Message {
         }
     }
 }
+
+#[test]
+fn test_framing_round_trip() {
+    let message = Message::Log {
+        content: "first line\nsecond line".into(),
+        level: ContentLevel::Info,
+    };
+    let json = serde_json::to_string(&message).unwrap();
+    let raw = "raw\nnewline";
+
+    let round_trip = |framing: Framing, payloads: &[&str]| {
+        let mut buf = payloads
+            .iter()
+            .flat_map(|payload| framing.encode(payload))
+            .collect::<Vec<_>>();
+        let mut decoded = vec![];
+        while let Some(payload) = framing.decode(&mut buf) {
+            decoded.push(String::from_utf8(payload).unwrap());
+        }
+        assert!(buf.is_empty());
+        decoded
+    };
+
+    assert_eq!(
+        round_trip(Framing::Newline, &[&json, &json]),
+        [&*json, &*json]
+    );
+    assert_eq!(round_trip(Framing::Length, &[&json, raw]), [&*json, raw]);
+    assert_eq!(round_trip(Framing::Newline, &[raw]), ["raw", "newline"]);
+}
@@ -1,18 +1,44 @@
+mod client;
+mod coordinator;
+mod coverage;
+mod diagnostic;
+mod formatter;
+mod listener;
+mod log_file;
 mod message;
+mod queue;
+mod result_bundle;
+mod shared;
 mod task;
+mod test_summary;
+mod timing_summary;
 
 pub use self::message::*;
+pub use client::*;
+pub use coordinator::*;
+pub use coverage::*;
+pub use diagnostic::*;
+pub use formatter::*;
+pub use listener::*;
+pub use log_file::*;
+pub use queue::*;
+pub use result_bundle::*;
+pub use shared::*;
 pub use task::*;
+pub use test_summary::*;
+pub use timing_summary::*;
 use tracing::instrument;
 
 use crate::util::extensions::PathExt;
 use crate::Result;
 use process_stream::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{UnixListener, UnixStream};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::UnixListener;
 use tokio::sync::{mpsc::*, Mutex, Notify};
 use tokio::task::JoinHandle;
 
@@ -28,36 +54,126 @@ pub struct Broadcast {
     /// Server handler
     pub server: JoinHandle<()>,
     /// Sender to be used within the server to write items to file_path
-    tx: UnboundedSender<(Option<u32>, Message)>,
+    tx: Arc<MessageQueue>,
     /// Abort notifier to stop the logger
     abort: Arc<Notify>,
     /// Socket listeners
-    #[allow(dead_code)]
-    listeners: Arc<Mutex<HashMap<u32, UnixStream>>>,
+    listeners: Arc<Mutex<HashMap<u32, Listener>>>,
+    /// Recent messages to replay to newly connected clients
+    replay: Replay,
+    /// Cancel notifiers of running builds keyed by target
+    builds: std::sync::Mutex<HashMap<String, Arc<Notify>>>,
+    /// Number of tasks of any kind currently running
+    running_tasks: Arc<AtomicUsize>,
+    /// Number of compile units seen in previous runs keyed by task target
+    compile_units: std::sync::Mutex<HashMap<String, u32>>,
+    /// Diagnostics sent to clients so far, to skip ones repeated by rebuilds
+    diagnostics: std::sync::Mutex<HashSet<(String, BuildDiagnostic)>>,
+    /// Time a consumed process may go without output before it's killed
+    process_timeout: Option<Duration>,
+    /// Minimum level of messages clients are notified with, lower ones are only logged
+    notify_level: ContentLevel,
+    /// Formatter of raw xcodebuild output
+    formatter: Formatter,
+    /// Builds tracked to update compile database from
+    coordinator: BuildCoordinator,
+    /// Builds in flight shared by identical build requests
+    shared_builds: SharedBuilds,
+}
+
+/// Recent serialized messages along with the time they were sent
+type Replay = Arc<Mutex<VecDeque<(SystemTime, String)>>>;
+
+/// Broadcast configuration
+#[derive(Debug, Clone)]
+pub struct BroadcastConfig {
+    /// Number of recent messages to replay to newly connected clients
+    pub replay_capacity: usize,
+    /// Number of messages to queue before dropping log-level ones
+    pub queue_capacity: usize,
+    /// Directory to create broadcast sockets in
+    pub root: PathBuf,
+    /// Time a consumed process may go without output before it's killed, None to disable
+    pub process_timeout: Option<Duration>,
+    /// Time without messages after which clients are sent a ping, None to disable
+    pub ping_interval: Option<Duration>,
+    /// Max size in bytes of a log file in root to persist messages to, None to disable
+    pub log_max_size: Option<u64>,
+    /// Minimum level of messages to notify clients with, lower ones are sent as logs instead
+    pub notify_level: ContentLevel,
+    /// Formatter of raw xcodebuild output, e.g. [`Formatter::Pretty`] to collapse build phases
+    pub formatter: Formatter,
+}
+
+impl Default for BroadcastConfig {
+    fn default() -> Self {
+        let root = std::env::var_os(Broadcast::ROOT_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| Broadcast::ROOT.into());
+
+        Self {
+            replay_capacity: 200,
+            queue_capacity: 2048,
+            root,
+            process_timeout: Some(Duration::from_secs(600)),
+            ping_interval: Some(Duration::from_secs(30)),
+            log_max_size: None,
+            notify_level: ContentLevel::Trace,
+            formatter: Formatter::Raw,
+        }
+    }
 }
 
 impl Broadcast {
     pub const ROOT: &'static str = "/private/tmp/xbase";
+    /// Environment variable to override [`Broadcast::ROOT`] with
+    pub const ROOT_ENV: &'static str = "XBASE_BROADCAST_ROOT";
+
+    /// Ensure broadcast sockets directory exists and is only accessible by current user
+    fn ensure_root(base: &Path) -> Result<()> {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+        if base.is_dir() {
+            return Ok(());
+        }
+
+        if base.exists() {
+            return Err(crate::Error::Unexpected(format!(
+                "Broadcast root {base:?} exists but is not a directory"
+            )));
+        }
+
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(base)?;
+        std::fs::set_permissions(base, std::fs::Permissions::from_mode(0o700))?;
+
+        Ok(())
+    }
 
     #[instrument(parent = None, name = "Broadcaster", skip_all, fields(name = root.as_ref().name().unwrap()))]
-    pub async fn new(root: impl AsRef<Path>) -> Result<Self> {
-        let (tx, rx) = unbounded_channel();
+    pub async fn new(root: impl AsRef<Path>, config: BroadcastConfig) -> Result<Self> {
+        let tx = Arc::new(MessageQueue::new(config.queue_capacity));
         let name = format!("{}.socket", root.as_ref().unique_name().unwrap());
-        let base = PathBuf::from(Self::ROOT);
+        let base = config.root.clone();
 
-        if !base.exists() {
-            tokio::fs::create_dir(Self::ROOT).await?;
-        }
+        Self::ensure_root(&base)?;
 
         let address = base.join(name);
         let name = root.as_ref().name().unwrap();
 
+        // A socket accepting connections is owned by another live instance, otherwise it's stale
         if address.exists() {
+            if tokio::net::UnixStream::connect(&address).await.is_ok() {
+                return Err(crate::Error::AlreadyRunning(address));
+            }
             tokio::fs::remove_file(&address).await.ok();
         };
 
         let abort: Arc<Notify> = Default::default();
-        let listeners: Arc<Mutex<HashMap<u32, UnixStream>>> = Default::default();
+        let listeners: Arc<Mutex<HashMap<u32, Listener>>> = Default::default();
+        let replay: Replay = Default::default();
 
         let listener = UnixListener::bind(&address)?;
         let server = tokio::spawn(Self::start_server(
@@ -65,12 +181,21 @@ impl Broadcast {
             listener,
             abort.clone(),
             listeners.clone(),
+            replay.clone(),
         ));
+        let log_file = config.log_max_size.map(|max_size| {
+            let name = format!("{}.log", root.as_ref().unique_name().unwrap());
+            LogFile::new(base.join(name), max_size)
+        });
         let handle = tokio::spawn(Self::start_messages_handler(
             name,
-            rx,
+            tx.clone(),
             abort.clone(),
             listeners.clone(),
+            replay.clone(),
+            config.replay_capacity,
+            config.ping_interval,
+            log_file,
         ));
 
         tracing::info!("Created");
@@ -81,6 +206,16 @@ impl Broadcast {
             abort,
             handle,
             listeners,
+            replay,
+            builds: Default::default(),
+            running_tasks: Default::default(),
+            compile_units: Default::default(),
+            diagnostics: Default::default(),
+            process_timeout: config.process_timeout,
+            notify_level: config.notify_level,
+            formatter: config.formatter,
+            coordinator: Default::default(),
+            shared_builds: Default::default(),
             server,
             address,
         })
@@ -92,7 +227,8 @@ impl Broadcast {
         name: String,
         listener: UnixListener,
         abort: Arc<Notify>,
-        listeners: Arc<Mutex<HashMap<u32, UnixStream>>>,
+        listeners: Arc<Mutex<HashMap<u32, Listener>>>,
+        replay: Replay,
     ) {
         loop {
             tokio::select! {
@@ -110,13 +246,19 @@ impl Broadcast {
 
                     // let reader = BufReader::new(&stream);
                     match reader.read_line(&mut buf).await {
-                        Ok(_) => match buf.trim().parse::<u32>() {
-                            Ok(id) =>  {
+                        Ok(_) => match Handshake::parse(&buf) {
+                            Ok(handshake) =>  {
+                                let id = handshake.id;
                                 tracing::info!("Connected [{id}]");
-                                listeners.insert(id, stream);
+                                let mut listener = Listener::new(stream, &handshake);
+                                // Replay while holding listeners lock to keep ordering with live messages
+                                for (_, value) in replay.lock().await.iter() {
+                                    listener.write(value).await.ok();
+                                }
+                                listeners.insert(id, listener);
                             }
                             Err(err) => {
-                                tracing::error!("Failed to parse id as u32: {err}");
+                                tracing::error!("Failed to parse client handshake: {err}");
 
                             }
                         }
@@ -134,56 +276,243 @@ impl Broadcast {
     #[instrument(parent = None, name = "Broadcaster", skip_all, fields(name=name))]
     async fn start_messages_handler(
         name: String,
-        mut rx: UnboundedReceiver<(Option<u32>, Message)>,
+        rx: Arc<MessageQueue>,
         abort: Arc<Notify>,
-        listeners: Arc<Mutex<HashMap<u32, UnixStream>>>,
+        listeners: Arc<Mutex<HashMap<u32, Listener>>>,
+        replay: Replay,
+        replay_capacity: usize,
+        ping_interval: Option<Duration>,
+        mut log_file: Option<LogFile>,
     ) {
+        let ping = tokio::time::sleep(ping_interval.unwrap_or_default());
+        tokio::pin!(ping);
+
         loop {
-            tokio::select! {
-                _ = abort.notified() => { break; },
-                result = rx.recv() => match result {
+            let (id, message) = tokio::select! {
+                _ = abort.notified() => break,
+                _ = &mut ping, if ping_interval.is_some() => (None, Message::Ping),
+                result = rx.pop() => match result {
                     None => break,
-                    Some((id, message)) => {
-                        let listeners =  listeners.clone();
-                        let mut listeners = listeners.lock().await;
-                        if let Message::Disconnect = message {
-                            listeners.remove(&id.unwrap());
-                            continue;
-                        }
+                    Some(item) => item,
+                },
+            };
 
-                        match serde_json::to_string(&message) {
-                            Ok(mut value) => {
-                                tracing::trace!("{value}");
-                                value.push('\n');
-                                if let Some(id) = id {
-                                    if let Some(stream) = listeners.get_mut(&id) {
-                                        stream.write_all(value.as_bytes()).await.ok();
-                                        stream.flush().await.ok();
-                                    } else {
-                                        tracing::error!("[CLIENT WITH {id} NOT FOUND]")
-                                    }
-                                } else {
-                                    for (_, listener) in listeners.iter_mut() {
-                                        listener.write_all(value.as_bytes()).await.ok();
-                                        listener.flush().await.ok();
-                                    };
-                                }
-                            },
-                            Err(err) => tracing::warn!("SendError: `{message:?}` = `{err}`"),
-                        }
+            // Only ping when idle
+            if let Some(interval) = ping_interval {
+                ping.as_mut().reset(tokio::time::Instant::now() + interval);
+            }
+
+            let mut listeners = listeners.lock().await;
+            if let Message::Disconnect = message {
+                listeners.remove(&id.unwrap());
+                continue;
+            }
+
+            let value = match serde_json::to_string(&message) {
+                Ok(value) => value,
+                Err(err) => {
+                    tracing::warn!("SendError: `{message:?}` = `{err}`");
+                    continue;
+                }
+            };
+
+            if !matches!(message, Message::Ping) {
+                tracing::trace!("{value}");
+            }
+            if id.is_none() && replay_capacity > 0 && message.is_replayable() {
+                let mut replay = replay.lock().await;
+                if replay.len() >= replay_capacity {
+                    replay.pop_front();
+                }
+                replay.push_back((SystemTime::now(), value.clone()));
+            }
+            let mut closed = vec![];
+            if let Some(id) = id {
+                if let Some(listener) = listeners.get_mut(&id) {
+                    if listener.accepts(&message) && listener.write(&value).await.is_err() {
+                        closed.push(id);
                     }
+                } else {
+                    tracing::error!("[CLIENT WITH {id} NOT FOUND]")
+                }
+            } else {
+                for (id, listener) in listeners.iter_mut() {
+                    if !listener.accepts(&message) {
+                        continue;
+                    }
+                    if listener.write(&value).await.is_err() {
+                        closed.push(*id);
+                    }
+                }
+            }
+            for id in closed {
+                listeners.remove(&id);
+                tracing::info!("Dropped [{id}]: connection closed");
+            }
+            drop(listeners);
+
+            if let Some(log_file) = log_file.as_mut() {
+                if !matches!(message, Message::Ping) {
+                    log_file.write(&format!("{value}\n"));
                 }
             }
         }
     }
 
     pub fn send(&self, id: Option<u32>, message: Message) {
-        self.tx.send((id, message)).ok();
+        self.tx.push(id, message);
     }
 
     /// Explicitly Abort/Consume logger
     pub fn abort(&self) {
         self.abort.notify_waiters();
+        self.tx.close();
+    }
+
+    /// Write recent messages to a file as plain text, with ANSI escape codes stripped and each
+    /// line prefixed with the time its message was sent.
+    ///
+    /// Returns the number of messages written.
+    pub async fn export_log(&self, path: &Path) -> Result<usize> {
+        let lines = self
+            .replay
+            .lock()
+            .await
+            .iter()
+            .flat_map(|(time, value)| {
+                let message = serde_json::from_str::<Message>(value).ok()?;
+                let time = crate::util::fmt::timestamp(*time);
+                let lines = message
+                    .to_plain_text()?
+                    .lines()
+                    .map(|line| format!("{time} {line}\n"))
+                    .collect::<String>();
+                Some(lines)
+            })
+            .collect::<Vec<_>>();
+
+        if lines.is_empty() {
+            return Err(crate::Error::NoLog(self.root.clone()));
+        }
+
+        crate::util::fs::write_atomic(path, lines.concat()).await?;
+        Ok(lines.len())
+    }
+
+    /// Get coordinator of builds and compile database updates
+    pub fn coordinator(&self) -> &BuildCoordinator {
+        &self.coordinator
+    }
+
+    /// Get builds in flight shared by identical build requests
+    pub fn shared_builds(&self) -> &SharedBuilds {
+        &self.shared_builds
+    }
+
+    /// Whether the broadcast server is still running and its socket exists
+    pub fn is_healthy(&self) -> bool {
+        !self.server.is_finished() && !self.handle.is_finished() && self.address.exists()
+    }
+
+    /// Get number of connected listeners
+    pub async fn listeners_count(&self) -> usize {
+        self.listeners.lock().await.len()
+    }
+
+    /// Wait until queued messages are below capacity
+    pub(crate) async fn ready(&self) {
+        self.tx.ready().await
+    }
+
+    /// Cancel running builds, abort consumed processes, stop the server and remove the socket.
+    ///
+    /// Server and messages handler are forcibly aborted if they don't stop within timeout.
+    pub async fn shutdown(&self, timeout: Duration) {
+        for (target, cancel) in self.builds.lock().unwrap().drain() {
+            tracing::info!("[{target}] Aborting build");
+            cancel.notify_one();
+        }
+
+        self.abort();
+
+        let started = Instant::now();
+        while !(self.server.is_finished() && self.handle.is_finished()) {
+            if started.elapsed() >= timeout {
+                tracing::warn!("Timed out waiting for broadcast server to stop");
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        self.server.abort();
+        self.handle.abort();
+
+        if self.address.exists() {
+            tokio::fs::remove_file(&self.address).await.ok();
+        }
+    }
+
+    /// Register a running build of target, returning the cancel notifier of the build it replaces
+    pub(crate) fn replace_build(&self, target: &str, cancel: Arc<Notify>) -> Option<Arc<Notify>> {
+        let mut builds = self.builds.lock().unwrap();
+        builds.insert(target.to_string(), cancel)
+    }
+
+    /// Get number of builds currently running
+    pub(crate) fn running_builds(&self) -> usize {
+        self.builds.lock().unwrap().len()
+    }
+
+    /// Whether a build or any other task, e.g. generate or test, is currently running
+    pub(crate) fn is_busy(&self) -> bool {
+        self.running_builds() > 0 || self.running_tasks.load(Ordering::Relaxed) > 0
+    }
+
+    /// Forget diagnostics sent for a target, so ones reported again by a new build are sent
+    pub(crate) fn clear_diagnostics(&self, target: &str) {
+        let mut diagnostics = self.diagnostics.lock().unwrap();
+        diagnostics.retain(|(sent_target, _)| sent_target != target);
+    }
+
+    /// Send a build diagnostic of a target to clients, unless it was already sent
+    pub fn diagnostic(&self, target: &str, diagnostic: BuildDiagnostic) {
+        let key = (target.to_string(), diagnostic);
+        if !self.diagnostics.lock().unwrap().insert(key.clone()) {
+            return;
+        }
+
+        let (target, diagnostic) = key;
+        self.send(None, Message::Diagnostic { target, diagnostic });
+    }
+
+    /// Get time a consumed process may go without output before it's killed
+    pub(crate) fn process_timeout(&self) -> Option<Duration> {
+        self.process_timeout
+    }
+
+    /// Get formatter of raw xcodebuild output
+    pub(crate) fn formatter(&self) -> Formatter {
+        self.formatter
+    }
+
+    /// Get known number of compile units of a target
+    pub(crate) fn compile_units(&self, target: &str) -> Option<u32> {
+        self.compile_units.lock().unwrap().get(target).cloned()
+    }
+
+    /// Remember number of compile units of a target, keeping the largest seen
+    pub(crate) fn update_compile_units(&self, target: &str, count: u32) {
+        let mut compile_units = self.compile_units.lock().unwrap();
+        let known = compile_units.entry(target.to_string()).or_default();
+        *known = count.max(*known);
+    }
+
+    /// Remove a running build of target, if it wasn't replaced by a newer one
+    pub(crate) fn remove_build(&self, target: &str, cancel: &Arc<Notify>) {
+        let mut builds = self.builds.lock().unwrap();
+        if builds.get(target).map_or(false, |c| Arc::ptr_eq(c, cancel)) {
+            builds.remove(target);
+        }
     }
 
     /// Get a reference to the logger's project root.
@@ -213,6 +542,31 @@ impl Broadcast {
             Message::UpdateCurrentTask {
                 content: content.as_ref().into(),
                 level,
+                ansi: None,
+            },
+        )
+    }
+
+    /// Tell connected clients a task is about to start, e.g. to show a spinner right away
+    pub fn task_started(&self, task: TaskKind, target: &str) {
+        let target = target.to_string();
+        self.send(None, Message::TaskStarted { task, target })
+    }
+
+    /// Tell connected clients a task started with [`Broadcast::task_started`] finished
+    pub fn task_finished(&self, task: TaskKind, target: &str, success: bool) {
+        let outcome = if success {
+            TaskStatus::Succeeded
+        } else {
+            TaskStatus::Failed
+        };
+        let target = target.to_string();
+        self.send(
+            None,
+            Message::TaskFinished {
+                task,
+                target,
+                outcome,
             },
         )
     }
@@ -236,14 +590,17 @@ impl Broadcast {
 
     /// Notify clients with a message
     fn notify<S: AsRef<str>>(&self, msg: S, level: ContentLevel) {
-        let msg = msg.as_ref();
-        self.send(
-            None,
-            Message::Notify {
-                content: msg.to_string(),
-                level,
-            },
-        )
+        self.send(None, self.notification(msg.as_ref(), level))
+    }
+
+    /// Get message to notify with, or to only log if below notify level
+    fn notification(&self, content: &str, level: ContentLevel) -> Message {
+        let content = content.to_string();
+        if level < self.notify_level {
+            Message::Log { content, level }
+        } else {
+            Message::Notify { content, level }
+        }
     }
 
     /// Log clients with a message
@@ -260,14 +617,7 @@ impl Broadcast {
 
     /// Notify client with a message and id
     fn notify_with_id<S: AsRef<str>>(&self, msg: S, id: u32, level: ContentLevel) {
-        let msg = msg.as_ref();
-        self.send(
-            Some(id),
-            Message::Notify {
-                content: msg.to_string(),
-                level,
-            },
-        )
+        self.send(Some(id), self.notification(msg.as_ref(), level))
     }
 
     /// Log client with a message and id
@@ -398,3 +748,142 @@ impl Broadcast {
         self.log_with_id(msg, id, ContentLevel::Debug)
     }
 }
+
+/// Create broadcast of a project at `project` in a temporary directory named after a test, with
+/// its sockets in the directory too.
+///
+/// Returns the runtime the broadcast runs in, and the temporary directory to remove once done.
+#[cfg(test)]
+pub(crate) fn test_broadcast(
+    name: &str,
+    project: &str,
+    config: BroadcastConfig,
+) -> (tokio::runtime::Runtime, Arc<Broadcast>, PathBuf) {
+    let base = std::env::temp_dir().join(format!("xbase-{name}-{}", std::process::id()));
+    let root = base.join(project);
+    std::fs::create_dir_all(&root).unwrap();
+    let config = BroadcastConfig {
+        root: base.join("sockets"),
+        ..config
+    };
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let broadcast = runtime.block_on(Broadcast::new(&root, config)).unwrap();
+    (runtime, Arc::new(broadcast), base)
+}
+
+#[test]
+fn test_ensure_root() {
+    let base = std::env::temp_dir().join(format!("xbase-broadcast-{}", std::process::id()));
+    let file = base.join("file");
+
+    Broadcast::ensure_root(&base.join("sockets")).unwrap();
+    assert!(base.join("sockets").is_dir());
+
+    std::fs::write(&file, "").unwrap();
+    assert!(Broadcast::ensure_root(&file).is_err());
+
+    std::fs::remove_dir_all(base).ok();
+}
+
+#[test]
+fn test_broadcast_root_with_spaces_and_unicode() {
+    let config = BroadcastConfig {
+        log_max_size: Some(1024),
+        ..Default::default()
+    };
+    let (runtime, broadcast, base) = test_broadcast("unicode", "Mobile Documents/Café App", config);
+
+    runtime.block_on(async {
+        let address = broadcast.address().clone();
+        let name = address.file_name().unwrap().to_str().unwrap();
+        assert!(name.ends_with("Mobile Documents_Café App.socket"), "{name}");
+        assert!(tokio::net::UnixStream::connect(&address).await.is_ok());
+        broadcast.abort();
+    });
+
+    std::fs::remove_dir_all(base).ok();
+}
+
+#[test]
+fn test_export_log() {
+    let (runtime, broadcast, base) = test_broadcast("export", "Projects/Demo", Default::default());
+    let path = base.join("build.log");
+
+    runtime.block_on(async {
+        assert!(broadcast.export_log(&path).await.is_err());
+
+        broadcast.log_info("[Demo] Building");
+        broadcast.log_error("\u{1b}[31merror: \u{1b}[0mcannot find 'foo' in scope");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(broadcast.export_log(&path).await.unwrap(), 2);
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines[0].ends_with("Z [Info] [Demo] Building"),
+            "{}",
+            lines[0]
+        );
+        assert!(lines[1].ends_with("Z [Error] error: cannot find 'foo' in scope"));
+        broadcast.abort();
+    });
+
+    std::fs::remove_dir_all(base).ok();
+}
+
+#[test]
+fn test_notify_level() {
+    let config = BroadcastConfig {
+        notify_level: ContentLevel::Error,
+        ..Default::default()
+    };
+    let (runtime, broadcast, base) = test_broadcast("notify", "Projects/Demo", config);
+    let path = base.join("build.log");
+
+    runtime.block_on(async {
+        broadcast.info("[Demo] Built");
+        broadcast.error("[Demo] Failed to run");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Notifications aren't exported, only logs are
+        assert_eq!(broadcast.export_log(&path).await.unwrap(), 1);
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            content.trim_end().ends_with("Z [Info] [Demo] Built"),
+            "{content}"
+        );
+        broadcast.abort();
+    });
+
+    std::fs::remove_dir_all(base).ok();
+}
+
+#[test]
+fn test_live_and_stale_socket() {
+    let (runtime, live, base) = test_broadcast("stale", "Projects/Demo", Default::default());
+    let root = base.join("Projects/Demo");
+    let config = BroadcastConfig {
+        root: base.join("sockets"),
+        ..Default::default()
+    };
+
+    runtime.block_on(async {
+        let result = Broadcast::new(&root, config.clone()).await;
+        assert!(matches!(result, Err(crate::Error::AlreadyRunning(_))));
+
+        // Socket file is left behind, e.g. by a crashed daemon
+        live.abort();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(live.address().exists());
+
+        let broadcast = Broadcast::new(&root, config).await.unwrap();
+        assert!(tokio::net::UnixStream::connect(broadcast.address())
+            .await
+            .is_ok());
+        broadcast.abort();
+    });
+
+    std::fs::remove_dir_all(base).ok();
+}
@@ -0,0 +1,142 @@
+use super::{ContentLevel, Message};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// Bounded queue of messages waiting to be written to clients.
+///
+/// Once full, pushing a log-level message drops the oldest queued log-level message. Other
+/// messages (notifications, diagnostics, task state) are never dropped, even over capacity.
+#[derive(Debug)]
+pub struct MessageQueue {
+    items: Mutex<VecDeque<(Option<u32>, Message)>>,
+    capacity: usize,
+    /// Notified once a message is pushed
+    pushed: Notify,
+    /// Notified once a message is popped
+    popped: Notify,
+    /// Whether the queue no longer accepts messages
+    closed: AtomicBool,
+}
+
+impl MessageQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: Default::default(),
+            capacity: capacity.max(1),
+            pushed: Notify::new(),
+            popped: Notify::new(),
+            closed: Default::default(),
+        }
+    }
+
+    /// Push a message, dropping oldest log-level message if the queue is full
+    pub fn push(&self, id: Option<u32>, message: Message) {
+        if self.closed.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let mut items = self.items.lock().unwrap();
+        if items.len() >= self.capacity && Self::is_droppable(&message) {
+            if let Some(idx) = items.iter().position(|(_, m)| Self::is_droppable(m)) {
+                items.remove(idx);
+            }
+        }
+        items.push_back((id, message));
+        drop(items);
+
+        self.pushed.notify_one();
+    }
+
+    /// Pop next message, waiting for one to be pushed. Returns None once closed.
+    pub async fn pop(&self) -> Option<(Option<u32>, Message)> {
+        loop {
+            if let Some(item) = self.items.lock().unwrap().pop_front() {
+                self.popped.notify_waiters();
+                return Some(item);
+            }
+
+            if self.closed.load(Ordering::SeqCst) {
+                return None;
+            }
+
+            self.pushed.notified().await;
+        }
+    }
+
+    /// Wait until the queue has room, for producers that can afford waiting
+    pub async fn ready(&self) {
+        loop {
+            let popped = self.popped.notified();
+            tokio::pin!(popped);
+            popped.as_mut().enable();
+
+            if self.len() < self.capacity || self.closed.load(Ordering::SeqCst) {
+                return;
+            }
+
+            popped.await;
+        }
+    }
+
+    /// Stop accepting messages and wake up consumer and waiting producers
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.pushed.notify_one();
+        self.popped.notify_waiters();
+    }
+
+    /// Number of queued messages
+    pub fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    /// Whether no messages are queued
+    pub fn is_empty(&self) -> bool {
+        self.items.lock().unwrap().is_empty()
+    }
+
+    /// Whether a message can be dropped under pressure
+    fn is_droppable(message: &Message) -> bool {
+        match message {
+            Message::Log { level, .. } | Message::UpdateCurrentTask { level, .. } => {
+                level < &ContentLevel::Warn
+            }
+            Message::Progress { .. } | Message::IndeterminateProgress { .. } | Message::Ping => {
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[test]
+fn test_message_queue_backpressure() {
+    let queue = MessageQueue::new(100);
+    let log = |i: usize| Message::Log {
+        content: format!("line {i}"),
+        level: ContentLevel::Info,
+    };
+
+    // Nothing drains the queue, i.e. a stalled listener
+    for i in 0..10_000 {
+        queue.push(None, log(i));
+        if i % 1000 == 0 {
+            queue.push(None, Message::from(format!("notification {i}")));
+        }
+    }
+
+    let items = queue.items.lock().unwrap();
+    let notifications = items
+        .iter()
+        .filter(|(_, m)| matches!(m, Message::Notify { .. }))
+        .count();
+
+    assert_eq!(items.len(), 100);
+    assert_eq!(notifications, 10);
+    // Oldest lines were dropped
+    assert!(
+        matches!(&items.back().unwrap().1, Message::Log { content, .. } if content == "line 9999")
+    );
+}
@@ -0,0 +1,175 @@
+use super::{Broadcast, BuildDiagnostic, Severity};
+use crate::{Error, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// Get errors and warnings of an xcresult bundle from `xcresulttool get --format json` output
+pub fn parse_result_bundle_issues(json: &str) -> Result<Vec<BuildDiagnostic>> {
+    let record: Value =
+        serde_json::from_str(json).map_err(|e| Error::Unexpected(format!("xcresulttool: {e}")))?;
+
+    let summaries = |key: &str, severity: Severity| {
+        record
+            .pointer(&format!("/issues/{key}/_values"))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .flat_map(move |issue| issue_diagnostic(issue, severity))
+            .collect::<Vec<_>>()
+    };
+
+    let mut diagnostics = summaries("errorSummaries", Severity::Error);
+    diagnostics.extend(summaries("warningSummaries", Severity::Warning));
+    Ok(diagnostics)
+}
+
+/// Convert an issue summary to a diagnostic, located with its document url, e.g.
+/// `file:///App/A.swift#StartingColumnNumber=7&StartingLineNumber=9`
+fn issue_diagnostic(issue: &Value, severity: Severity) -> Option<BuildDiagnostic> {
+    let message = issue.pointer("/message/_value")?.as_str()?.to_string();
+    let url = issue
+        .pointer("/documentLocationInCreatingWorkspace/url/_value")
+        .and_then(Value::as_str);
+    let (path, fragment) = match url.and_then(|url| url.strip_prefix("file://")) {
+        Some(url) => url.split_once('#').unwrap_or((url, "")),
+        None => {
+            return Some(BuildDiagnostic {
+                message,
+                severity,
+                ..Default::default()
+            })
+        }
+    };
+
+    // Locations are zero based
+    let location = |key: &str| {
+        fragment.split('&').find_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            (name == key).then(|| value.parse::<u32>().ok().map(|n| n + 1))?
+        })
+    };
+
+    Some(BuildDiagnostic {
+        file: Some(percent_decode(path)),
+        line: location("StartingLineNumber"),
+        column: location("StartingColumnNumber"),
+        message,
+        severity,
+        notes: vec![],
+    })
+}
+
+/// Decode percent-encoded characters of a url path, e.g. `%20` to a space
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Read errors and warnings of a build result bundle
+pub async fn read_result_bundle_issues(result_bundle: &Path) -> Result<Vec<BuildDiagnostic>> {
+    // Xcode 16 requires --legacy for the json format, older versions reject it
+    let mut stderr = String::new();
+    for legacy in [true, false] {
+        let mut command = tokio::process::Command::new("xcrun");
+        command.envs(crate::xcodebuild::envs());
+        command.args(["xcresulttool", "get"]);
+        if legacy {
+            command.arg("--legacy");
+        }
+        let output = command
+            .args(["--format", "json", "--path"])
+            .arg(result_bundle)
+            .output()
+            .await?;
+
+        if output.status.success() {
+            return parse_result_bundle_issues(&String::from_utf8_lossy(&output.stdout));
+        }
+        stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    }
+
+    Err(Error::Unexpected(format!("xcresulttool failed: {stderr}")))
+}
+
+/// Report errors and warnings of a build result bundle as diagnostics of target, then remove it.
+///
+/// Diagnostics already parsed from build output aren't sent again, and those are all that's
+/// reported when xcresulttool is unavailable.
+pub async fn report_result_bundle_issues(
+    result_bundle: &Path,
+    target: &str,
+    broadcast: &Broadcast,
+) {
+    if !result_bundle.exists() {
+        return;
+    }
+
+    match read_result_bundle_issues(result_bundle).await {
+        Ok(diagnostics) => diagnostics
+            .into_iter()
+            .for_each(|diagnostic| broadcast.diagnostic(target, diagnostic)),
+        Err(err) => tracing::debug!("[{target}] Using build output diagnostics only: {err}"),
+    }
+
+    tokio::fs::remove_dir_all(result_bundle).await.ok();
+}
+
+#[test]
+fn test_parse_result_bundle_issues() {
+    let json = r#"{
+      "issues": {
+        "errorSummaries": { "_values": [
+          {
+            "issueType": { "_value": "Swift Compiler Error" },
+            "message": { "_value": "cannot find 'foo' in scope" },
+            "documentLocationInCreatingWorkspace": { "url": {
+              "_value": "file:///tmp/My%20App/A.swift#EndingColumnNumber=10&EndingLineNumber=3&StartingColumnNumber=7&StartingLineNumber=3"
+            } }
+          },
+          { "message": { "_value": "Signing for \"App\" requires a development team." } }
+        ] },
+        "warningSummaries": { "_values": [
+          {
+            "message": { "_value": "initialization of 'x' was never used" },
+            "documentLocationInCreatingWorkspace": { "url": {
+              "_value": "file:///tmp/My%20App/B.swift#StartingColumnNumber=0&StartingLineNumber=0"
+            } }
+          }
+        ] }
+      }
+    }"#;
+
+    let diagnostics = parse_result_bundle_issues(json).unwrap();
+    assert_eq!(
+        diagnostics[0],
+        BuildDiagnostic {
+            file: Some("/tmp/My App/A.swift".into()),
+            line: Some(4),
+            column: Some(8),
+            message: "cannot find 'foo' in scope".into(),
+            severity: Severity::Error,
+            notes: vec![],
+        }
+    );
+    assert!(diagnostics[1].file.is_none() && diagnostics[1].is_code_signing());
+    assert_eq!(diagnostics[2].severity, Severity::Warning);
+    assert_eq!(diagnostics[2].line, Some(1));
+}
@@ -0,0 +1,151 @@
+use crate::{Error, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+use tokio::sync::mpsc::{channel, Receiver};
+use tokio::sync::watch;
+
+/// Builds in flight keyed by identity (i.e. arguments), shared by identical build requests.
+///
+/// A build requested while an identical one is running, e.g. by another client, waits for the
+/// running one instead of spawning another process that would cancel it.
+#[derive(Debug, Default)]
+pub struct SharedBuilds {
+    /// Incremented on file changes, builds started before a change are outdated
+    generation: AtomicU64,
+    builds: std::sync::Mutex<HashMap<String, SharedBuild>>,
+}
+
+#[derive(Debug)]
+struct SharedBuild {
+    generation: u64,
+    started: SystemTime,
+    /// Build result, None while in flight
+    result: watch::Receiver<Option<std::result::Result<(), String>>>,
+}
+
+impl SharedBuilds {
+    /// Mark builds in flight as outdated, so identical requests start new builds
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Start time of the last build of key, if it succeeded and no file changed since it started
+    pub fn up_to_date_since(&self, key: &str) -> Option<SystemTime> {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let builds = self.builds.lock().unwrap();
+        let build = builds.get(key)?;
+        let succeeded = matches!(*build.result.borrow(), Some(Ok(())));
+
+        (build.generation == generation && succeeded).then(|| build.started)
+    }
+
+    /// Wait for an up to date identical build in flight, or start a new one with `spawn`.
+    ///
+    /// Returns whether a new build was started along with the build result receiver.
+    pub fn share<F>(&self, key: &str, spawn: F) -> Result<(bool, Receiver<Result<()>>)>
+    where
+        F: FnOnce() -> Result<Receiver<Result<()>>>,
+    {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let mut builds = self.builds.lock().unwrap();
+
+        if let Some(build) = builds.get(key) {
+            if build.generation == generation && build.result.borrow().is_none() {
+                return Ok((false, wait(build.result.clone())));
+            }
+        }
+
+        let started = SystemTime::now();
+        let mut recv = spawn()?;
+        let (send, result) = watch::channel(None);
+        let build = SharedBuild {
+            generation,
+            started,
+            result,
+        };
+        builds.insert(key.to_string(), build);
+
+        let (send_result, recv_result) = channel(1);
+        tokio::spawn(async move {
+            let result = recv.recv().await;
+            let shared = match result {
+                Some(Ok(())) => Ok(()),
+                Some(Err(ref err)) => Err(err.to_string()),
+                None => Err("Shared build stopped".to_string()),
+            };
+            send.send(Some(shared)).ok();
+            if let Some(result) = result {
+                send_result.send(result).await.ok();
+            }
+        });
+
+        Ok((true, recv_result))
+    }
+}
+
+/// Wait for shared build result
+fn wait(
+    mut result: watch::Receiver<Option<std::result::Result<(), String>>>,
+) -> Receiver<Result<()>> {
+    let (send, recv) = channel(1);
+    tokio::spawn(async move {
+        let result = loop {
+            let current = result.borrow().clone();
+            if let Some(result) = current {
+                break result;
+            }
+            if result.changed().await.is_err() {
+                break Err("Shared build stopped".to_string());
+            }
+        };
+        send.send(result.map_err(Error::Build)).await.ok();
+    });
+    recv
+}
+
+#[test]
+fn test_share_identical_builds() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let shared = Arc::new(SharedBuilds::default());
+        let spawns = Arc::new(AtomicUsize::new(0));
+        let (send, recv) = channel(1);
+        let recv = std::sync::Mutex::new(Some(recv));
+        let key = "build -target App";
+
+        // Two clients requesting the same build at the same time
+        let request = || {
+            let (shared, spawns) = (shared.clone(), spawns.clone());
+            let recv = recv.lock().unwrap().take();
+            tokio::spawn(async move {
+                let spawn = || {
+                    spawns.fetch_add(1, Ordering::SeqCst);
+                    Ok(recv.unwrap())
+                };
+                shared.share(key, spawn).unwrap().1
+            })
+        };
+        let (first, second) = (request(), request());
+        let (mut first, mut second) = (first.await.unwrap(), second.await.unwrap());
+        assert_eq!(spawns.load(Ordering::SeqCst), 1);
+
+        send.send(Ok(())).await.unwrap();
+        assert!(matches!(first.recv().await, Some(Ok(()))));
+        assert!(matches!(second.recv().await, Some(Ok(()))));
+        assert!(shared.up_to_date_since(key).is_some());
+
+        // Finished and outdated builds aren't shared
+        let (_send, recv) = channel(1);
+        let (started, _) = shared.share(key, || Ok(recv)).unwrap();
+        assert!(started);
+        shared.invalidate();
+        let (_send, recv) = channel(1);
+        let (started, _) = shared.share(key, || Ok(recv)).unwrap();
+        assert!(started);
+        assert!(shared.up_to_date_since(key).is_none());
+    });
+}
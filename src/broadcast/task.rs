@@ -1,39 +1,156 @@
 use super::*;
+use crate::util::fmt::strip_ansi;
+use crate::util::regex::ClangUnitParser;
+use crate::Error;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Max number of error lines kept in [`TaskOutcome::tail`]
+const TAIL_LINES: usize = 20;
+
+/// Outcome of a consumed process
+#[derive(Debug, Clone, Default)]
+pub struct TaskOutcome {
+    /// Whether the process succeeded without reporting errors
+    pub success: bool,
+    /// Process exit code, unset when killed (e.g. cancelled or timed out)
+    pub code: Option<i32>,
+    /// Time from spawn to output end
+    pub duration: Duration,
+    /// Last error lines reported in output
+    pub tail: Vec<String>,
+    /// Number of errors reported in build or test output
+    pub errors: u32,
+    /// Number of warnings reported in build or test output
+    pub warnings: u32,
+}
+
+impl TaskOutcome {
+    /// Short summary, e.g. `in 12.3s (7 warnings)` or `(exit 1) (2 errors, 1 warning)`
+    pub fn summary(&self) -> String {
+        let summary = match (self.success, self.code) {
+            (true, _) => format!("in {:.1}s", self.duration.as_secs_f64()),
+            (false, Some(code)) => format!("(exit {code})"),
+            (false, None) => format!("after {:.1}s", self.duration.as_secs_f64()),
+        };
+        let counts = [(self.errors, "error"), (self.warnings, "warning")]
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, kind)| match count {
+                1 => format!("1 {kind}"),
+                n => format!("{n} {kind}s"),
+            })
+            .collect::<Vec<_>>();
+        if counts.is_empty() {
+            summary
+        } else {
+            format!("{summary} ({})", counts.join(", "))
+        }
+    }
+}
+
+/// Last error lines of consumed process output
+#[derive(Debug, Default)]
+struct Tail(VecDeque<String>);
+
+impl Tail {
+    fn push(&mut self, content: &str) {
+        if self.0.len() == TAIL_LINES {
+            self.0.pop_front();
+        }
+        self.0.push_back(content.to_string());
+    }
+
+    fn outcome(&mut self, success: bool, code: Option<i32>, started: Instant) -> TaskOutcome {
+        TaskOutcome {
+            success,
+            code,
+            duration: started.elapsed(),
+            tail: self.0.drain(..).collect(),
+            errors: 0,
+            warnings: 0,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Task {
-    #[allow(dead_code)]
     task: TaskKind,
     #[allow(dead_code)]
     target: String,
     inner: Arc<Broadcast>,
+    /// Errors reported in consumed process output
+    diagnostics: Arc<Mutex<Vec<BuildDiagnostic>>>,
+    /// Whether the task was cancelled by a newer build of the same target
+    cancelled: Arc<AtomicBool>,
+    /// Whether the task process was killed for producing no output within process timeout
+    timed_out: Arc<AtomicBool>,
+    /// Compile commands of Objective-C/C units found in compile task output
+    clang_units: Arc<Mutex<Vec<Value>>>,
+    /// Counts the task as running in its broadcast, until the task and its clones are dropped
+    #[allow(dead_code)]
+    running: Arc<RunningGuard>,
+}
+
+/// Guard decrementing number of running tasks of a broadcast on drop
+#[derive(Debug)]
+struct RunningGuard(Arc<AtomicUsize>);
+
+impl RunningGuard {
+    fn new(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter.clone())
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl Task {
     /// Create a new task with it's kind, the target, and broadcast to send message through
     pub fn new(task: TaskKind, target: &str, broadcast: Arc<Broadcast>) -> Task {
-        broadcast
-            .tx
-            .send((
-                None,
-                Message::SetCurrentTask {
-                    kind: task.clone(),
-                    target: target.into(),
-                    status: TaskStatus::Processing,
-                },
-            ))
-            .ok();
+        broadcast.tx.push(
+            None,
+            Message::SetCurrentTask {
+                kind: task.clone(),
+                target: target.into(),
+                status: TaskStatus::Processing,
+            },
+        );
         Task {
             task,
             target: target.into(),
+            running: Arc::new(RunningGuard::new(&broadcast.running_tasks)),
             inner: broadcast,
+            diagnostics: Default::default(),
+            cancelled: Default::default(),
+            timed_out: Default::default(),
+            clang_units: Default::default(),
         }
     }
 
     fn update<S: AsRef<str>>(&self, level: ContentLevel, content: S) {
         let content = content.as_ref().into();
-        let message = Message::UpdateCurrentTask { content, level };
-        self.inner.tx.send((None, message)).ok();
+        let message = Message::UpdateCurrentTask {
+            content,
+            level,
+            ansi: None,
+        };
+        self.inner.tx.push(None, message);
+    }
+
+    /// Update CurrentTask with a line of process output, along with the line as emitted by the
+    /// process if it had ANSI escape codes
+    fn output(&self, level: ContentLevel, content: String, ansi: Option<String>) {
+        let message = Message::UpdateCurrentTask {
+            content: self.tagged(content),
+            level,
+            ansi: ansi.map(|ansi| self.tagged(ansi)),
+        };
+        self.inner.tx.push(None, message);
     }
 
     /// Update CurrentTask with info and content
@@ -60,62 +177,266 @@ impl Task {
         self.update(ContentLevel::Error, content);
     }
 
+    /// Send progress of compile units, total is taken from previous runs of the same target
+    fn progress(&self, current: u32) {
+        let task = self.target.clone();
+        let message = match self.inner.compile_units(&self.target) {
+            Some(total) if total > 0 => Message::Progress {
+                task,
+                current: current.min(total),
+                total,
+            },
+            _ => Message::IndeterminateProgress { task, current },
+        };
+        self.inner.tx.push(None, message);
+    }
+
+    /// Prefix output with task target while other builds run, keeping merged output readable
+    fn tagged(&self, content: String) -> String {
+        if self.task == TaskKind::Build && self.inner.running_builds() > 1 {
+            format!("[{}] {content}", self.target)
+        } else {
+            content
+        }
+    }
+
     /// Finish task with whether it was successfull or not
     pub fn finish(&self, success: bool) {
         tracing::trace!("Finishing {:?}: success: {success}", self.task);
 
-        self.inner
-            .tx
-            .send((
-                None,
-                Message::FinishCurrentTask {
-                    status: if success {
-                        TaskStatus::Succeeded
-                    } else {
-                        TaskStatus::Failed
-                    },
+        self.inner.tx.push(
+            None,
+            Message::FinishCurrentTask {
+                status: if success {
+                    TaskStatus::Succeeded
+                } else {
+                    TaskStatus::Failed
                 },
-            ))
-            .ok();
+            },
+        );
 
         if !success {
             self.inner.open_logger();
         }
     }
 
-    pub fn consume(&self, mut process: Box<dyn ProcessExt + Send>) -> Result<Receiver<bool>> {
+    pub fn consume(&self, process: Box<dyn ProcessExt + Send>) -> Result<Receiver<bool>> {
+        self.consume_with(process, Formatter::Raw)
+    }
+
+    /// Consume process output, formatting each line with the given formatter
+    pub fn consume_with(
+        &self,
+        process: Box<dyn ProcessExt + Send>,
+        formatter: Formatter,
+    ) -> Result<Receiver<bool>> {
+        let mut recv = self.consume_outcome_with(process, formatter)?;
+        let (send_status, recv_status) = channel(1);
+
+        tokio::spawn(async move {
+            if let Some(outcome) = recv.recv().await {
+                send_status.send(outcome.success).await.ok();
+            }
+        });
+
+        Ok(recv_status)
+    }
+
+    /// Consume process output, receiving exit code, duration and last error lines on completion
+    pub fn consume_outcome(
+        &self,
+        process: Box<dyn ProcessExt + Send>,
+    ) -> Result<Receiver<TaskOutcome>> {
+        self.consume_outcome_with(process, Formatter::Raw)
+    }
+
+    /// Consume process output with the given formatter, receiving [`TaskOutcome`] on completion
+    pub fn consume_outcome_with(
+        &self,
+        mut process: Box<dyn ProcessExt + Send>,
+        formatter: Formatter,
+    ) -> Result<Receiver<TaskOutcome>> {
         let mut stream = process.spawn_and_stream()?;
+        let started = Instant::now();
         let cancel = self.inner.abort.clone();
         let abort = process.aborter().unwrap();
         let this = self.clone();
-        let (send_status, recv_status) = channel(1);
+        let (send_outcome, recv_outcome) = channel(1);
+        let cancel_build: Arc<Notify> = Default::default();
+        // Run tasks stream app output, which may legitimately be silent for long
+        let timeout = match self.task {
+            TaskKind::Run => None,
+            _ => self.inner.process_timeout(),
+        };
+
+        if matches!(self.task, TaskKind::Build | TaskKind::Test) {
+            self.inner.clear_diagnostics(&self.target);
+        }
+
+        if self.task == TaskKind::Build {
+            if let Some(previous) = self.inner.replace_build(&self.target, cancel_build.clone()) {
+                tracing::info!("[{}] Cancelling previous build", self.target);
+                previous.notify_one();
+            }
+        }
 
         tokio::spawn(async move {
+            // Whether output reported errors, as xcodebuild may still exit with 0
+            let mut has_errors = false;
+            let trusts_exit_code = formatter.trusts_exit_code();
+            let mut formatter = FormatterState::new(formatter);
+            // Number of compile units processed so far
+            let mut compiled = 0;
+            // Diagnostics are only sent to clients for build and test tasks
+            let mut parser = matches!(this.task, TaskKind::Build | TaskKind::Test)
+                .then(DiagnosticParser::default);
+            let mut tests = (this.task == TaskKind::Test).then(TestSummaryParser::default);
+            let mut timing = (this.task == TaskKind::Build).then(TimingSummaryParser::default);
+            let mut clang = (this.task == TaskKind::Compile).then(ClangUnitParser::default);
+            // Watchdog, reset on every output so only stalled processes get killed
+            let watchdog = tokio::time::sleep(timeout.unwrap_or_default());
+            tokio::pin!(watchdog);
+            // Last error lines, reported in outcome
+            let mut tail = Tail::default();
             loop {
-                let send_status = send_status.clone();
+                let send_outcome = send_outcome.clone();
                 tokio::select! {
                     _ = cancel.notified() => {
                         abort.notify_one();
-                        send_status.send(false).await.unwrap_or_default();
+                        this.finish(false);
+                        send_outcome.send(tail.outcome(false, None, started)).await.ok();
                         break;
                     },
-                    result = stream.next() => match result {
+                    _ = cancel_build.notified() => {
+                        abort.notify_one();
+                        this.cancelled.store(true, Ordering::SeqCst);
+                        tracing::info!("[{}] Build cancelled", this.target);
+                        this.finish(false);
+                        send_outcome.send(tail.outcome(false, None, started)).await.ok();
+                        break;
+                    },
+                    _ = &mut watchdog, if timeout.is_some() => {
+                        abort.notify_one();
+                        this.timed_out.store(true, Ordering::SeqCst);
+                        let err = Error::Timeout(this.target.clone(), timeout.unwrap_or_default());
+                        tracing::error!("{err}");
+                        this.error(err.to_string());
+                        this.finish(false);
+                        send_outcome.send(tail.outcome(false, None, started)).await.ok();
+                        break;
+                    },
+                    result = async {
+                        // Slow down reading output while clients are behind
+                        this.inner.ready().await;
+                        stream.next().await
+                    } => match result {
                         Some(output) => {
+                            if let Some(timeout) = timeout {
+                                watchdog.as_mut().reset(tokio::time::Instant::now() + timeout);
+                            }
                             if let Some(succ) = output.is_success() {
                                 tracing::trace!("{output:?}");
+                                if let Some(d) = parser.as_mut().and_then(|p| p.finish()) {
+                                    this.inner.diagnostic(&this.target, d);
+                                }
+                                if let Some(phases) = timing.as_mut().and_then(|p| p.finish()) {
+                                    let target = this.target.clone();
+                                    let summary = Message::BuildTimingSummary { target, phases };
+                                    this.inner.send(None, summary);
+                                }
+                                if let Some(unit) = clang.as_mut().and_then(|p| p.finish()) {
+                                    this.clang_units.lock().await.push(unit);
+                                }
+                                let code = match output {
+                                    ProcessItem::Exit(ref code) => code.parse().ok(),
+                                    _ => None,
+                                };
+                                let succ = succ && (trusts_exit_code || !has_errors);
+                                if succ && compiled > 0 {
+                                    this.inner.update_compile_units(&this.target, compiled);
+                                }
                                 this.finish(succ);
-                                send_status.send(succ).await.ok();
+                                let mut outcome = tail.outcome(succ, code, started);
+                                if let Some(parser) = parser.as_ref() {
+                                    outcome.errors = parser.errors();
+                                    outcome.warnings = parser.warnings();
+                                }
+                                send_outcome.send(outcome).await.ok();
                                 break;
                             } else if let ProcessItem::Error(content) = output {
-                                this.error(content)
+                                let (content, ansi) = strip_ansi(content);
+                                if let Some(d) = parser.as_mut().and_then(|p| p.feed(&content)) {
+                                    this.inner.diagnostic(&this.target, d);
+                                }
+                                if let Some(s) = tests.as_mut().and_then(|p| p.feed(&content)) {
+                                    this.inner.send(None, s.into());
+                                }
+                                if let Some(p) = timing.as_mut() {
+                                    p.feed(&content);
+                                }
+                                let level = formatter.stderr_level(&content);
+                                if level == ContentLevel::Error {
+                                    if let Some(d) = BuildDiagnostic::parse(&content) {
+                                        has_errors = true;
+                                        this.diagnostics.lock().await.push(d);
+                                    }
+                                    tail.push(&content);
+                                }
+                                this.output(level, content, ansi)
                             } else if let ProcessItem::Output(content) = output {
-                                if content.to_lowercase().contains("error") {
-                                    this.error(content)
+                                let (content, ansi) = strip_ansi(content);
+                                if let Some(unit) = clang.as_mut().and_then(|p| p.feed(&content)) {
+                                    this.clang_units.lock().await.push(unit);
+                                }
+                                if content.contains(BUILD_FAILED_MARKER) {
+                                    has_errors = true;
+                                }
+                                if let Some(d) = parser.as_mut().and_then(|p| p.feed(&content)) {
+                                    this.inner.diagnostic(&this.target, d);
+                                }
+                                if let Some(s) = tests.as_mut().and_then(|p| p.feed(&content)) {
+                                    this.inner.send(None, s.into());
+                                }
+                                if let Some(p) = timing.as_mut() {
+                                    p.feed(&content);
+                                }
+                                if formatter.is_source_excerpt(&content) {
+                                    this.output(ContentLevel::Info, content, ansi);
+                                    continue;
+                                }
+                                if content.starts_with("CompileSwift ")
+                                    || content.starts_with("CompileC ")
+                                {
+                                    compiled += 1;
+                                    this.progress(compiled);
+                                }
+                                let diagnostic = BuildDiagnostic::parse(&content);
+                                let is_error = diagnostic.is_some();
+                                if let Some(diagnostic) = diagnostic {
+                                    has_errors = true;
+                                    this.diagnostics.lock().await.push(diagnostic);
+                                }
+                                let formatted = match formatter.format(content.clone()) {
+                                    Some(formatted) => formatted,
+                                    None => continue,
+                                };
+                                // Colors are kept only for lines passed through as is
+                                let ansi = match ansi {
+                                    Some(ansi) if formatted == content => Some(ansi),
+                                    Some(ansi) if formatted == content.trim() => {
+                                        Some(ansi.trim().to_string())
+                                    }
+                                    _ => None,
+                                };
+                                let content = formatted;
+                                if is_error || content.to_lowercase().contains("error") {
+                                    tail.push(&content);
+                                    this.output(ContentLevel::Error, content, ansi)
                                 } else if content.to_lowercase().contains("warn") {
-                                    this.warn(content)
+                                    this.output(ContentLevel::Warn, content, ansi)
                                 } else {
                                     if content != "Resolving Packages" {
-                                        this.info(content)
+                                        this.output(ContentLevel::Info, content, ansi)
                                     };
                                 }
                             }
@@ -124,11 +445,115 @@ impl Task {
                     }
                 };
             }
+            this.inner.remove_build(&this.target, &cancel_build);
         });
-        Ok(recv_status)
+        Ok(recv_outcome)
     }
 
     pub fn inner(&self) -> &Broadcast {
         self.inner.as_ref()
     }
+
+    /// Convert a consume outcome receiver into a receiver of the build result.
+    ///
+    /// On success, reports how long it took. On failure, the result carries errors reported in
+    /// the build output, or the exit code when none were reported.
+    pub fn build_result(&self, mut recv: Receiver<TaskOutcome>) -> Receiver<Result<()>> {
+        let this = self.clone();
+        let (send_result, recv_result) = channel(1);
+
+        tokio::spawn(async move {
+            let outcome = recv.recv().await.unwrap_or_default();
+            let target = this.target.clone();
+            let result = if outcome.success {
+                let done = if this.task == TaskKind::Test {
+                    "Tested"
+                } else {
+                    "Built"
+                };
+                this.info(format!("[{target}] {done} {}", outcome.summary()));
+                Ok(())
+            } else if this.cancelled.load(Ordering::SeqCst) {
+                Err(Error::Build(format!("{target} build cancelled")))
+            } else if this.timed_out.load(Ordering::SeqCst) {
+                let timeout = this.inner.process_timeout().unwrap_or_default();
+                Err(Error::Timeout(target, timeout))
+            } else {
+                let diagnostics = this.diagnostics().await;
+                match diagnostics.iter().find(|d| d.is_code_signing()) {
+                    Some(d) => Err(Error::CodeSigning(target, d.message.clone())),
+                    None if diagnostics.is_empty() && outcome.code.is_some() => Err(Error::Build(
+                        format!("{target} failed {}", outcome.summary()),
+                    )),
+                    None => Err(Error::BuildFailed(target, diagnostics)),
+                }
+            };
+            send_result.send(result).await.ok();
+        });
+
+        recv_result
+    }
+
+    /// Get errors reported so far in consumed process output
+    pub async fn diagnostics(&self) -> Vec<BuildDiagnostic> {
+        self.diagnostics.lock().await.clone()
+    }
+
+    /// Get compile commands of Objective-C/C units found so far in consumed process output
+    pub async fn clang_units(&self) -> Vec<Value> {
+        self.clang_units.lock().await.clone()
+    }
+}
+
+#[test]
+fn test_task_outcome_summary() {
+    let outcome = TaskOutcome {
+        success: true,
+        duration: Duration::from_millis(12_340),
+        warnings: 7,
+        ..Default::default()
+    };
+    assert_eq!(outcome.summary(), "in 12.3s (7 warnings)");
+
+    let outcome = TaskOutcome {
+        code: Some(65),
+        ..Default::default()
+    };
+    assert_eq!(outcome.summary(), "(exit 65)");
+
+    let outcome = TaskOutcome {
+        code: Some(1),
+        errors: 2,
+        warnings: 1,
+        ..Default::default()
+    };
+    assert_eq!(outcome.summary(), "(exit 1) (2 errors, 1 warning)");
+}
+
+#[test]
+fn test_task_outcome_summary_killed() {
+    let outcome = TaskOutcome {
+        duration: Duration::from_secs(600),
+        errors: 1,
+        ..Default::default()
+    };
+    assert_eq!(outcome.summary(), "after 600.0s (1 error)");
+}
+
+#[test]
+fn test_tail_keeps_last_lines() {
+    let mut tail = Tail::default();
+    for line in 0..TAIL_LINES + 5 {
+        tail.push(&format!("error {line}"));
+    }
+
+    let outcome = tail.outcome(false, Some(1), Instant::now());
+    assert_eq!(outcome.tail.len(), TAIL_LINES);
+    assert_eq!(outcome.tail.first().unwrap(), "error 5");
+    assert_eq!(
+        outcome.tail.last().unwrap(),
+        &format!("error {}", TAIL_LINES + 4)
+    );
+    assert_eq!(outcome.code, Some(1));
+    assert!(tail.outcome(true, None, Instant::now()).tail.is_empty());
 }
@@ -0,0 +1,154 @@
+use super::Message;
+
+/// Summary of an XCTest suite run
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestSummary {
+    /// Test suite name
+    pub suite: String,
+    /// Number of passed tests
+    pub passed: u32,
+    /// Number of failed tests
+    pub failed: u32,
+    /// Duration in seconds
+    pub duration: f64,
+}
+
+impl From<TestSummary> for Message {
+    fn from(summary: TestSummary) -> Self {
+        let TestSummary {
+            suite,
+            passed,
+            failed,
+            duration,
+        } = summary;
+        Message::TestResult {
+            suite,
+            passed,
+            failed,
+            duration,
+        }
+    }
+}
+
+/// Parser of XCTest suite summaries, i.e.
+///
+/// ```text
+/// Test Suite 'AppTests' failed at 2023-01-01 10:00:00.000.
+///      Executed 3 tests, with 1 failure (0 unexpected) in 0.005 (0.006) seconds
+/// ```
+///
+/// Only summaries of suites running test cases are reported, as the suites containing them
+/// (e.g. `All tests` or `AppTests.xctest`) would count the same tests again.
+#[derive(Debug, Default)]
+pub struct TestSummaryParser {
+    /// Started suites, along with whether they ran test cases themselves
+    suites: Vec<(String, bool)>,
+    /// Finished suite whose summary is expected next
+    suite: Option<String>,
+}
+
+impl TestSummaryParser {
+    /// Feed a line of output, returns suite summary once it's complete
+    pub fn feed(&mut self, line: &str) -> Option<TestSummary> {
+        let line = line.trim();
+
+        if let Some(rest) = line.strip_prefix("Test Suite '") {
+            let (suite, status) = rest.split_once("' ")?;
+            if status.starts_with("started") {
+                self.suites.push((suite.to_string(), false));
+            } else if status.starts_with("passed") || status.starts_with("failed") {
+                let position = self.suites.iter().rposition(|(name, _)| name == suite);
+                let has_cases = match position {
+                    Some(position) => self.suites.drain(position..).next()?.1,
+                    // Suite start wasn't seen, e.g. output was consumed midway
+                    None => true,
+                };
+                self.suite = has_cases.then(|| suite.to_string());
+            }
+            return None;
+        }
+
+        if line.starts_with("Test Case '") {
+            if let Some((_, has_cases)) = self.suites.last_mut() {
+                *has_cases = true;
+            }
+            return None;
+        }
+
+        let rest = line.strip_prefix("Executed ")?;
+        let suite = self.suite.take()?;
+        let (executed, rest) = rest.split_once(' ')?;
+        let executed: u32 = executed.parse().ok()?;
+        let (_, rest) = rest.split_once("with ")?;
+        let (failed, rest) = rest.split_once(' ')?;
+        let failed: u32 = failed.parse().ok()?;
+        let (_, rest) = rest.split_once(" in ")?;
+        let duration = rest.split_whitespace().next()?.parse().ok()?;
+
+        Some(TestSummary {
+            suite,
+            passed: executed.saturating_sub(failed),
+            failed,
+            duration,
+        })
+    }
+}
+
+#[test]
+fn test_parse_test_summary() {
+    let output = [
+        "Test Suite 'AppTests' started at 2023-01-01 10:00:00.000",
+        "Test Case '-[AppTests.AppTests testFoo]' passed (0.001 seconds).",
+        "Test Suite 'AppTests' failed at 2023-01-01 10:00:00.010.",
+        "\t Executed 3 tests, with 1 failure (0 unexpected) in 0.005 (0.006) seconds",
+        "Executed 3 tests, with 1 failure (0 unexpected) in 0.005 (0.006) seconds",
+    ];
+
+    let mut parser = TestSummaryParser::default();
+    let summaries = output
+        .iter()
+        .flat_map(|line| parser.feed(line))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        summaries,
+        vec![TestSummary {
+            suite: "AppTests".into(),
+            passed: 2,
+            failed: 1,
+            duration: 0.005,
+        }]
+    );
+}
+
+#[test]
+fn test_parse_nested_test_summaries() {
+    let output = [
+        "Test Suite 'All tests' started at 2023-01-01 10:00:00.000",
+        "Test Suite 'AppTests.xctest' started at 2023-01-01 10:00:00.000",
+        "Test Suite 'AppTests' started at 2023-01-01 10:00:00.000",
+        "Test Case '-[AppTests.AppTests testFoo]' passed (0.001 seconds).",
+        "Test Suite 'AppTests' passed at 2023-01-01 10:00:00.001.",
+        "\t Executed 1 test, with 0 failures (0 unexpected) in 0.001 (0.001) seconds",
+        "Test Suite 'ModelTests' started at 2023-01-01 10:00:00.001",
+        "Test Case '-[AppTests.ModelTests testBar]' failed (0.002 seconds).",
+        "Test Suite 'ModelTests' failed at 2023-01-01 10:00:00.003.",
+        "\t Executed 1 test, with 1 failure (0 unexpected) in 0.002 (0.002) seconds",
+        "Test Suite 'AppTests.xctest' failed at 2023-01-01 10:00:00.003.",
+        "\t Executed 2 tests, with 1 failure (0 unexpected) in 0.003 (0.003) seconds",
+        "Test Suite 'All tests' failed at 2023-01-01 10:00:00.003.",
+        "\t Executed 2 tests, with 1 failure (0 unexpected) in 0.003 (0.004) seconds",
+    ];
+
+    let mut parser = TestSummaryParser::default();
+    let summaries = output
+        .iter()
+        .flat_map(|line| parser.feed(line))
+        .map(|summary| (summary.suite, summary.passed, summary.failed))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        summaries,
+        vec![("AppTests".into(), 1, 0), ("ModelTests".into(), 0, 1)]
+    );
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+/// Time spent in a build phase, e.g. `CompileSwiftSources`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeDef)]
+pub struct BuildPhaseTiming {
+    /// Build phase name
+    pub phase: String,
+    /// Number of tasks ran in phase
+    pub tasks: u32,
+    /// Duration in seconds
+    pub duration: f64,
+}
+
+/// Parser of the timing summary xcodebuild prints with `-showBuildTimingSummary`, i.e.
+///
+/// ```text
+/// Build Timing Summary
+///
+/// CompileSwiftSources (1 task) | 5.123 seconds
+///
+/// Ld (1 task) | 0.321 seconds
+/// ```
+#[derive(Debug, Default)]
+pub struct TimingSummaryParser {
+    in_summary: bool,
+    phases: Vec<BuildPhaseTiming>,
+}
+
+impl TimingSummaryParser {
+    /// Feed a line of output
+    pub fn feed(&mut self, line: &str) {
+        let line = line.trim();
+
+        if line == "Build Timing Summary" {
+            self.in_summary = true;
+            self.phases.clear();
+            return;
+        }
+
+        if !self.in_summary || line.is_empty() {
+            return;
+        }
+
+        match Self::parse_phase(line) {
+            Some(phase) => self.phases.push(phase),
+            None => self.in_summary = false,
+        }
+    }
+
+    /// Take parsed phases timing, if a timing summary was printed
+    pub fn finish(&mut self) -> Option<Vec<BuildPhaseTiming>> {
+        self.in_summary = false;
+        if self.phases.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.phases))
+        }
+    }
+
+    fn parse_phase(line: &str) -> Option<BuildPhaseTiming> {
+        let (phase, duration) = line.split_once(" | ")?;
+        let (phase, tasks) = phase.rsplit_once(" (")?;
+        let tasks = tasks.split_whitespace().next()?.parse().ok()?;
+        let duration = duration.strip_suffix(" seconds")?.parse().ok()?;
+
+        Some(BuildPhaseTiming {
+            phase: phase.to_string(),
+            tasks,
+            duration,
+        })
+    }
+}
+
+#[test]
+fn test_parse_timing_summary() {
+    let output = [
+        "** BUILD SUCCEEDED ** [10.234 sec]",
+        "",
+        "Build Timing Summary",
+        "",
+        "CompileSwiftSources (1 task) | 5.123 seconds",
+        "",
+        "CompileC (12 tasks) | 3.456 seconds",
+        "",
+        "Ld (1 task) | 0.321 seconds",
+        "",
+        "Touch /tmp/Build/Debug/Demo.app (in target 'Demo' from project 'Demo')",
+    ];
+
+    let mut parser = TimingSummaryParser::default();
+    output.iter().for_each(|line| parser.feed(line));
+
+    let phase = |phase: &str, tasks, duration| BuildPhaseTiming {
+        phase: phase.into(),
+        tasks,
+        duration,
+    };
+    assert_eq!(
+        parser.finish(),
+        Some(vec![
+            phase("CompileSwiftSources", 1, 5.123),
+            phase("CompileC", 12, 3.456),
+            phase("Ld", 1, 0.321),
+        ])
+    );
+    assert_eq!(parser.finish(), None);
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use crate::BuildDiagnostic;
 use serde::{Deserialize, Serialize};
 use thiserror::Error as ThisError;
 use typescript_type_def::TypeDef;
@@ -9,6 +10,39 @@ use typescript_type_def::TypeDef;
 pub struct ServerError {
     kind: String,
     msg: String,
+    #[serde(default)]
+    code: ErrorCode,
+}
+
+/// Stable machine-readable error code, for clients to react to specific errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, TypeDef)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    SetupFailed,
+    NotFound,
+    BuildFailed,
+    CodeSigningFailed,
+    RunFailed,
+    GenerateFailed,
+    GeneratorMissing,
+    GeneratorIncompatible,
+    PodInstallFailed,
+    Timeout,
+    CompileFailed,
+    DefinitionParsing,
+    NoDefinition,
+    MultipleDefinitions,
+    NoProject,
+    Internal,
+    MessageParse,
+    AlreadyRunning,
+    Unexpected,
+}
+
+impl Default for ErrorCode {
+    fn default() -> Self {
+        Self::Unexpected
+    }
 }
 
 #[derive(ThisError, Debug)]
@@ -20,10 +54,34 @@ pub enum Error {
     Lookup(String, String),
     #[error("Failed to build target/scheme: {0}")]
     Build(String),
+    #[error("Failed to build {0}: {} error(s)", .1.len())]
+    /// Target, Reported errors
+    BuildFailed(String, Vec<BuildDiagnostic>),
+    #[error("Code signing failed for {0}: {1}")]
+    /// Target, Message
+    CodeSigning(String, String),
     #[error("Failed to run target/scheme: {0}")]
     Run(String),
     #[error("Failed to generate project definition")]
     Generate,
+    #[error("`{name}` is not installed or not in PATH, install it with `{install_hint}`")]
+    GeneratorMissing {
+        /// Generator executable name
+        name: String,
+        /// Command to install the generator with
+        install_hint: String,
+    },
+    #[error("Installed `{0}` version is incompatible with the project, upgrade it: {1}")]
+    /// Generator executable name, Reported error
+    GeneratorIncompatible(String, String),
+    #[error("{0}")]
+    PodInstall(String),
+    #[error("Project load hook `{0}` failed {1}")]
+    /// Command, Outcome summary
+    LoadHook(String, String),
+    #[error("{0} produced no output for {}s and was killed", .1.as_secs())]
+    /// Task target, Timeout
+    Timeout(String, std::time::Duration),
     #[error("Failed to generate compile commands")]
     Compile,
     #[error("Failed to parse project definition: {0}")]
@@ -42,15 +100,27 @@ pub enum Error {
     MessageParse(String),
     #[error("{0} is not a registered project!")]
     UnknownProject(PathBuf),
+    #[error("No project found in {0} or any of its parents")]
+    NoProjectRoot(PathBuf),
+    #[error("No log retained for {0}, enable `log_file` in project config to persist logs")]
+    NoLog(PathBuf),
+    #[error("Another xbase instance is already broadcasting on {0}")]
+    AlreadyRunning(PathBuf),
+    #[error("No compile command found for {0}, regenerate compile commands if it was just added")]
+    NoCompileCommand(PathBuf),
+    #[error("Can't relocate project to {0}: {1}")]
+    /// New root, Reason
+    Relocate(PathBuf, String),
 }
 
 impl From<ServerError> for Error {
     fn from(v: ServerError) -> Self {
         match v.kind.as_str() {
             "Setup" => Self::Setup("".into(), v.msg),
-            "Build" => Self::Build(v.msg),
+            "Build" | "BuildFailed" | "CodeSigning" => Self::Build(v.msg),
             "Run" => Self::Run(v.msg),
             "Generate" => Self::Generate,
+            "PodInstall" => Self::PodInstall(v.msg),
             "DefinitionParsing" => Self::DefinitionParsing(v.msg),
             "DefinitionLocating" => Self::DefinitionLocating,
             "DefinitionMutliFound" => Self::DefinitionMutliFound,
@@ -66,13 +136,21 @@ impl From<&Error> for ServerError {
         let mut res = ServerError {
             kind: Default::default(),
             msg: err.to_string(),
+            code: err.code(),
         };
         match err {
             Error::Setup(_, _) => res.kind = "Setup".into(),
             Error::Lookup(_, _) => res.kind = "Lookup".into(),
             Error::Build(_) => res.kind = "Build".into(),
+            Error::BuildFailed(_, _) => res.kind = "BuildFailed".into(),
+            Error::CodeSigning(_, _) => res.kind = "CodeSigning".into(),
             Error::Run(_) => res.kind = "Run".into(),
             Error::Generate => res.kind = "Generate".into(),
+            Error::GeneratorMissing { .. } => res.kind = "GeneratorMissing".into(),
+            Error::GeneratorIncompatible(_, _) => res.kind = "GeneratorIncompatible".into(),
+            Error::PodInstall(_) => res.kind = "PodInstall".into(),
+            Error::LoadHook(_, _) => res.kind = "LoadHook".into(),
+            Error::Timeout(_, _) => res.kind = "Timeout".into(),
             Error::DefinitionParsing(_) => res.kind = "DefinitionParsing".into(),
             Error::DefinitionLocating => res.kind = "DefinitionLocating".into(),
             Error::DefinitionMutliFound => res.kind = "DefinitionMutliFound".into(),
@@ -82,11 +160,60 @@ impl From<&Error> for ServerError {
             Error::MessageParse(_) => res.kind = "MessageParse".into(),
             Error::Compile => res.kind = "Compile".into(),
             Error::UnknownProject(_) => res.kind = "UnknownProject".into(),
+            Error::NoProjectRoot(_) => res.kind = "NoProjectRoot".into(),
+            Error::NoLog(_) => res.kind = "NoLog".into(),
+            Error::AlreadyRunning(_) => res.kind = "AlreadyRunning".into(),
+            Error::NoCompileCommand(_) => res.kind = "NoCompileCommand".into(),
+            Error::Relocate(_, _) => res.kind = "Relocate".into(),
         };
         res
     }
 }
 
+impl Error {
+    /// Error for a generator/package manager that isn't installed, with a hint on installing it
+    pub fn generator_missing(name: &str) -> Self {
+        let install_hint = match name {
+            "pod" => "brew install cocoapods".into(),
+            "bazel" => "brew install bazelisk".into(),
+            name => format!("brew install {name}"),
+        };
+        Self::GeneratorMissing {
+            name: name.into(),
+            install_hint,
+        }
+    }
+
+    /// Get error code
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::Setup(_, _) | Error::LoadHook(_, _) | Error::Relocate(_, _) => {
+                ErrorCode::SetupFailed
+            }
+            Error::Lookup(_, _) | Error::NoLog(_) | Error::NoCompileCommand(_) => {
+                ErrorCode::NotFound
+            }
+            Error::Build(_) | Error::BuildFailed(_, _) => ErrorCode::BuildFailed,
+            Error::CodeSigning(_, _) => ErrorCode::CodeSigningFailed,
+            Error::Run(_) => ErrorCode::RunFailed,
+            Error::Generate => ErrorCode::GenerateFailed,
+            Error::GeneratorMissing { .. } => ErrorCode::GeneratorMissing,
+            Error::GeneratorIncompatible(_, _) => ErrorCode::GeneratorIncompatible,
+            Error::PodInstall(_) => ErrorCode::PodInstallFailed,
+            Error::Timeout(_, _) => ErrorCode::Timeout,
+            Error::Compile => ErrorCode::CompileFailed,
+            Error::DefinitionParsing(_) => ErrorCode::DefinitionParsing,
+            Error::DefinitionLocating | Error::NoProjectRoot(_) => ErrorCode::NoDefinition,
+            Error::DefinitionMutliFound => ErrorCode::MultipleDefinitions,
+            Error::UnknownProject(_) => ErrorCode::NoProject,
+            Error::JoinError(_) | Error::SendError(_) => ErrorCode::Internal,
+            Error::MessageParse(_) => ErrorCode::MessageParse,
+            Error::AlreadyRunning(_) => ErrorCode::AlreadyRunning,
+            Error::Unexpected(_) => ErrorCode::Unexpected,
+        }
+    }
+}
+
 impl Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
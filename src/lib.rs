@@ -26,6 +26,48 @@ pub type ProjectRuntimes = HashMap<PathBuf, PRMessageSender>;
 
 static RUNTIMES: Lazy<Arc<Mutex<ProjectRuntimes>>> = Lazy::new(Default::default);
 
+/// Daemon start time, set on first access, i.e. on startup
+pub static STARTED_AT: Lazy<std::time::Instant> = Lazy::new(std::time::Instant::now);
+
+/// Max time to wait for each broadcast server to stop on shutdown
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Shutdown project runtimes, aborting running tasks and removing broadcast sockets
+pub async fn shutdown() {
+    let (broadcasters, addresses): (Vec<_>, Vec<_>) = runtimes()
+        .await
+        .values()
+        .map(|runtime| (runtime.broadcaster(), runtime.broadcaster_adderss().clone()))
+        .unzip();
+    let broadcasters = broadcasters.into_iter().flatten().collect::<Vec<_>>();
+
+    tracing::info!("Shutting down {} runtime(s)", broadcasters.len());
+
+    let shutdowns = broadcasters.iter().map(|b| b.shutdown(SHUTDOWN_TIMEOUT));
+    futures::future::join_all(shutdowns).await;
+
+    // Also remove sockets of runtimes whose broadcaster was dropped, keeping ones of other daemons
+    for address in addresses {
+        tokio::fs::remove_file(&address).await.ok();
+    }
+}
+
+/// Get time since the daemon started
+pub fn uptime() -> std::time::Duration {
+    STARTED_AT.elapsed()
+}
+
+/// Disconnect clients that exited without dropping their roots, e.g. crashed editors.
+///
+/// Runtimes left without clients close themselves, stopping their watchers and runs.
+pub async fn reap_dead_clients() {
+    for runtime in runtimes().await.values_mut() {
+        for id in runtime.disconnect_dead() {
+            tracing::info!("[{}] Reaped dead client {id}", runtime.name());
+        }
+    }
+}
+
 /// Get OwnedMutexGuard of runtimes
 #[tracing::instrument(name = "Runtimes")]
 pub async fn runtimes() -> OwnedMutexGuard<ProjectRuntimes> {
@@ -9,13 +9,20 @@ use tracing::info;
 use tracing_setup::setup as tracing_setup;
 use xbase::*;
 
+/// Interval to check whether registered clients are still alive
+const REAP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
 #[tokio::main]
 // TODO: store futures somewhere, to gracefully close connection to clients
 async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     let os_signal_handler = tokio::spawn(handle_os_signals());
+    once_cell::sync::Lazy::force(&STARTED_AT);
 
     let listener = {
         tracing_setup(LOG_PATH, tracing::Level::DEBUG, true)?;
+        if let Err(err) = xcodebuild::setup() {
+            tracing::error!("{err}");
+        }
         cleanup_daemon_runtime(PID_PATH, SOCK_ADDR).await?;
         write(PID_PATH, std::process::id().to_string()).await?;
         UnixListener::bind(SOCK_ADDR).unwrap()
@@ -24,15 +31,20 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     pin!(os_signal_handler);
     info!("SERVER STARTED");
 
+    let mut reap = tokio::time::interval(REAP_INTERVAL);
+
     loop {
         select! {
             Ok((stream, _)) = listener.accept() => tokio::spawn(server::handle(stream)),
+            _ = reap.tick() => tokio::spawn(reap_dead_clients()),
             _ = &mut os_signal_handler => break,
         };
     }
 
     drop(listener);
 
+    shutdown().await;
+
     cleanup_daemon_runtime(PID_PATH, SOCK_ADDR).await?;
 
     Ok(())
@@ -15,7 +15,12 @@ pub struct BareboneProject {
     num_clients: i32,
     watchignore: Vec<String>,
     #[serde(skip)]
-    xcodeproj: XCodeProject,
+    xcodeproj: Arc<XCodeProject>,
+    #[serde(skip)]
+    xcodeproj_path: PathBuf,
+    /// DerivedData path set in project config
+    #[serde(skip)]
+    derived_data: Option<PathBuf>,
 }
 
 impl ProjectData for BareboneProject {
@@ -42,6 +47,18 @@ impl ProjectData for BareboneProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn derived_data(&self) -> Option<&PathBuf> {
+        self.derived_data.as_ref()
+    }
+
+    fn xcodeproj_path(&self) -> Option<&PathBuf> {
+        Some(&self.xcodeproj_path)
+    }
+
+    fn xcodeproj(&self) -> Option<&Arc<XCodeProject>> {
+        Some(&self.xcodeproj)
+    }
 }
 
 #[async_trait::async_trait]
@@ -74,18 +91,20 @@ impl ProjectCompile for BareboneProject {
                     "-scheme".into(),
                     scheme.name.clone(),
                 ]);
-                let xclogger = XCLogger::new(&root, &args)?;
+                let mut xclogger = XCLogger::new(&root, &args)?;
+                xcodebuild::set_envs(&mut xclogger);
                 xccommands.push(xclogger.compile_commands.clone());
                 tasks_recvs.push(task.consume(Box::new(xclogger))?);
             }
         } else {
             args.extend_from_slice(&["-project".into(), format!("{name}.xcodeproj")]);
-            let xclogger = XCLogger::new(&root, &args)?;
+            let mut xclogger = XCLogger::new(&root, &args)?;
+            xcodebuild::set_envs(&mut xclogger);
             xccommands.push(xclogger.compile_commands.clone());
             tasks_recvs.push(task.consume(Box::new(xclogger))?);
         }
 
-        let _all_pass = tasks_recvs
+        let all_pass = tasks_recvs
             .into_iter()
             .map(|mut t| tokio::spawn(async move { t.recv().await.unwrap_or_default() }))
             .pipe(try_join_all)
@@ -105,13 +124,15 @@ impl ProjectCompile for BareboneProject {
             .collect::<Vec<_>>();
 
         xccommands.dedup();
-        if xccommands.is_empty() {
+        let units = task.clang_units().await;
+        if xccommands.is_empty() && units.is_empty() {
             broadcast.warn("No compile command was generated!");
         }
 
-        let json = serde_json::to_string_pretty(&xccommands)?
+        let json = String::from_utf8_lossy(&compile_database(&xccommands, units)?)
             .replace("-use-frontend-parseable-output", "");
-        tokio::fs::write(root.join(".compile"), &json).await?;
+        self.replace_compile_database(all_pass, json.into_bytes(), broadcast)
+            .await?;
 
         Ok(())
     }
@@ -134,35 +155,123 @@ impl ProjectGenerate for BareboneProject {
 
 #[async_trait::async_trait]
 impl Project for BareboneProject {
-    async fn new(root: &PathBuf, _logger: &Arc<Broadcast>) -> Result<Self> {
+    async fn new(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<Self> {
         let mut project = Self {
             root: root.clone(),
             watchignore: generate_watchignore(root).await,
             num_clients: 1,
+            derived_data: derived_data_path(root, broadcast).await,
             ..Self::default()
         };
 
         let xcodeproj_paths = project.get_xcodeproj_paths()?;
-        if xcodeproj_paths.len() > 1 {
-            tracing::warn!(
-                "Found more then on xcodeproj, using {:?}",
-                xcodeproj_paths[0]
-            );
-        }
+        let xcodeproj_path = select_xcodeproj(root, xcodeproj_paths, broadcast)
+            .await
+            .ok_or(Error::DefinitionLocating)?;
 
-        if xcodeproj_paths.is_empty() {
-            return Err(Error::DefinitionLocating);
-        };
-
-        project.xcodeproj = XCodeProject::new(&xcodeproj_paths[0])?;
-        project.targets = project
-            .xcodeproj
-            .targets_info()
-            .into_iter()
-            .map(|(k, info)| (k, info.into()))
-            .collect();
+        project.xcodeproj = cache::xcodeproj(&xcodeproj_path)?;
+        project.xcodeproj_path = xcodeproj_path;
+        project.targets = xcodeproj_targets(&project.xcodeproj);
 
         tracing::info!("targets: {:?}", project.targets());
         Ok(project)
     }
+
+    async fn reload(&mut self, broadcast: &Arc<Broadcast>) -> Result<bool> {
+        self.xcodeproj = cache::xcodeproj(&self.xcodeproj_path)?;
+        let name = self.name().to_string();
+        Ok(reload_targets(
+            &name,
+            &mut self.targets,
+            &self.xcodeproj,
+            broadcast,
+        ))
+    }
+}
+
+#[test]
+fn test_build_args() {
+    let project = BareboneProject {
+        root: PathBuf::from("/tmp/Demo"),
+        targets: HashMap::from([(
+            "App".to_string(),
+            TargetInfo {
+                platform: "iOS".into(),
+                configurations: vec!["Debug".into()],
+                kind: BuildableKind::Target,
+                watching: false,
+                product_type: None,
+                bundle_id: None,
+            },
+        )]),
+        ..BareboneProject::default()
+    };
+    let cfg = BuildSettings {
+        target: "App".into(),
+        configuration: Some("Debug".into()),
+        scheme: None,
+        sdk: None,
+        destination: None,
+        xcconfig: None,
+        overrides: vec![],
+    };
+
+    let args = project.args_for(&cfg, None).unwrap();
+    let symroot = fs::get_build_cache_dir_with_config("/tmp/Demo", &cfg).unwrap();
+    let xcodeproj = format!("{}.xcodeproj", project.name());
+
+    assert_eq!(
+        args[..5],
+        ["build", "-configuration", "Debug", "-target", "App"]
+    );
+    assert!(args.contains(&format!("SYMROOT={symroot}")));
+    assert_eq!(
+        args[args.len() - 4..],
+        ["-project", &xcodeproj, "-sdk", "iphonesimulator"]
+    );
+
+    let overridden = BuildSettings {
+        overrides: vec![("ONLY_ACTIVE_ARCH".into(), "YES".into())],
+        ..cfg.clone()
+    };
+    let args = project.args_for(&overridden, None).unwrap();
+    let signing = args.iter().position(|arg| arg == "CODE_SIGNING_ALLOWED=NO");
+    let overrides = args.iter().position(|arg| arg == "ONLY_ACTIVE_ARCH=YES");
+    assert!(signing.is_some() && signing < overrides);
+
+    // User signing settings apply to physical devices, instead of signing being disabled
+    let iphone = Destination::Physical(PhysicalDevice {
+        udid: "00008110-000A1C2E3C41801E".into(),
+        name: "iPhone".into(),
+        platform: xcodeproj::pbxproj::PBXTargetPlatform::IOS,
+    });
+    let signed = BuildSettings {
+        overrides: vec![("CODE_SIGN_STYLE".into(), "Automatic".into())],
+        ..cfg.clone()
+    };
+    let args = project.args_for(&signed, Some(&iphone)).unwrap();
+    assert!(!args.iter().any(|arg| arg.starts_with("CODE_SIGNING_")));
+    assert!(args.contains(&"CODE_SIGN_STYLE=Automatic".to_string()));
+    assert_eq!(
+        args[args.len() - 2..],
+        ["-destination", "id=00008110-000A1C2E3C41801E"]
+    );
+
+    let injected = BuildSettings {
+        overrides: vec![("-scheme App ONLY_ACTIVE_ARCH".into(), "YES".into())],
+        ..cfg.clone()
+    };
+    let err = project.args_for(&injected, None).unwrap_err();
+    assert!(err
+        .to_string()
+        .ends_with("Invalid build setting name `-scheme App ONLY_ACTIVE_ARCH`"));
+
+    let staging = BuildSettings {
+        configuration: Some("Staging".into()),
+        ..cfg
+    };
+    let err = project.args_for(&staging, None).unwrap_err();
+    assert!(err
+        .to_string()
+        .ends_with("Unknown configuration `Staging` for App, available: Debug"));
 }
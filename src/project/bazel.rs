@@ -0,0 +1,556 @@
+use super::*;
+use crate::util::fs::which;
+use crate::watcher::Event;
+use crate::{Error, Result};
+use process_stream::Process;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use xcodeproj::pbxproj::PBXTargetPlatform;
+
+#[derive(Debug, Serialize, Default)]
+#[serde(default)]
+pub struct BazelProject {
+    name: String,
+    root: PathBuf,
+    targets: HashMap<String, TargetInfo>,
+    num_clients: i32,
+    watchignore: Vec<String>,
+}
+
+impl ProjectData for BazelProject {
+    fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn targets(&self) -> &HashMap<String, TargetInfo> {
+        &self.targets
+    }
+
+    fn clients(&self) -> &i32 {
+        &self.num_clients
+    }
+
+    fn clients_mut(&mut self) -> &mut i32 {
+        &mut self.num_clients
+    }
+
+    fn watchignore(&self) -> &Vec<String> {
+        &self.watchignore
+    }
+
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::Bazel
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectBuild for BazelProject {
+    fn build(
+        &self,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+        flags: &[String],
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<Result<()>>)> {
+        let mut args = self.args_for(cfg, device)?;
+        args.extend(Self::extra_flags(flags));
+
+        let mut process = Process::new(Self::bazel()?);
+        process.args(&args);
+        process.current_dir(self.root());
+
+        let task = Task::new(TaskKind::Build, cfg.target.as_str(), broadcast.clone());
+        task.debug(format!(
+            "[{}] bazel {}",
+            cfg.target,
+            shell_words::join(&args)
+        ));
+        let recv = task.consume_outcome(Box::new(process))?;
+
+        Ok((args, task.build_result(recv)))
+    }
+
+    fn args_for(&self, cfg: &BuildSettings, device: Option<&Destination>) -> Result<Vec<String>> {
+        self.validate_configuration(cfg)?;
+
+        let mut args = vec!["build".to_string(), cfg.target.clone()];
+        args.extend(Self::build_flags(cfg, device));
+        Ok(args)
+    }
+
+    async fn clean(
+        &self,
+        cfg: &BuildSettings,
+        deep: bool,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<()> {
+        let target = &cfg.target;
+        let task = Task::new(TaskKind::Clean, target, broadcast.clone());
+
+        if deep {
+            task.info(format!("[{target}] Removing build cache"));
+            self.remove_build_cache().await?;
+        }
+
+        let mut process = Process::new(Self::bazel()?);
+        process.arg("clean");
+        if deep {
+            process.arg("--expunge");
+        }
+        process.current_dir(self.root());
+
+        let success = task.consume(Box::new(process))?.recv().await;
+        if success.unwrap_or_default() {
+            Ok(())
+        } else {
+            Err(Error::Build(format!("Failed to clean {target}")))
+        }
+    }
+
+    /// Run tests with `bazel test`, target is expected to be a test rule label
+    fn test(
+        &self,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+        only_testing: &[String],
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<tokio::sync::mpsc::Receiver<Result<()>>> {
+        let mut args = vec!["test".to_string(), cfg.target.clone()];
+        args.extend(Self::build_flags(cfg, device));
+        args.push("--test_output=all".into());
+        if !only_testing.is_empty() {
+            args.push(format!("--test_filter={}", only_testing.join(",")));
+        }
+
+        let mut process = Process::new(Self::bazel()?);
+        process.args(&args);
+        process.current_dir(self.root());
+
+        let task = Task::new(TaskKind::Test, cfg.target.as_str(), broadcast.clone());
+        task.debug(format!(
+            "[{}] bazel {}",
+            cfg.target,
+            shell_words::join(&args)
+        ));
+        let recv = task.consume_outcome(Box::new(process))?;
+
+        Ok(task.build_result(recv))
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectRun for BazelProject {
+    async fn get_runner(
+        &self,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+        build: bool,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<(
+        Box<dyn Runner + Send + Sync>,
+        Vec<String>,
+        tokio::sync::mpsc::Receiver<Result<()>>,
+    )> {
+        let (args, recv) = if build {
+            self.build(cfg, device, &[], broadcast)?
+        } else {
+            (self.args_for(cfg, device)?, built())
+        };
+        let runner = Box::new(BazelRunner {
+            root: self.root().clone(),
+            cfg: cfg.clone(),
+            device: device.cloned(),
+            inner: Default::default(),
+        });
+
+        Ok((runner, args, recv))
+    }
+}
+
+/// Runner of a bazel target, getting the target output and bundle id on first run.
+///
+/// Bazel queries wait for the bazel server, which is busy until the target is built.
+struct BazelRunner {
+    root: PathBuf,
+    cfg: BuildSettings,
+    device: Option<Destination>,
+    inner: tokio::sync::OnceCell<Box<dyn Runner + Send + Sync>>,
+}
+
+impl BazelRunner {
+    /// Get runner of the built target output
+    async fn inner(&self) -> Result<&(dyn Runner + Send + Sync)> {
+        let Self { root, cfg, .. } = self;
+        let runner = self.inner.get_or_try_init(|| async {
+            let output_path = BazelProject::output_path(root, cfg, self.device.as_ref()).await?;
+            tracing::info!("Running {:?} via {output_path:?}", cfg.target);
+
+            let runner: Box<dyn Runner + Send + Sync> = match self.device.as_ref() {
+                Some(Destination::Simulator(device)) => Box::new(SimulatorRunner {
+                    device: device.clone(),
+                    app_id: BazelProject::bundle_id(root, &cfg.target).await?,
+                    output_dir: output_path,
+                }),
+                Some(Destination::Physical(device)) => Box::new(PhysicalRunner {
+                    device: device.clone(),
+                    app_id: BazelProject::bundle_id(root, &cfg.target).await?,
+                    output_dir: output_path,
+                }),
+                None => Box::new(BinRunner::from_path(&output_path)),
+            };
+            Ok::<_, Error>(runner)
+        });
+
+        Ok(runner.await?.as_ref())
+    }
+}
+
+#[async_trait::async_trait]
+impl Runner for BazelRunner {
+    async fn run<'a>(&self, task: &Task, options: &LaunchOptions) -> Result<Process> {
+        self.inner().await?.run(task, options).await
+    }
+
+    fn log_stream(&self) -> Option<Process> {
+        self.inner.get()?.log_stream()
+    }
+
+    async fn focus(&self, task: &Task) {
+        if let Some(runner) = self.inner.get() {
+            runner.focus(task).await
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectCompile for BazelProject {
+    /// Generate compile database from compile_commands.json produced by Bazel.
+    ///
+    /// Uses an existing compile_commands.json in root or bazel-bin, otherwise tries to refresh it
+    /// with hedron_compile_commands.
+    async fn update_compile_database(&self, broadcast: &Arc<Broadcast>) -> Result<()> {
+        let compile_commands = match self.find_compile_commands() {
+            Some(path) => path,
+            None => {
+                let task = Task::new(TaskKind::Compile, &self.name, broadcast.clone());
+                let mut process = Process::new(Self::bazel()?);
+                process.args(&["run", Self::REFRESH_COMPILE_COMMANDS]);
+                process.current_dir(self.root());
+
+                let success = task
+                    .consume(Box::new(process))?
+                    .recv()
+                    .await
+                    .unwrap_or_default();
+
+                match self.find_compile_commands() {
+                    Some(path) if success => path,
+                    _ => return Err(Error::Compile),
+                }
+            }
+        };
+
+        tracing::debug!("Using {compile_commands:?}");
+        let content = tokio::fs::read(compile_commands).await?;
+        self.write_compile_database(content).await?;
+        broadcast.reload_lsp_server();
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectGenerate for BazelProject {
+    fn should_generate(&self, event: &Event) -> bool {
+        let is_config_file = Self::CONFIG_FILES.contains(&event.file_name().as_str());
+        let is_content_update = event.is_content_update_event();
+
+        is_config_file && (is_content_update || event.is_create_event() || event.is_remove_event())
+    }
+
+    /// Query bazel targets
+    async fn generate(&mut self, _broadcast: &Arc<Broadcast>) -> Result<()> {
+        self.update_targets().await?;
+
+        tracing::info!("(name: {:?}, targets: {:?})", self.name(), self.targets());
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Project for BazelProject {
+    #[tracing::instrument(parent = None, name = "Project", skip_all, fields(name = root.name().unwrap(), kind = "bazel"))]
+    async fn new(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<Self> {
+        let mut watchignore = generate_watchignore(root).await;
+        watchignore.push("**/bazel-*/**".into());
+
+        let mut project = Self {
+            name: root.name().unwrap(),
+            root: root.clone(),
+            watchignore,
+            num_clients: 1,
+            ..Self::default()
+        };
+
+        project.generate(broadcast).await?;
+
+        Ok(project)
+    }
+}
+
+impl BazelProject {
+    /// Files that define bazel workspace
+    pub const WORKSPACE_FILES: [&'static str; 3] = ["WORKSPACE", "WORKSPACE.bazel", "MODULE.bazel"];
+    /// Files that define bazel targets or workspace
+    const CONFIG_FILES: [&'static str; 5] = [
+        "BUILD",
+        "BUILD.bazel",
+        "WORKSPACE",
+        "WORKSPACE.bazel",
+        "MODULE.bazel",
+    ];
+    /// Rule kinds to register as project targets
+    const RULE_KINDS: [&'static str; 5] = [
+        "ios_application",
+        "macos_application",
+        "macos_command_line_application",
+        "tvos_application",
+        "watchos_application",
+    ];
+    /// Target refreshing compile_commands.json, see hedronvision/bazel-compile-commands-extractor
+    const REFRESH_COMPILE_COMMANDS: &'static str = "@hedron_compile_commands//:refresh_all";
+
+    /// Whether bazel workspace exists in root
+    pub fn is_workspace(root: &PathBuf) -> bool {
+        Self::WORKSPACE_FILES.iter().any(|f| root.join(f).exists())
+    }
+
+    /// Get bazel executable, preferring bazelisk
+    fn bazel() -> Result<String> {
+        which("bazelisk")
+            .or_else(|_| which("bazel"))
+            .map_err(|_| Error::generator_missing("bazel"))
+    }
+
+    /// Get bazel flags for build settings and destination
+    fn build_flags(cfg: &BuildSettings, device: Option<&Destination>) -> Vec<String> {
+        let mut flags = vec![];
+
+        match cfg.configuration.as_deref() {
+            Some("Release") => flags.push("--compilation_mode=opt".into()),
+            Some(_) => flags.push("--compilation_mode=dbg".into()),
+            None => {}
+        }
+
+        // Output .app bundles instead of .ipa archives, so they can be installed directly
+        flags.push("--define=apple.experimental.tree_artifact_outputs=1".into());
+
+        // rules_apple names arm64 simulators `sim_arm64`, but Intel ones just `x86_64`
+        let simulator_cpu = match std::env::consts::ARCH {
+            "aarch64" => "sim_arm64",
+            _ => "x86_64",
+        };
+        match device {
+            Some(Destination::Simulator(device)) if device.platform == PBXTargetPlatform::IOS => {
+                flags.push(format!("--ios_multi_cpus={simulator_cpu}"));
+            }
+            Some(Destination::Physical(device)) if device.platform == PBXTargetPlatform::IOS => {
+                flags.push("--ios_multi_cpus=arm64".into());
+            }
+            _ => {}
+        }
+
+        flags
+    }
+
+    /// Get bazel equivalents of extra xcodebuild flags, dropping flags without one
+    fn extra_flags(flags: &[String]) -> Vec<String> {
+        flags
+            .iter()
+            .filter_map(|flag| match flag.as_str() {
+                "-verbose" => Some("--subcommands".to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get path of target build output, i.e. `.app` bundle or binary
+    async fn output_path(
+        root: &Path,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+    ) -> Result<PathBuf> {
+        let output = Command::new(Self::bazel()?)
+            .args(["cquery", &cfg.target, "--output=files"])
+            .args(Self::build_flags(cfg, device))
+            .current_dir(root)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Run(format!(
+                "Getting {} output failed {stderr}",
+                cfg.target
+            )));
+        }
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(|path| root.join(path))
+            .ok_or_else(|| Error::Run(format!("No output found for {}", cfg.target)))
+    }
+
+    /// Get bundle identifier of a target
+    async fn bundle_id(root: &Path, label: &str) -> Result<String> {
+        let output = Command::new(Self::bazel()?)
+            .args(["query", label, "--output=build"])
+            .current_dir(root)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .await?;
+
+        parse_bundle_id(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| Error::Run(format!("No bundle_id found for {label}")))
+    }
+
+    /// Find compile_commands.json in root or bazel-bin
+    fn find_compile_commands(&self) -> Option<PathBuf> {
+        let path = self.root().join("compile_commands.json");
+        if path.exists() {
+            return Some(path);
+        }
+
+        wax::walk("**/compile_commands.json", self.root().join("bazel-bin"))
+            .ok()?
+            .flatten()
+            .map(|entry| entry.into_path())
+            .next()
+    }
+
+    /// Query bazel for buildable targets and update internal state
+    async fn update_targets(&mut self) -> Result<()> {
+        let query = format!("kind('{}', //...)", Self::RULE_KINDS.join("|"));
+        let output = Command::new(Self::bazel()?)
+            .args(["query", &query, "--output=label_kind"])
+            .current_dir(self.root())
+            .stdin(std::process::Stdio::null())
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            tracing::error!("Fail to query bazel targets {stderr}");
+            return Err(Error::DefinitionParsing(stderr.trim().to_string()));
+        }
+
+        self.targets = parse_label_kinds(&String::from_utf8_lossy(&output.stdout));
+
+        Ok(())
+    }
+}
+
+/// Parse `bazel query --output=label_kind` output into targets
+fn parse_label_kinds(output: &str) -> HashMap<String, TargetInfo> {
+    output
+        .lines()
+        .flat_map(|line| {
+            let (kind, label) = line.trim().split_once(" rule ")?;
+            let platform = match kind.split('_').next()? {
+                "ios" => PBXTargetPlatform::IOS,
+                "macos" => PBXTargetPlatform::MacOS,
+                "tvos" => PBXTargetPlatform::TvOS,
+                "watchos" => PBXTargetPlatform::WatchOS,
+                _ => PBXTargetPlatform::Unknown,
+            };
+            let info = TargetInfo {
+                platform: platform.to_string(),
+                configurations: BuildSettings::DEFAULT_CONFIGURATIONS
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                kind: BuildableKind::Target,
+                watching: false,
+                product_type: None,
+                bundle_id: None,
+            };
+            Some((label.trim().to_string(), info))
+        })
+        .collect()
+}
+
+/// Parse `bundle_id` attribute from `bazel query --output=build` output
+fn parse_bundle_id(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("bundle_id = ")?;
+        Some(value.trim_end_matches(',').trim_matches('"').to_string())
+    })
+}
+
+#[test]
+fn test_parse_bazel_query_output() {
+    let output =
+        "ios_application rule //app:App\nmacos_command_line_application rule //tools:cli\n";
+    let targets = parse_label_kinds(output);
+
+    assert_eq!(targets.len(), 2);
+    assert_eq!(
+        targets["//app:App"].platform,
+        PBXTargetPlatform::IOS.to_string()
+    );
+    assert_eq!(
+        targets["//tools:cli"].platform,
+        PBXTargetPlatform::MacOS.to_string()
+    );
+
+    let output = "ios_application(\n  name = \"App\",\n  bundle_id = \"com.example.app\",\n)";
+    assert_eq!(parse_bundle_id(output), Some("com.example.app".into()));
+}
+
+#[test]
+fn test_build_flags() {
+    let cfg = BuildSettings {
+        target: "//app:App".into(),
+        configuration: Some("Release".into()),
+        scheme: None,
+        sdk: None,
+        destination: None,
+        xcconfig: None,
+        overrides: vec![],
+    };
+    let physical = |platform| {
+        Destination::Physical(PhysicalDevice {
+            udid: "00008110-000A1C2E3C41801E".into(),
+            name: "iPhone".into(),
+            platform,
+        })
+    };
+
+    let flags = BazelProject::build_flags(&cfg, None);
+    assert_eq!(flags[0], "--compilation_mode=opt");
+    assert!(!flags.iter().any(|f| f.starts_with("--ios_multi_cpus")));
+
+    let iphone = physical(PBXTargetPlatform::IOS);
+    let flags = BazelProject::build_flags(&cfg, Some(&iphone));
+    assert!(flags.contains(&"--ios_multi_cpus=arm64".to_string()));
+
+    let watch = physical(PBXTargetPlatform::WatchOS);
+    let flags = BazelProject::build_flags(&cfg, Some(&watch));
+    assert!(!flags.iter().any(|f| f.starts_with("--ios_multi_cpus")));
+
+    let flags = [
+        "-showBuildTimingSummary".to_string(),
+        "-verbose".to_string(),
+    ];
+    assert_eq!(BazelProject::extra_flags(&flags), vec!["--subcommands"]);
+}
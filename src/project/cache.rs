@@ -0,0 +1,84 @@
+//! Cache of parsed project files, invalidated when files change on disk
+use crate::Result;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use xcodeproj::XCodeProject;
+
+static XCODEPROJ_CACHE: Lazy<FileCache<XCodeProject>> = Lazy::new(Default::default);
+
+/// Values loaded from files, keyed by path and the modification time they were loaded at
+pub struct FileCache<T> {
+    entries: Mutex<HashMap<PathBuf, (SystemTime, Arc<T>)>>,
+}
+
+impl<T> Default for FileCache<T> {
+    fn default() -> Self {
+        Self {
+            entries: Default::default(),
+        }
+    }
+}
+
+impl<T> FileCache<T> {
+    /// Get cached value of path, loading it if missing or modified since it was loaded
+    pub fn get_or_load<F>(&self, path: &Path, modified: SystemTime, load: F) -> Result<Arc<T>>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some((loaded_at, value)) = entries.get(path) {
+            if *loaded_at == modified {
+                return Ok(value.clone());
+            }
+        }
+
+        let value = Arc::new(load()?);
+        entries.insert(path.to_path_buf(), (modified, value.clone()));
+        Ok(value)
+    }
+
+    /// Drop cached value of path
+    pub fn invalidate(&self, path: &Path) {
+        self.entries.lock().unwrap().remove(path);
+    }
+}
+
+/// Get parsed XCodeProject, re-parsing only when its project.pbxproj changed
+pub fn xcodeproj(path: &Path) -> Result<Arc<XCodeProject>> {
+    let modified = std::fs::metadata(path.join("project.pbxproj"))?.modified()?;
+    XCODEPROJ_CACHE.get_or_load(path, modified, || {
+        tracing::debug!("Parsing {path:?}");
+        Ok(XCodeProject::new(path)?)
+    })
+}
+
+/// Drop cached XCodeProject, e.g. after regenerating it
+pub fn invalidate_xcodeproj(path: &Path) {
+    XCODEPROJ_CACHE.invalidate(path)
+}
+
+#[test]
+fn test_file_cache() {
+    use std::time::Duration;
+
+    let cache = FileCache::<usize>::default();
+    let path = Path::new("/tmp/Demo.xcodeproj");
+    let loaded_at = SystemTime::UNIX_EPOCH;
+    let mut loads = 0;
+    let mut load = || {
+        loads += 1;
+        Ok(loads)
+    };
+
+    assert_eq!(*cache.get_or_load(path, loaded_at, &mut load).unwrap(), 1);
+    assert_eq!(*cache.get_or_load(path, loaded_at, &mut load).unwrap(), 1);
+
+    let modified = loaded_at + Duration::from_secs(1);
+    assert_eq!(*cache.get_or_load(path, modified, &mut load).unwrap(), 2);
+
+    cache.invalidate(path);
+    assert_eq!(*cache.get_or_load(path, modified, &mut load).unwrap(), 3);
+}
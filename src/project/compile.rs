@@ -0,0 +1,452 @@
+//! Functions to inspect and persist compile database
+use crate::util::fs;
+use crate::Result;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Get index store path used by compile commands.
+///
+/// If compile commands report different index store paths, their common ancestor is returned.
+pub fn index_store_path(commands: &[Value]) -> Option<PathBuf> {
+    let mut paths = commands.iter().flat_map(command_index_store_path);
+    let first = paths.next()?;
+
+    paths
+        .try_fold(first, |common, path| {
+            common
+                .ancestors()
+                .find(|ancestor| path.starts_with(ancestor))
+                .map(Path::to_path_buf)
+        })
+        .filter(|path| path.parent().is_some())
+}
+
+/// Get index store path of a single compile command
+fn command_index_store_path(command: &Value) -> Option<PathBuf> {
+    if let Some(path) = command.get("index_store_path").and_then(Value::as_str) {
+        return Some(path.into());
+    }
+
+    let arguments = match command.get("arguments").and_then(Value::as_array) {
+        Some(arguments) => arguments
+            .iter()
+            .flat_map(Value::as_str)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        None => shell_words::split(command.get("command")?.as_str()?).ok()?,
+    };
+
+    arguments
+        .iter()
+        .position(|arg| arg == "-index-store-path")
+        .and_then(|idx| arguments.get(idx + 1))
+        .map(PathBuf::from)
+}
+
+/// Extensions of source files that have compile commands
+pub const SOURCE_EXTENSIONS: [&str; 6] = ["swift", "m", "mm", "c", "cc", "cpp"];
+
+/// Get source files compiled by a compile command
+pub fn command_files(command: &Value) -> Vec<PathBuf> {
+    let mut files = command
+        .get("files")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .chain(command.get("file"))
+        .flat_map(Value::as_str)
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+/// Whether a source file has a compile command
+pub fn contains_file(commands: &[Value], path: &Path) -> bool {
+    find_command(commands, path).is_some()
+}
+
+/// Get compile command of a source file
+pub fn find_command<'a>(commands: &'a [Value], path: &Path) -> Option<&'a Value> {
+    commands
+        .iter()
+        .find(|command| command_files(command).iter().any(|file| file == path))
+}
+
+/// Get compile database path, `.compile` in root unless set in project config
+pub async fn database_path(root: &Path) -> PathBuf {
+    root.join(super::ProjectConfig::load(root).await.compile_database)
+}
+
+/// Write compile database atomically to its configured paths
+pub async fn write_database(root: &Path, content: Vec<u8>) -> Result<()> {
+    for path in super::ProjectConfig::load(root)
+        .await
+        .compile_database_paths()
+    {
+        let path = root.join(path);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        fs::write_atomic(path, content.clone()).await?;
+    }
+    Ok(())
+}
+
+/// Count compile commands written to root compile database, 0 if missing or invalid
+pub async fn count_commands(root: &Path) -> usize {
+    let content = match tokio::fs::read(database_path(root).await).await {
+        Ok(content) => content,
+        Err(_) => return 0,
+    };
+    serde_json::from_slice::<Vec<Value>>(&content).map_or(0, |commands| commands.len())
+}
+
+/// Replace compile commands of changed files with updated ones.
+///
+/// Returns false if an updated command has no existing counterpart compiling the same files,
+/// i.e. a structural change that requires regenerating the whole compile database.
+pub fn merge_commands(commands: &mut [Value], updated: Vec<Value>, changed: &[PathBuf]) -> bool {
+    for command in updated {
+        let files = command_files(&command);
+        if !files.iter().any(|file| changed.contains(file)) {
+            continue;
+        }
+
+        match commands.iter().position(|c| command_files(c) == files) {
+            Some(idx) => commands[idx] = command,
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// Get compile commands of `swiftc` and `clang` invocations logged by `swift build --verbose`,
+/// relative to package root.
+///
+/// Only the first invocation compiling the same source files is kept, e.g. SwiftPM may invoke
+/// swiftc again for a module to emit its interface.
+pub fn swiftpm_commands(output: &str, root: &Path) -> Vec<Value> {
+    let directory = root.display().to_string();
+    let mut commands: Vec<Value> = vec![];
+
+    for line in output.lines().map(str::trim) {
+        let arguments = match shell_words::split(line) {
+            Ok(words) if !words.is_empty() => words,
+            _ => continue,
+        };
+        let program = Path::new(&arguments[0])
+            .file_name()
+            .and_then(|n| n.to_str());
+
+        let command = match program {
+            Some("swiftc") => {
+                let files = arguments
+                    .iter()
+                    .filter(|arg| arg.ends_with(".swift"))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if files.is_empty() || !arguments.iter().any(|arg| arg == "-module-name") {
+                    continue;
+                }
+                serde_json::json!({
+                    "directory": directory,
+                    "command": line,
+                    "files": files,
+                    "arguments": arguments,
+                })
+            }
+            Some("clang" | "clang++") => {
+                let idx = match arguments.iter().position(|arg| arg == "-c") {
+                    Some(idx) => idx,
+                    None => continue,
+                };
+                let file = match arguments.get(idx + 1) {
+                    Some(file) => file.clone(),
+                    None => continue,
+                };
+                serde_json::json!({
+                    "directory": directory,
+                    "file": file,
+                    "arguments": arguments,
+                })
+            }
+            _ => continue,
+        };
+
+        let files = command_files(&command);
+        if !commands.iter().any(|c| command_files(c) == files) {
+            commands.push(command);
+        }
+    }
+
+    commands
+}
+
+/// Name of build server binary sourcekit-lsp is configured to start
+const BUILD_SERVER_BIN: &str = "xbase-sourcekit-helper";
+
+/// Resolve installed build server binary.
+///
+/// Looks in xbase install root, then PATH, then next to the running daemon executable.
+pub fn build_server_path() -> PathBuf {
+    let install_root: PathBuf = crate::BIN_ROOT
+        .replace("$HOME", &std::env::var("HOME").unwrap_or_default())
+        .into();
+    let installed = install_root.join(BUILD_SERVER_BIN);
+
+    if installed.exists() {
+        return installed;
+    }
+
+    if let Ok(path) = crate::util::fs::which(BUILD_SERVER_BIN) {
+        return path.into();
+    }
+
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(BUILD_SERVER_BIN)))
+        .filter(|path| path.exists())
+        .unwrap_or(installed)
+}
+
+/// Build server configuration (buildServer.json) starting the given build server binary
+fn build_server_config(server: &Path, languages: &[&str]) -> Value {
+    serde_json::json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "argv": [server],
+        "version": env!("CARGO_PKG_VERSION"),
+        "bspVersion": "0.2",
+        "languages": languages
+    })
+}
+
+/// Get language of a source file extension, as named by LSP
+fn extension_language(extension: &str) -> Option<&'static str> {
+    match extension {
+        "swift" => Some("swift"),
+        "m" => Some("objective-c"),
+        "mm" => Some("objective-cpp"),
+        "c" => Some("c"),
+        "cc" | "cpp" | "cxx" => Some("cpp"),
+        _ => None,
+    }
+}
+
+/// Get languages of source files in project root, skipping build products and dependencies.
+///
+/// Falls back to swift when no source files are found.
+pub fn project_languages(root: &Path) -> Vec<&'static str> {
+    const SKIPPED: [&str; 4] = ["build", "Build", "Pods", "DerivedData"];
+
+    let mut languages = vec![];
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries.flatten(),
+            Err(_) => continue,
+        };
+
+        for entry in entries {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry.file_type().map_or(false, |t| t.is_dir());
+
+            if is_dir {
+                let skip = name.starts_with('.')
+                    || name.starts_with("bazel-")
+                    || name.ends_with(".xcodeproj")
+                    || name.ends_with(".xcworkspace")
+                    || SKIPPED.contains(&name.as_str());
+                if !skip {
+                    dirs.push(path);
+                }
+            } else if let Some(language) = path
+                .extension()
+                .and_then(|e| extension_language(&e.to_string_lossy()))
+            {
+                if !languages.contains(&language) {
+                    languages.push(language);
+                }
+            }
+        }
+    }
+
+    if languages.is_empty() {
+        languages.push("swift");
+    }
+
+    languages.sort_unstable();
+    languages
+}
+
+/// Whether buildServer.json was written by another version or points to a missing binary
+fn is_build_server_config_outdated(config: &Value) -> bool {
+    let version = config.get("version").and_then(Value::as_str);
+    let server = config.pointer("/argv/0").and_then(Value::as_str);
+
+    version != Some(env!("CARGO_PKG_VERSION")) || !server.map_or(false, |p| Path::new(p).exists())
+}
+
+/// Create buildServer.json or rewrite it when outdated or forced, keeping persisted index store
+/// path.
+pub async fn ensure_build_server_config(root: &Path, force: bool) -> Result<()> {
+    let build_server_path = root.join("buildServer.json");
+    let existing = match tokio::fs::read(&build_server_path).await {
+        Ok(content) => serde_json::from_slice::<Value>(&content).ok(),
+        Err(_) => None,
+    };
+
+    let outdated = existing
+        .as_ref()
+        .map_or(true, is_build_server_config_outdated);
+    if !outdated && !force {
+        return Ok(());
+    }
+
+    // Walking the project is blocking, and slow for large ones
+    let project_root = root.to_path_buf();
+    let languages = tokio::task::spawn_blocking(move || project_languages(&project_root))
+        .await
+        .unwrap_or_else(|_| vec!["swift"]);
+    let mut config = build_server_config(&build_server_path(), &languages);
+    let index_store_path = existing.as_ref().and_then(|c| c.get("indexStorePath"));
+    if let (Some(path), Some(config)) = (index_store_path, config.as_object_mut()) {
+        config.insert("indexStorePath".into(), path.clone());
+    }
+
+    tracing::info!("Writing {build_server_path:?}");
+    fs::write_atomic(build_server_path, serde_json::to_vec_pretty(&config)?).await?;
+
+    Ok(())
+}
+
+/// Persist index store path in buildServer.json, so sourcekit-helper can reuse it.
+pub async fn persist_index_store_path(root: &Path, index_store_path: &Path) -> Result<()> {
+    let build_server_path = root.join("buildServer.json");
+    if !build_server_path.exists() {
+        return Ok(());
+    }
+
+    let content = tokio::fs::read(&build_server_path).await?;
+    let mut config = serde_json::from_slice::<Value>(&content)?;
+    let index_store_path = Value::String(index_store_path.display().to_string());
+
+    if let Some(config) = config.as_object_mut() {
+        if config.get("indexStorePath") == Some(&index_store_path) {
+            return Ok(());
+        }
+        config.insert("indexStorePath".into(), index_store_path);
+    }
+
+    fs::write_atomic(build_server_path, serde_json::to_vec_pretty(&config)?).await?;
+
+    Ok(())
+}
+
+#[test]
+fn test_build_server_config_outdated() {
+    let exe = std::env::current_exe().unwrap();
+    let languages = ["swift"];
+    assert!(!is_build_server_config_outdated(&build_server_config(
+        &exe, &languages
+    )));
+
+    let missing = Path::new("/Users/someone/repos/xbase-sourcekit-helper");
+    let missing = build_server_config(missing, &languages);
+    assert!(is_build_server_config_outdated(&missing));
+
+    let mut legacy = build_server_config(&exe, &languages);
+    legacy["version"] = "0.1".into();
+    assert!(is_build_server_config_outdated(&legacy));
+}
+
+#[test]
+fn test_index_store_path() {
+    use serde_json::json;
+
+    let single = vec![json!({
+        "command": "swiftc -module-name App -index-store-path /cache/Build/Index/DataStore -c",
+    })];
+    assert_eq!(
+        index_store_path(&single),
+        Some(PathBuf::from("/cache/Build/Index/DataStore"))
+    );
+
+    let multi = vec![
+        json!({ "arguments": ["swiftc", "-index-store-path", "/cache/App/Index/DataStore"] }),
+        json!({ "arguments": ["swiftc", "-index-store-path", "/cache/Kit/Index/DataStore"] }),
+        json!({ "arguments": ["clang", "-c", "main.m"] }),
+    ];
+    assert_eq!(index_store_path(&multi), Some(PathBuf::from("/cache")));
+
+    assert_eq!(
+        index_store_path(&[json!({ "command": "clang -c main.m" })]),
+        None
+    );
+}
+
+#[test]
+fn test_merge_commands() {
+    use serde_json::json;
+
+    let mut commands = vec![
+        json!({ "file": "/app/main.m", "command": "clang -O0 -c /app/main.m" }),
+        json!({ "files": ["/app/B.swift", "/app/A.swift"], "command": "swiftc -Onone" }),
+    ];
+    let changed = vec![PathBuf::from("/app/A.swift")];
+
+    let updated =
+        vec![json!({ "files": ["/app/A.swift", "/app/B.swift"], "command": "swiftc -O" })];
+    assert!(merge_commands(&mut commands, updated, &changed));
+    assert_eq!(commands[1]["command"], "swiftc -O");
+    assert_eq!(commands[0]["command"], "clang -O0 -c /app/main.m");
+
+    let structural =
+        vec![json!({ "files": ["/app/A.swift", "/app/C.swift"], "command": "swiftc" })];
+    assert!(!merge_commands(&mut commands, structural, &changed));
+    assert!(contains_file(&commands, Path::new("/app/B.swift")));
+    assert!(!contains_file(&commands, Path::new("/app/C.swift")));
+}
+
+#[test]
+fn test_find_command() {
+    let commands = vec![
+        serde_json::json!({ "file": "/tmp/App/main.swift", "command": "swiftc main.swift" }),
+        serde_json::json!({
+            "files": ["/tmp/App/A.swift", "/tmp/App/B.swift"],
+            "command": "swiftc A.swift B.swift"
+        }),
+    ];
+
+    let command = find_command(&commands, Path::new("/tmp/App/B.swift")).unwrap();
+    assert_eq!(command["command"], "swiftc A.swift B.swift");
+    assert!(find_command(&commands, Path::new("/tmp/App/C.swift")).is_none());
+}
+
+#[test]
+fn test_swiftpm_commands() {
+    let output = "\
+Compiling Kit Kit.swift
+/usr/bin/swiftc -module-name Kit -c /pkg/Sources/Kit/Kit.swift /pkg/Sources/Kit/Util.swift
+/usr/bin/swiftc -module-name Kit -emit-module /pkg/Sources/Kit/Kit.swift /pkg/Sources/Kit/Util.swift
+/usr/bin/clang -fobjc-arc -c /pkg/Sources/CKit/CKit.c -o /pkg/.build/CKit.o
+/usr/bin/swiftc -frontend -c /pkg/Sources/Kit/Kit.swift
+/usr/bin/ld -o /pkg/.build/debug/Kit
+";
+    let commands = swiftpm_commands(output, Path::new("/pkg"));
+
+    assert_eq!(commands.len(), 2);
+    assert_eq!(commands[0]["directory"], "/pkg");
+    assert_eq!(
+        command_files(&commands[0]),
+        vec![
+            PathBuf::from("/pkg/Sources/Kit/Kit.swift"),
+            PathBuf::from("/pkg/Sources/Kit/Util.swift")
+        ]
+    );
+    assert!(commands[0]["command"].as_str().unwrap().contains(" -c "));
+    assert_eq!(commands[1]["file"], "/pkg/Sources/CKit/CKit.c");
+}
@@ -0,0 +1,348 @@
+use crate::{BuildSettings, ContentLevel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Project configuration, read from `.xbase.json` in project root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// Time window in milliseconds to coalesce file system events within
+    pub debounce_ms: u64,
+    /// Whether the watcher should also skip paths ignored by nested .gitignore files and keep
+    /// paths negated with `!`. Paths ignored by the root .gitignore are always skipped
+    pub gitignore: bool,
+    /// Whether to run `pod install` when Podfile.lock is outdated
+    pub pods: bool,
+    /// Name or path relative to root of the xcodeproj to use when several exist
+    pub xcodeproj: Option<String>,
+    /// DerivedData path relative to root to build and index with, xcodebuild default if unset
+    pub derived_data_path: Option<PathBuf>,
+    /// Whether to update compile commands of edited source files on save
+    pub incremental_compile: bool,
+    /// Seconds xcodebuild/generators may go without output before being killed, 0 to disable
+    pub process_timeout_secs: u64,
+    /// Seconds without messages after which clients are pinged, 0 to disable
+    pub ping_interval_secs: u64,
+    /// Whether to persist broadcast messages to `<broadcast root>/<project>.log`
+    pub log_file: bool,
+    /// Size in bytes after which the log file is rotated
+    pub log_file_max_bytes: u64,
+    /// Extra globs, relative to root, of paths the watcher should ignore
+    pub watchignore: Vec<String>,
+    /// Globs, relative to root, of the only paths the watcher should react to, all if empty
+    pub watch_only: Vec<String>,
+    /// Extra directories to watch along with root, e.g. local packages the project depends on
+    pub watch_roots: Vec<PathBuf>,
+    /// Directory relative to root containing Tuist manifests, e.g. in monorepos
+    pub tuist_path: Option<PathBuf>,
+    /// Whether to run `tuist fetch` before generating when Tuist/Dependencies.swift changed
+    pub tuist_fetch: bool,
+    /// Default build settings per target, see [`ProjectConfig::settings_for`]
+    pub targets: HashMap<String, TargetSettings>,
+    /// Shell command to run in project root when the project is loaded, e.g. codegen scripts.
+    ///
+    /// The project fails to register if the command fails or times out.
+    pub on_load: Option<String>,
+    /// Whether running always builds first, even if nothing changed since the last successful build
+    pub always_build_before_run: bool,
+    /// Whether a watched run stops the running app when rebuilding it fails, instead of keeping
+    /// it running until a build succeeds
+    pub stop_run_on_build_failure: bool,
+    /// Minimum level of messages to notify with, e.g. `Error` to only be notified of failures,
+    /// lower ones are still logged
+    pub notify_level: ContentLevel,
+    /// Compile database path relative to root, e.g. `build/compile_commands.json`
+    pub compile_database: PathBuf,
+    /// Whether to also write the compile database to `compile_commands.json` in root, for clangd
+    pub compile_commands_json: bool,
+    /// Whether to collapse raw xcodebuild output into concise summaries of build phases
+    pub pretty_output: bool,
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 150,
+            gitignore: false,
+            pods: true,
+            xcodeproj: None,
+            derived_data_path: None,
+            incremental_compile: false,
+            process_timeout_secs: 600,
+            ping_interval_secs: 30,
+            log_file: false,
+            log_file_max_bytes: 5 * 1024 * 1024,
+            watchignore: vec![],
+            watch_only: vec![],
+            watch_roots: vec![],
+            tuist_path: None,
+            tuist_fetch: true,
+            targets: HashMap::default(),
+            on_load: None,
+            always_build_before_run: false,
+            stop_run_on_build_failure: false,
+            notify_level: ContentLevel::Trace,
+            compile_database: PathBuf::from(".compile"),
+            compile_commands_json: false,
+            pretty_output: false,
+        }
+    }
+}
+
+/// Default build settings of a target, set in `.xbase.json` under `targets`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetSettings {
+    pub configuration: Option<String>,
+    pub scheme: Option<String>,
+    pub sdk: Option<String>,
+    pub destination: Option<String>,
+    pub xcconfig: Option<PathBuf>,
+}
+
+impl ProjectConfig {
+    pub const FILE_NAME: &'static str = ".xbase.json";
+
+    /// Load project configuration, falling back to defaults when missing or invalid
+    pub async fn load(root: &Path) -> Self {
+        let path = root.join(Self::FILE_NAME);
+        let content = match tokio::fs::read(&path).await {
+            Ok(content) => content,
+            Err(_) => return Self::default(),
+        };
+
+        serde_json::from_slice(&content).unwrap_or_else(|err| {
+            tracing::warn!("Invalid {}: {err}, using defaults", Self::FILE_NAME);
+            Self::default()
+        })
+    }
+
+    /// File system events debounce window
+    pub fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms)
+    }
+
+    /// Time without messages after which clients are pinged
+    pub fn ping_interval(&self) -> Option<Duration> {
+        (self.ping_interval_secs > 0).then(|| Duration::from_secs(self.ping_interval_secs))
+    }
+
+    /// Time a spawned process may go without output before being killed
+    pub fn process_timeout(&self) -> Option<Duration> {
+        (self.process_timeout_secs > 0).then(|| Duration::from_secs(self.process_timeout_secs))
+    }
+
+    /// Globs in `watchignore` and `watch_only` that fail to parse, along with the parse error
+    pub fn invalid_globs(&self) -> Vec<(String, String)> {
+        self.watchignore
+            .iter()
+            .chain(self.watch_only.iter())
+            .flat_map(|pattern| match wax::Glob::new(pattern) {
+                Ok(_) => None,
+                Err(err) => Some((pattern.clone(), err.to_string())),
+            })
+            .collect()
+    }
+
+    /// Valid `watchignore` globs, matched case-insensitively where the file system is
+    pub fn watchignore_globs(&self) -> Vec<String> {
+        fs_globs(&self.watchignore)
+    }
+
+    /// Paths, relative to root, compile database is written to
+    pub fn compile_database_paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.compile_database.clone()];
+        let copy = PathBuf::from("compile_commands.json");
+        if self.compile_commands_json && !paths.contains(&copy) {
+            paths.push(copy);
+        }
+        paths
+    }
+
+    /// Valid `watch_only` globs, matched case-insensitively where the file system is
+    pub fn watch_only_globs(&self) -> Vec<String> {
+        fs_globs(&self.watch_only)
+    }
+
+    /// Get build settings with unset fields filled from the settings target defaults.
+    ///
+    /// Precedence is field by field: values set in the request always win, and defaults in
+    /// `.xbase.json` only fill in what the request leaves unset.
+    pub fn settings_for(&self, settings: &BuildSettings) -> BuildSettings {
+        let defaults = match self.targets.get(&settings.target) {
+            Some(defaults) => defaults,
+            None => return settings.clone(),
+        };
+        let or = |value: &Option<String>, default: &Option<String>| {
+            value.clone().or_else(|| default.clone())
+        };
+
+        BuildSettings {
+            target: settings.target.clone(),
+            configuration: or(&settings.configuration, &defaults.configuration),
+            scheme: or(&settings.scheme, &defaults.scheme),
+            sdk: or(&settings.sdk, &defaults.sdk),
+            destination: or(&settings.destination, &defaults.destination),
+            xcconfig: settings
+                .xcconfig
+                .clone()
+                .or_else(|| defaults.xcconfig.clone()),
+            overrides: settings.overrides.clone(),
+        }
+    }
+
+    /// Existing `watch_roots`, relative to root unless absolute.
+    ///
+    /// Roots inside, or containing, root or another extra root are skipped, as their events
+    /// would otherwise be reported twice.
+    pub fn watch_roots(&self, root: &Path) -> Vec<PathBuf> {
+        let root = match std::fs::canonicalize(root) {
+            Ok(root) => root,
+            Err(_) => return vec![],
+        };
+        let mut roots = self
+            .watch_roots
+            .iter()
+            .flat_map(|path| match std::fs::canonicalize(root.join(path)) {
+                Ok(path) if path.is_dir() => Some(path),
+                _ => {
+                    tracing::warn!("Skipping watch root {path:?}, not a directory");
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Outer roots first, so that nested ones are skipped
+        roots.sort_by_key(|path| path.components().count());
+
+        let mut watched = vec![root];
+        roots.retain(|path| {
+            let overlaps = watched
+                .iter()
+                .any(|other| path.starts_with(other) || other.starts_with(path));
+            if overlaps {
+                tracing::warn!("Skipping watch root {path:?}, overlapping a watched root");
+            } else {
+                watched.push(path.clone());
+            }
+            !overlaps
+        });
+
+        roots
+    }
+}
+
+/// Drop invalid globs and make the rest case-insensitive on macOS, as paths differing only in
+/// case point to the same file there
+fn fs_globs(patterns: &[String]) -> Vec<String> {
+    patterns
+        .iter()
+        .filter(|pattern| wax::Glob::new(pattern).is_ok())
+        .map(|pattern| {
+            if cfg!(target_os = "macos") {
+                format!("(?i){pattern}")
+            } else {
+                pattern.clone()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_invalid_globs() {
+    let config = ProjectConfig {
+        watchignore: vec!["Pods/**".into(), "{Vendor,Carthage".into()],
+        watch_only: vec!["Sources/**/*.swift".into()],
+        ..ProjectConfig::default()
+    };
+
+    let invalid = config.invalid_globs();
+    assert_eq!(invalid.len(), 1);
+    assert_eq!(invalid[0].0, "{Vendor,Carthage");
+    assert_eq!(config.watchignore_globs().len(), 1);
+    assert_eq!(config.watch_only_globs().len(), 1);
+}
+
+#[test]
+fn test_watch_roots() {
+    let base = std::env::temp_dir().join(format!("xbase-watch-roots-{}", std::process::id()));
+    let root = base.join("App");
+    for dir in ["App/Sources", "Kit/Sources", "Core"] {
+        std::fs::create_dir_all(base.join(dir)).unwrap();
+    }
+
+    let config = ProjectConfig {
+        watch_roots: vec![
+            "../Kit/Sources".into(),
+            "../Kit".into(),
+            "Sources".into(),
+            "..".into(),
+            base.join("Core"),
+            "../Missing".into(),
+        ],
+        ..ProjectConfig::default()
+    };
+
+    let base = std::fs::canonicalize(&base).unwrap();
+    assert_eq!(
+        config.watch_roots(&root),
+        vec![base.join("Kit"), base.join("Core")]
+    );
+
+    std::fs::remove_dir_all(&base).ok();
+}
+
+#[test]
+fn test_settings_for() {
+    let config = ProjectConfig {
+        targets: HashMap::from([(
+            "App".to_string(),
+            TargetSettings {
+                configuration: Some("Release".into()),
+                sdk: Some("iphoneos".into()),
+                ..TargetSettings::default()
+            },
+        )]),
+        ..ProjectConfig::default()
+    };
+    let settings = |target: &str, configuration: Option<&str>| BuildSettings {
+        target: target.into(),
+        configuration: configuration.map(String::from),
+        scheme: None,
+        sdk: None,
+        destination: None,
+        xcconfig: None,
+        overrides: vec![],
+    };
+
+    let merged = config.settings_for(&settings("App", Some("Debug")));
+    assert_eq!(merged.configuration.as_deref(), Some("Debug"));
+    assert_eq!(merged.sdk.as_deref(), Some("iphoneos"));
+    assert_eq!(merged.scheme, None);
+
+    let merged = config.settings_for(&settings("App", None));
+    assert_eq!(merged.configuration.as_deref(), Some("Release"));
+
+    let other = settings("Widget", None);
+    assert_eq!(config.settings_for(&other), other);
+}
+
+#[test]
+fn test_compile_database_paths() {
+    let mut config = ProjectConfig::default();
+    assert_eq!(
+        config.compile_database_paths(),
+        vec![PathBuf::from(".compile")]
+    );
+
+    config.compile_commands_json = true;
+    assert_eq!(
+        config.compile_database_paths(),
+        vec![PathBuf::from(".compile"), "compile_commands.json".into()]
+    );
+
+    config.compile_database = "compile_commands.json".into();
+    assert_eq!(config.compile_database_paths().len(), 1);
+}
@@ -0,0 +1,254 @@
+//! Read targets metadata not provided by xcodeproj targets info from parsed project
+use crate::{ProductType, TargetInfo};
+use std::collections::HashMap;
+use xcodeproj::XCodeProject;
+
+/// Set product type, bundle identifier and configurations of targets read from xcodeproj
+pub fn apply(xcodeproj: &XCodeProject, targets: &mut HashMap<String, TargetInfo>) {
+    let project_configurations = xcodeproj
+        .root_project()
+        .build_configuration_list
+        .into_iter()
+        .flat_map(|list| list.build_configurations)
+        .map(|config| config.name.to_string())
+        .collect::<Vec<_>>();
+
+    for target in xcodeproj.targets() {
+        let info = match target.name.and_then(|name| targets.get_mut(name)) {
+            Some(info) => info,
+            None => continue,
+        };
+        let configurations = target
+            .build_configuration_list
+            .into_iter()
+            .flat_map(|list| list.build_configurations)
+            .collect::<Vec<_>>();
+
+        info.product_type = Some(ProductType::from(&target.product_type));
+        // Take the first build configuration bundle identifier that has no variables
+        info.bundle_id = configurations
+            .iter()
+            .flat_map(|config| {
+                config
+                    .build_settings
+                    .get_string("PRODUCT_BUNDLE_IDENTIFIER")
+            })
+            .find(|bundle_id| !bundle_id.contains('$'))
+            .map(ToString::to_string);
+
+        // xcodebuild only accepts project level configurations, which targets lacking them
+        // replace with their default one
+        let shared = configurations
+            .iter()
+            .map(|config| config.name.to_string())
+            .filter(|name| project_configurations.contains(name))
+            .collect::<Vec<_>>();
+        if !shared.is_empty() {
+            info.configurations = shared;
+        }
+    }
+}
+
+/// Oldest Xcode version, as `(major, minor)`, able to open projects of a given `objectVersion`
+const OBJECT_VERSIONS: [(u32, (u32, u32)); 14] = [
+    (46, (3, 2)),
+    (47, (6, 3)),
+    (48, (8, 0)),
+    (50, (9, 3)),
+    (51, (10, 0)),
+    (52, (11, 0)),
+    (53, (11, 4)),
+    (54, (12, 0)),
+    (55, (13, 0)),
+    (56, (14, 0)),
+    (60, (15, 0)),
+    (63, (15, 3)),
+    (70, (16, 0)),
+    (77, (16, 0)),
+];
+
+/// Get oldest Xcode version, as `(major, minor)`, the parsed xcodeproj can be built with.
+///
+/// Derived from the project `objectVersion`, or from `LastUpgradeCheck` (e.g. `1430` for
+/// 14.3) when the object version is newer than known ones.
+pub fn expected_xcode_version(xcodeproj: &XCodeProject) -> Option<(u32, u32)> {
+    let attributes = &xcodeproj.root_project().attributes;
+    let last_upgrade_check = attributes
+        .get_number("LastUpgradeCheck")
+        .map(|check| *check as u32)
+        .or_else(|| attributes.get_string("LastUpgradeCheck")?.parse().ok());
+    xcode_version(xcodeproj.object_version() as u32, last_upgrade_check)
+}
+
+fn xcode_version(object_version: u32, last_upgrade_check: Option<u32>) -> Option<(u32, u32)> {
+    let (latest, latest_xcode) = OBJECT_VERSIONS[OBJECT_VERSIONS.len() - 1];
+    if object_version <= latest {
+        return OBJECT_VERSIONS
+            .iter()
+            .rev()
+            .find(|(version, _)| *version <= object_version)
+            .map(|(_, xcode)| *xcode);
+    }
+
+    let last_upgrade_check = last_upgrade_check
+        .map(|check| (check / 100, check % 100 / 10))
+        .filter(|xcode| *xcode > latest_xcode);
+    Some(last_upgrade_check.unwrap_or(latest_xcode))
+}
+
+#[test]
+fn test_apply_targets_metadata() {
+    let content = r#"// !$*UTF8*$!
+{
+	archiveVersion = 1;
+	classes = {
+	};
+	objectVersion = 56;
+	objects = {
+
+/* Begin PBXGroup section */
+		G1 = {
+			isa = PBXGroup;
+			children = (
+			);
+			sourceTree = "<group>";
+		};
+/* End PBXGroup section */
+
+/* Begin PBXProject section */
+		P1 /* Project object */ = {
+			isa = PBXProject;
+			buildConfigurationList = C0 /* Build configuration list for PBXProject "Demo" */;
+			mainGroup = G1;
+			targets = (
+				B1 /* Demo */,
+				B2 /* DemoKit */,
+			);
+		};
+/* End PBXProject section */
+
+/* Begin PBXNativeTarget section */
+		B1 /* Demo */ = {
+			isa = PBXNativeTarget;
+			buildConfigurationList = C1 /* Build configuration list for PBXNativeTarget "Demo" */;
+			buildPhases = (
+			);
+			name = Demo;
+			productType = "com.apple.product-type.application";
+		};
+		B2 /* DemoKit */ = {
+			isa = PBXNativeTarget;
+			buildConfigurationList = C2 /* Build configuration list for PBXNativeTarget "DemoKit" */;
+			name = DemoKit;
+			productType = "com.apple.product-type.framework";
+		};
+/* End PBXNativeTarget section */
+
+/* Begin XCBuildConfiguration section */
+		E1 /* Debug */ = {
+			isa = XCBuildConfiguration;
+			buildSettings = {
+				PRODUCT_BUNDLE_IDENTIFIER = com.example.Demo;
+				SDKROOT = iphoneos;
+			};
+			name = Debug;
+		};
+		E2 /* Debug */ = {
+			isa = XCBuildConfiguration;
+			buildSettings = {
+				PRODUCT_BUNDLE_IDENTIFIER = "$(BUNDLE_PREFIX).DemoKit";
+			};
+			name = Debug;
+		};
+		E3 /* Staging */ = {
+			isa = XCBuildConfiguration;
+			buildSettings = {
+				PRODUCT_BUNDLE_IDENTIFIER = com.example.Demo.staging;
+			};
+			name = Staging;
+		};
+		E4 /* Beta */ = {
+			isa = XCBuildConfiguration;
+			buildSettings = {
+			};
+			name = Beta;
+		};
+		E5 /* Debug */ = {
+			isa = XCBuildConfiguration;
+			buildSettings = {
+			};
+			name = Debug;
+		};
+		E6 /* Staging */ = {
+			isa = XCBuildConfiguration;
+			buildSettings = {
+			};
+			name = Staging;
+		};
+/* End XCBuildConfiguration section */
+
+/* Begin XCConfigurationList section */
+		C0 /* Build configuration list for PBXProject "Demo" */ = {
+			isa = XCConfigurationList;
+			buildConfigurations = (
+				E5 /* Debug */,
+				E6 /* Staging */,
+			);
+		};
+		C1 /* Build configuration list for PBXNativeTarget "Demo" */ = {
+			isa = XCConfigurationList;
+			buildConfigurations = (
+				E1 /* Debug */,
+				E3 /* Staging */,
+				E4 /* Beta */,
+			);
+			defaultConfigurationName = Debug;
+		};
+		C2 /* Build configuration list for PBXNativeTarget "DemoKit" */ = {
+			isa = XCConfigurationList;
+			buildConfigurations = (
+				E2 /* Debug */,
+			);
+		};
+/* End XCConfigurationList section */
+	};
+	rootObject = P1 /* Project object */;
+}
+"#;
+
+    let root = std::env::temp_dir().join(format!("xbase-metadata-{}", std::process::id()));
+    let path = root.join("Demo.xcodeproj");
+    std::fs::create_dir_all(&path).unwrap();
+    std::fs::write(path.join("project.pbxproj"), content).unwrap();
+    let xcodeproj = XCodeProject::new(&path).unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    let mut targets = xcodeproj
+        .targets_info()
+        .into_iter()
+        .map(|(k, info)| (k, info.into()))
+        .collect::<HashMap<String, TargetInfo>>();
+    apply(&xcodeproj, &mut targets);
+
+    let demo = &targets["Demo"];
+    assert_eq!(demo.product_type, Some(ProductType::Application));
+    assert_eq!(demo.bundle_id, Some("com.example.Demo".into()));
+    assert_eq!(demo.configurations, vec!["Debug", "Staging"]);
+
+    let kit = &targets["DemoKit"];
+    assert_eq!(kit.product_type, Some(ProductType::Framework));
+    assert_eq!(kit.bundle_id, None);
+    assert_eq!(kit.configurations, vec!["Debug"]);
+    assert!(!ProductType::Framework.is_runnable());
+}
+
+#[test]
+fn test_xcode_version() {
+    assert_eq!(xcode_version(56, Some(1430)), Some((14, 0)));
+    assert_eq!(xcode_version(57, Some(1430)), Some((14, 0)));
+    assert_eq!(xcode_version(77, Some(1600)), Some((16, 0)));
+    assert_eq!(xcode_version(90, Some(2610)), Some((26, 1)));
+    assert_eq!(xcode_version(90, Some(1430)), Some((16, 0)));
+    assert_eq!(xcode_version(90, None), Some((16, 0)));
+    assert_eq!(xcode_version(40, None), None);
+}
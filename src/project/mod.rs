@@ -1,17 +1,27 @@
 mod barebone;
+mod bazel;
+pub mod cache;
+pub mod compile;
+mod config;
+mod metadata;
+pub mod pods;
 mod swift;
 mod tuist;
+pub mod workspace;
 mod xcodegen;
 
 use crate::util::PathExt;
 use crate::*;
 use anyhow::Context;
-use once_cell::sync::Lazy;
+use process_stream::Process;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use xclog::{XCBuildSettings, XCLogger};
 
+pub use config::*;
+pub use metadata::expected_xcode_version;
+
 /// Project Data
 pub trait ProjectData: std::fmt::Debug {
     /// Project root
@@ -36,64 +46,454 @@ pub trait ProjectData: std::fmt::Debug {
     }
     /// Get Ignore patterns
     fn watchignore(&self) -> &Vec<String>;
-    /// read dir and get xcodeproj paths
+    /// Tool the project is generated or described with
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::Xcodeproj
+    }
+    /// DerivedData path to build with, xcodebuild default if unset
+    fn derived_data(&self) -> Option<&PathBuf> {
+        None
+    }
+    /// Path to the xcodeproj the project is built with, if any
+    fn xcodeproj_path(&self) -> Option<&PathBuf> {
+        None
+    }
+    /// Parsed xcodeproj the project is built with, if any
+    fn xcodeproj(&self) -> Option<&Arc<xcodeproj::XCodeProject>> {
+        None
+    }
+    /// Get `-derivedDataPath` arguments, if DerivedData path is set
+    fn derived_data_args(&self) -> Vec<String> {
+        match self.derived_data() {
+            Some(path) => vec!["-derivedDataPath".into(), path.display().to_string()],
+            None => vec![],
+        }
+    }
+    /// read dir and get xcodeproj paths, sorted
     fn get_xcodeproj_paths(&self) -> Result<Vec<PathBuf>> {
-        Ok(wax::walk("*.xcodeproj", &self.root())
+        let mut paths = wax::walk("*.xcodeproj", &self.root())
             .context("Glob")?
             .flatten()
             .map(|entry| entry.into_path())
-            .collect::<Vec<PathBuf>>())
+            .collect::<Vec<PathBuf>>();
+        paths.sort();
+        Ok(paths)
+    }
+    /// Get platform of each project target
+    fn targets_platform(&self) -> HashMap<String, String> {
+        self.targets()
+            .iter()
+            .map(|(name, info)| (name.clone(), info.platform.clone()))
+            .collect()
+    }
+    /// Get project workspace path, preferring `{name}.xcworkspace`
+    fn workspace(&self) -> Option<PathBuf> {
+        let named = self.root().join(format!("{}.xcworkspace", self.name()));
+        if named.exists() {
+            return Some(named);
+        }
+        wax::walk("*.xcworkspace", self.root())
+            .ok()?
+            .flatten()
+            .map(|entry| entry.into_path())
+            .next()
+    }
+    /// Get workspace schemes, shared schemes first and xcodebuild listed schemes otherwise.
+    ///
+    /// Listing schemes with xcodebuild is slow, so only ones already listed with
+    /// [`workspace::list_schemes`] are returned.
+    fn schemes(&self) -> Vec<String> {
+        let workspace = match self.workspace() {
+            Some(workspace) => workspace,
+            None => return vec![],
+        };
+
+        let schemes = workspace::shared_schemes(self.root(), &workspace);
+        if !schemes.is_empty() {
+            return schemes;
+        }
+
+        workspace::listed_schemes(&workspace).unwrap_or_default()
+    }
+    /// Get workspace without shared schemes, whose schemes are listed by xcodebuild instead
+    fn unshared_workspace(&self) -> Option<PathBuf> {
+        let workspace = self.workspace()?;
+        workspace::shared_schemes(self.root(), &workspace)
+            .is_empty()
+            .then(|| workspace)
+    }
+    /// Get targets and workspace schemes that can be built
+    fn buildables(&self) -> HashMap<String, TargetInfo> {
+        let targets = self.targets();
+        let mut buildables = targets.clone();
+
+        for scheme in self.schemes() {
+            // Schemes are usually named after the target they build, e.g. `App` or `App-Dev`,
+            // otherwise take the first target by name
+            let info = targets
+                .iter()
+                .filter(|(name, _)| scheme.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .or_else(|| targets.iter().min_by_key(|(name, _)| *name))
+                .map(|(_, info)| info);
+            let (platform, configurations) = match info {
+                Some(info) => (info.platform.clone(), info.configurations.clone()),
+                None => (
+                    Default::default(),
+                    BuildSettings::DEFAULT_CONFIGURATIONS
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                ),
+            };
+            buildables.entry(scheme).or_insert_with(|| TargetInfo {
+                platform,
+                configurations,
+                kind: BuildableKind::Scheme,
+                watching: false,
+                product_type: None,
+                bundle_id: None,
+            });
+        }
+
+        buildables
     }
 }
 
 #[async_trait::async_trait]
 pub trait ProjectBuild: ProjectData {
-    /// Build Project using BuildSettings and optionally a device
+    /// Build Project using BuildSettings and optionally a device, with extra xcodebuild flags,
+    /// e.g. `-showBuildTimingSummary`.
+    ///
+    /// Flags are ignored by projects not built with xcodebuild.
     fn build(
         &self,
         cfg: &BuildSettings,
-        device: Option<&Device>,
+        device: Option<&Destination>,
+        flags: &[String],
         broadcast: &Arc<Broadcast>,
-    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<bool>)> {
-        let mut args = cfg.to_args();
-        let target = &cfg.target;
-        let name = self.name().to_owned();
-        let xcworkspace = format!("{}.xcworkspace", &name);
-        let task = Task::new(TaskKind::Build, target, broadcast.clone());
+    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<Result<()>>)> {
+        let mut args = self.args_for(cfg, device)?;
+        args.extend_from_slice(flags);
+        xcodebuild_build(self.root(), &cfg.target, args, broadcast)
+    }
+
+    /// Get arguments to build with, shared by builds and dry runs
+    fn args_for(&self, cfg: &BuildSettings, device: Option<&Destination>) -> Result<Vec<String>> {
+        self.validate_configuration(cfg)?;
+
+        let mut args = self.xcodebuild_args(cfg, device)?;
 
         args.insert(0, "build".to_string());
+        args.extend(self.destination_args(cfg, device)?);
 
-        if let Some(device) = device {
-            args.extend(device.special_build_args())
+        Ok(args)
+    }
+
+    /// Start time of the last successful build with cfg, if no file change was seen since.
+    ///
+    /// Only xcodebuild builds are tracked.
+    fn last_build(
+        &self,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+        broadcast: &Arc<Broadcast>,
+    ) -> Option<std::time::SystemTime> {
+        if matches!(self.kind(), ProjectKind::Swift | ProjectKind::Bazel) {
+            return None;
         }
+        let args = self.args_for(cfg, device).ok()?;
+        broadcast.shared_builds().up_to_date_since(&args.join(" "))
+    }
+
+    /// Get shell command a build would run, without running it
+    fn dry_run(&self, cfg: &BuildSettings, device: Option<&Destination>) -> Result<String> {
+        let program = match self.kind() {
+            ProjectKind::Swift => "/usr/bin/swift".to_string(),
+            ProjectKind::Bazel => "bazel".to_string(),
+            _ => xcodebuild::path().display().to_string(),
+        };
+        let args = self.args_for(cfg, device)?;
+        Ok(shell_words::join(std::iter::once(program).chain(args)))
+    }
+
+    /// Clean build products, deep clean also removes build cache root and compile database
+    async fn clean(
+        &self,
+        cfg: &BuildSettings,
+        deep: bool,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<()> {
+        let mut args = self.xcodebuild_args(cfg, None)?;
+        let target = &cfg.target;
+        let task = Task::new(TaskKind::Clean, target, broadcast.clone());
+
+        if deep {
+            task.info(format!("[{target}] Removing build cache"));
+            self.remove_build_cache().await?;
+        }
+
+        args.insert(0, "clean".to_string());
+
+        task.debug(format!("[{target}] {}", shell_words::join(&args)));
+
+        let mut process = Process::new(xcodebuild::path());
+        process.args(&args);
+        process.current_dir(self.root());
+        xcodebuild::set_envs(&mut process);
+
+        let formatter = broadcast.formatter();
+        let success = task
+            .consume_with(Box::new(process), formatter)?
+            .recv()
+            .await;
+        if success.unwrap_or_default() {
+            Ok(())
+        } else {
+            Err(Error::Build(format!("Failed to clean {target}")))
+        }
+    }
+
+    /// Run tests of a target/scheme, optionally only the given test identifiers
+    fn test(
+        &self,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+        only_testing: &[String],
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<tokio::sync::mpsc::Receiver<Result<()>>> {
+        self.xcodebuild_test(cfg, device, only_testing, &[], broadcast)
+    }
+
+    /// Run tests with extra xcodebuild flags, e.g. `-enableCodeCoverage YES`.
+    ///
+    /// Flags are ignored for projects not tested with xcodebuild.
+    fn test_with_flags(
+        &self,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+        only_testing: &[String],
+        flags: &[String],
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<tokio::sync::mpsc::Receiver<Result<()>>> {
+        if flags.is_empty() || matches!(self.kind(), ProjectKind::Swift | ProjectKind::Bazel) {
+            return self.test(cfg, device, only_testing, broadcast);
+        }
+        self.xcodebuild_test(cfg, device, only_testing, flags, broadcast)
+    }
+
+    /// Run tests with xcodebuild
+    fn xcodebuild_test(
+        &self,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+        only_testing: &[String],
+        flags: &[String],
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<tokio::sync::mpsc::Receiver<Result<()>>> {
+        self.validate_configuration(cfg)?;
+
+        // xcodebuild can only test schemes, fallback to a scheme named after the target
+        let mut cfg = cfg.clone();
+        cfg.scheme.get_or_insert_with(|| cfg.target.clone());
+
+        let mut args = self.xcodebuild_args(&cfg, device)?;
+        let target = &cfg.target;
+        let task = Task::new(TaskKind::Test, target, broadcast.clone());
+
+        args.insert(0, "test".to_string());
+        args.extend(self.destination_args(&cfg, device)?);
+
+        // Physical devices are already built for by id
+        match device {
+            Some(device) if !device.is_physical() && cfg.destination.is_none() => {
+                args.extend(["-destination".into(), format!("id={}", device.udid())]);
+            }
+            _ => {}
+        }
+
+        args.extend(only_testing.iter().map(|t| format!("-only-testing:{t}")));
+        args.extend_from_slice(flags);
+
+        task.debug(format!("[{target}] {}", shell_words::join(&args)));
+
+        let mut process = Process::new(xcodebuild::path());
+        process.args(&args);
+        process.current_dir(self.root());
+        xcodebuild::set_envs(&mut process);
+
+        let recv = task.consume_outcome_with(Box::new(process), broadcast.formatter())?;
+
+        Ok(task.build_result(recv))
+    }
+
+    /// Get sdk/destination arguments for selected device, or target platform default sdk.
+    ///
+    /// Errors if sdk or destination set in build settings can't build the target platform, or if
+    /// destination doesn't match the selected device.
+    fn destination_args(
+        &self,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+    ) -> Result<Vec<String>> {
+        let platform = self
+            .targets()
+            .get(&cfg.target)
+            .map(|info| info.platform.as_str())
+            .unwrap_or_default();
+
+        cfg.validate_platform(platform)?;
+
+        if let Some(device) = device.filter(|device| !device.supports(platform)) {
+            return Err(Error::Build(format!(
+                "{} ({}) can't run {} ({platform} target)",
+                device.name(),
+                device.platform(),
+                cfg.target
+            )));
+        }
+
+        // Explicit sdk or destination take precedence and are already part of xcodebuild args
+        match (cfg.destination.as_ref(), device) {
+            (Some(destination), Some(device)) if !destination.contains(device.udid()) => {
+                return Err(Error::Build(format!(
+                    "{} destination `{destination}` doesn't match {} ({}), unset it to build for \
+                     the device",
+                    cfg.target,
+                    device.name(),
+                    device.udid()
+                )));
+            }
+            (Some(_), _) => return Ok(vec![]),
+            // Physical devices are still built for by id, sdk alone only sets their platform
+            _ if cfg.sdk.is_some() => {
+                return Ok(device
+                    .filter(|device| device.is_physical())
+                    .map(Destination::special_build_args)
+                    .unwrap_or_default())
+            }
+            _ => {}
+        }
+
+        Ok(match device {
+            Some(device) => device.special_build_args(),
+            None => BuildSettings::default_sdk(platform)
+                .map(|sdk| vec!["-sdk".into(), sdk.into()])
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Remove build cache root and compile database
+    async fn remove_build_cache(&self) -> Result<()> {
+        let cache_root = fs::get_build_cache_dir(self.root())?;
+        if Path::new(&cache_root).exists() {
+            tokio::fs::remove_dir_all(&cache_root).await?;
+        }
+
+        let config = ProjectConfig::load(self.root()).await;
+        for path in config.compile_database_paths() {
+            let path = self.root().join(path);
+            if path.exists() {
+                tokio::fs::remove_file(path).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get xcodebuild arguments shared between build and clean, signing for physical devices
+    fn xcodebuild_args(
+        &self,
+        cfg: &BuildSettings,
+        device: Option<&Destination>,
+    ) -> Result<Vec<String>> {
+        cfg.validate_overrides()?;
+        let mut args = cfg.to_args();
+        args.extend(self.derived_data_args());
+        let name = self.name().to_owned();
 
         let cache_build_root = fs::get_build_cache_dir_with_config(self.root(), cfg)?;
 
         args.extend_from_slice(&[
             format!("SYMROOT={cache_build_root}"),
             "-allowProvisioningUpdates".into(),
-            "CODE_SIGN_ENTITLEMENTS= ".into(),
-            "CODE_SIGN_IDENTITY= ".into(),
-            "CODE_SIGNING_REQUIRED=NO".into(),
-            "CODE_SIGNING_ALLOWED=NO".into(),
         ]);
+        args.extend(disable_signing_args(device));
+        args.extend_from_slice(xcodebuild::global_args());
+        args.extend(cfg.override_args());
 
-        if self.root().join(&xcworkspace).exists() {
-            args.iter_mut().for_each(|arg| {
-                if arg == "-target" {
-                    *arg = "-scheme".into()
+        if let Some(xcworkspace) = self.workspace() {
+            if cfg.scheme.is_some() {
+                // xcodebuild doesn't accept -target along with -workspace
+                if let Some(idx) = args.iter().position(|arg| arg == "-target") {
+                    args.drain(idx..=idx + 1);
                 }
-            });
+            } else {
+                args.iter_mut().for_each(|arg| {
+                    if arg == "-target" {
+                        *arg = "-scheme".into()
+                    }
+                });
+            }
+            let xcworkspace = xcworkspace.to_string_lossy().to_string();
             args.extend_from_slice(&["-workspace".into(), xcworkspace]);
         } else {
             args.extend_from_slice(&["-project".into(), format!("{}.xcodeproj", name)]);
         }
 
-        task.debug(format!("[{target}] {}", args.join(" ")));
+        Ok(args)
+    }
+
+    /// Ensure the requested configuration is known for the target to build, and xcconfig and
+    /// build setting overrides, if any, are valid and supported by the project build tool
+    fn validate_configuration(&self, cfg: &BuildSettings) -> Result<()> {
+        let kind = self.kind();
+        if matches!(kind, ProjectKind::Swift | ProjectKind::Bazel) {
+            if cfg.xcconfig.is_some() {
+                return Err(Error::Build(format!(
+                    "xcconfig files are not supported by {kind:?} projects"
+                )));
+            }
+            if !cfg.overrides.is_empty() {
+                return Err(Error::Build(format!(
+                    "Build setting overrides are not supported by {kind:?} projects"
+                )));
+            }
+        }
+        cfg.validate_xcconfig(self.root())?;
+
+        let configuration = match cfg.configuration.as_ref() {
+            Some(configuration) => configuration,
+            None => return Ok(()),
+        };
 
-        let recv = task.consume(Box::new(XCLogger::new(self.root(), &args)?))?;
+        // Schemes accept configurations of any target
+        let mut available = match self.targets().get(&cfg.target) {
+            Some(info) => info.configurations.clone(),
+            None => self
+                .targets()
+                .values()
+                .flat_map(|info| info.configurations.iter().cloned())
+                .collect(),
+        };
+        if available.is_empty() {
+            available = BuildSettings::DEFAULT_CONFIGURATIONS
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+        }
+        available.sort();
+        available.dedup();
 
-        Ok((args, recv))
+        if available.contains(configuration) {
+            Ok(())
+        } else {
+            let target = &cfg.target;
+            Err(Error::Build(format!(
+                "Unknown configuration `{configuration}` for {target}, available: {}",
+                available.join(", ")
+            )))
+        }
     }
 
     /// Get build cache root
@@ -106,23 +506,52 @@ pub trait ProjectBuild: ProjectData {
 
 #[async_trait::async_trait]
 pub trait ProjectRun: ProjectData + ProjectBuild {
-    fn get_runner(
+    /// Get runner of cfg target, building it first if `build` is true
+    async fn get_runner(
         &self,
         cfg: &BuildSettings,
-        device: Option<&Device>,
+        device: Option<&Destination>,
+        build: bool,
         broadcast: &Arc<Broadcast>,
     ) -> Result<(
         Box<dyn Runner + Send + Sync>,
         Vec<String>,
-        tokio::sync::mpsc::Receiver<bool>,
+        tokio::sync::mpsc::Receiver<Result<()>>,
     )> {
-        let (args, recv) = self.build(cfg, device, broadcast)?;
+        let target = self.targets().get(&cfg.target);
+        if let Some(product_type) = target.and_then(|info| info.product_type.as_ref()) {
+            if !product_type.is_runnable() {
+                return Err(Error::Run(format!(
+                    "{} can't be ran, it builds a {product_type:?} product",
+                    cfg.target
+                )));
+            }
+        }
 
-        let info = XCBuildSettings::new_sync(self.root(), &args)?;
+        let (args, recv) = if build {
+            self.build(cfg, device, &[], broadcast)?
+        } else {
+            (self.args_for(cfg, device)?, built())
+        };
+
+        let info = XCBuildSettings::new(self.root(), &args).await?;
+        let is_macos = self
+            .targets()
+            .get(&cfg.target)
+            .map_or(false, |info| info.platform == "macOS");
 
         let runner: Box<dyn Runner + Send + Sync> = match device {
-            Some(device) => Box::new(SimulatorRunner::new(device.clone(), &info)),
-            None => Box::new(BinRunner::from_build_info(&info)),
+            Some(Destination::Simulator(device)) => {
+                Box::new(SimulatorRunner::new(device.clone(), &info))
+            }
+            Some(Destination::Physical(device)) => {
+                Box::new(PhysicalRunner::new(device.clone(), &info))
+            }
+            // macOS apps run as a process, without a device to install to
+            None => match MacRunner::from_build_info(&info).filter(|_| is_macos) {
+                Some(runner) => Box::new(runner),
+                None => Box::new(BinRunner::from_build_info(&info)),
+            },
         };
 
         Ok((runner, args, recv))
@@ -134,9 +563,164 @@ pub trait ProjectCompile: ProjectData {
     /// Generate compile database in project root
     async fn update_compile_database(&self, broadcast: &Arc<Broadcast>) -> Result<()>;
 
+    /// Write compile database to its configured paths and persist its index store path
+    async fn write_compile_database(&self, content: Vec<u8>) -> Result<()> {
+        let root = self.root();
+        let commands = serde_json::from_slice::<Vec<serde_json::Value>>(&content)?;
+
+        if let Some(index_store_path) = compile::index_store_path(&commands) {
+            tracing::debug!("Using index store path {index_store_path:?}");
+            compile::persist_index_store_path(root, &index_store_path).await?;
+        }
+
+        // Readers (i.e. language server) must never see a partial file
+        compile::write_database(root, content).await
+    }
+
+    /// Replace compile database with one generated by compile builds, only if they succeeded.
+    ///
+    /// Otherwise, the previous compile database is kept, so sources are still indexed with stale
+    /// but complete compile commands. Without a previous one, the incomplete one is written.
+    async fn replace_compile_database(
+        &self,
+        success: bool,
+        content: Vec<u8>,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<()> {
+        let name = self.name();
+        if !success {
+            if compile::database_path(self.root()).await.exists() {
+                broadcast.warn(format!(
+                    "[{name}] Kept previous compile database (build failed)"
+                ));
+                return Err(Error::Compile);
+            }
+            broadcast.warn(format!(
+                "[{name}] Compile database is incomplete (build failed)"
+            ));
+        }
+
+        self.write_compile_database(content).await
+    }
+
+    /// Update compile commands of changed source files only, reusing the existing compile database.
+    ///
+    /// Falls back to full regeneration when there is no compile database or on structural changes,
+    /// e.g. new files.
+    async fn update_compile_database_incremental(
+        &self,
+        changed: &[PathBuf],
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<()> {
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        if self.compile_database_with(changed).await.is_none() {
+            return self.update_compile_database(broadcast).await;
+        }
+
+        let updated = self.incremental_compile_commands(broadcast).await?;
+        self.merge_compile_database(updated, changed, broadcast)
+            .await
+    }
+
+    /// Merge updated compile commands of changed source files, e.g. logged by a build, into
+    /// compile database.
+    ///
+    /// Falls back to full regeneration when there is no compile database or on structural changes.
+    async fn merge_compile_database(
+        &self,
+        updated: Vec<serde_json::Value>,
+        changed: &[PathBuf],
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<()> {
+        if updated.is_empty() {
+            return Ok(());
+        }
+
+        let mut commands = match self.compile_database_with(changed).await {
+            Some(commands) => commands,
+            None => return self.update_compile_database(broadcast).await,
+        };
+
+        if !compile::merge_commands(&mut commands, updated, changed) {
+            tracing::debug!("Compile commands changed structurally, regenerating");
+            return self.update_compile_database(broadcast).await;
+        }
+
+        self.write_compile_database(serde_json::to_vec_pretty(&commands)?)
+            .await?;
+        broadcast.reload_lsp_server();
+        Ok(())
+    }
+
+    /// Read compile database, if it exists and has commands for all the given source files
+    async fn compile_database_with(&self, files: &[PathBuf]) -> Option<Vec<serde_json::Value>> {
+        let path = compile::database_path(self.root()).await;
+        let content = tokio::fs::read(path).await.ok()?;
+        let commands = serde_json::from_slice::<Vec<serde_json::Value>>(&content).ok()?;
+        files
+            .iter()
+            .all(|file| compile::contains_file(&commands, file))
+            .then(|| commands)
+    }
+
+    /// Get compile commands of sources recompiled by an incremental (i.e. without clean) build
+    async fn incremental_compile_commands(
+        &self,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<Vec<serde_json::Value>> {
+        let (name, root) = (self.name(), self.root());
+        let cache_root = fs::get_build_cache_dir(root)?;
+        let mut args = self.compile_arguments();
+        args.retain(|arg| arg != "clean");
+        args.push(format!("SYMROOT={cache_root}"));
+
+        let builds = match self.workspace() {
+            Some(workspace) => self
+                .schemes()
+                .into_iter()
+                .map(|scheme| {
+                    let workspace = workspace.display().to_string();
+                    vec!["-workspace".into(), workspace, "-scheme".into(), scheme]
+                })
+                .collect::<Vec<Vec<String>>>(),
+            None => vec![vec!["-project".into(), format!("{name}.xcodeproj")]],
+        };
+
+        let task = Task::new(TaskKind::Compile, name, broadcast.clone());
+        let mut commands = vec![];
+
+        for build_args in builds {
+            let mut args = args.clone();
+            args.extend(build_args);
+
+            let mut xclogger = XCLogger::new(root, &args)?;
+            xcodebuild::set_envs(&mut xclogger);
+            let compile_commands = xclogger.compile_commands.clone();
+            let success = task
+                .consume(Box::new(xclogger))?
+                .recv()
+                .await
+                .unwrap_or_default();
+
+            if !success {
+                return Err(Error::Compile);
+            }
+
+            for command in compile_commands.lock().await.iter() {
+                commands.push(serde_json::to_value(command)?);
+            }
+        }
+
+        add_clang_units(&mut commands, task.clang_units().await);
+        Ok(commands)
+    }
+
     /// Get compile arguments
     fn compile_arguments(&self) -> Vec<String> {
-        vec![
+        let mut args = vec![
             "clean",
             "build",
             "-configuration",
@@ -148,7 +732,12 @@ pub trait ProjectCompile: ProjectData {
         ]
         .iter()
         .map(ToString::to_string)
-        .collect()
+        .collect::<Vec<_>>();
+
+        // Same DerivedData as builds, so index store paths in compile commands match
+        args.extend(self.derived_data_args());
+        args.extend_from_slice(xcodebuild::global_args());
+        args
     }
 }
 
@@ -172,78 +761,54 @@ pub trait Project:
     where
         Self: Sized;
 
+    /// Re-read xcodeproj and update targets, without regenerating it.
+    ///
+    /// Returns true if targets were added or removed.
+    async fn reload(&mut self, _broadcast: &Arc<Broadcast>) -> Result<bool> {
+        Ok(false)
+    }
+
     #[tracing::instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name()))]
     async fn ensure_setup(
         &mut self,
         event: Option<&Event>,
         broadcast: &Arc<Broadcast>,
     ) -> Result<bool> {
-        use tokio::fs::File;
-        use tokio::io::AsyncWriteExt;
-
         let root = self.root();
-        let compile_path = root.join(".compile");
+        let compile_path = compile::database_path(root).await;
         let is_swift_project = root.join("Package.swift").exists();
 
-        /// Server Config
-        static BUILD_SERVER_CONFIG: Lazy<Vec<u8>> = Lazy::new(|| {
-            let path: PathBuf = BIN_ROOT
-                .replace("$HOME", &std::env::var("HOME").unwrap())
-                .into();
-            serde_json::json!({
-                "name": "XBase",
-                "argv": [path.join("xbase-sourcekit-helper")],
-                "version": "0.3",
-                "bspVersion": "0.2",
-                "languages": ["swift", "objective-c", "objective-cpp", "c", "cpp"]
-            })
-            .to_string()
-            .into_bytes()
-        });
-
         if !is_swift_project {
-            let build_server_path = root.join("buildServer.json");
-            let build_server_file_exists = build_server_path.exists();
-
-            let outdated = if build_server_file_exists {
-                let content = tokio::fs::read(&build_server_path).await?;
-                serde_json::from_slice::<serde_json::Value>(&content)
-                    .unwrap()
-                    .get("version")
-                    .and_then(|v| v.as_str())
-                    .map(|v| v != "0.3")
-                    .unwrap_or_default()
-            } else {
-                false
-            };
+            compile::ensure_build_server_config(root, false).await?;
+        }
 
-            if !build_server_path.exists() || outdated {
-                // NOTE: Use broadcast
-                tracing::info!("Creating {:?}", build_server_path);
-                let mut build_server_file = File::create(build_server_path).await?;
-                build_server_file.write_all(&BUILD_SERVER_CONFIG).await?;
-                build_server_file.sync_all().await?;
-                build_server_file.shutdown().await?;
-            }
-        };
+        let name = self.name().to_string();
 
         if let Some(event) = event {
             if self.should_generate(event) {
-                self.generate(broadcast).await.map_err(|err| {
-                    Error::Setup(self.name().to_string(), format!("Generation failure {err}"))
+                broadcast.task_started(TaskKind::Generate, &name);
+                let generated = self.generate(broadcast).await;
+                broadcast.task_finished(TaskKind::Generate, &name, generated.is_ok());
+                generated.map_err(|err| {
+                    Error::Setup(name.clone(), format!("Generation failure {err}"))
+                })?;
+
+                broadcast.task_started(TaskKind::Compile, &name);
+                let compiled = self.update_compile_database(broadcast).await;
+                broadcast.task_finished(TaskKind::Compile, &name, compiled.is_ok());
+                compiled.map_err(|err| {
+                    Error::Setup(name.clone(), format!("Compile database: {err}"))
                 })?;
-                self.update_compile_database(broadcast)
-                    .await
-                    .map_err(|err| {
-                        Error::Setup(self.name().to_string(), format!("Compile database: {err}"))
-                    })?;
                 broadcast.reload_lsp_server();
                 return Ok(true);
             }
         }
 
         if !is_swift_project && !compile_path.exists() {
-            self.update_compile_database(broadcast).await.unwrap();
+            broadcast.task_started(TaskKind::Compile, &name);
+            let compiled = self.update_compile_database(broadcast).await;
+            broadcast.task_finished(TaskKind::Compile, &name, compiled.is_ok());
+            compiled.map_err(|err| Error::Setup(name, format!("Compile database: {err}")))?;
             broadcast.reload_lsp_server();
             Ok(true)
         } else {
@@ -252,24 +817,411 @@ pub trait Project:
     }
 }
 
+/// Notify clients when a generator isn't installed, on how to install it
+fn check_generator(root: &Path, name: &str, broadcast: &Arc<Broadcast>) {
+    if fs::which(name).is_err() {
+        let project = root.name().unwrap_or_default();
+        broadcast.warn(format!("[{project}] {}", Error::generator_missing(name)));
+    }
+}
+
+/// Update targets with latest ones read from xcodeproj, keeping whether surviving targets are
+/// watched.
+///
+/// Returns sorted names of added and removed targets.
+fn merge_targets(
+    targets: &mut HashMap<String, TargetInfo>,
+    latest: HashMap<String, TargetInfo>,
+) -> (Vec<String>, Vec<String>) {
+    let mut removed = targets
+        .keys()
+        .filter(|name| !latest.contains_key(*name))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut added = vec![];
+
+    for name in removed.iter() {
+        targets.remove(name);
+    }
+
+    for (name, mut info) in latest {
+        match targets.get(&name) {
+            Some(existing) => info.watching = existing.watching,
+            None => added.push(name.clone()),
+        }
+        targets.insert(name, info);
+    }
+
+    added.sort();
+    removed.sort();
+    (added, removed)
+}
+
+/// Get targets of xcodeproj, along with their product type and bundle id
+fn xcodeproj_targets(xcodeproj: &xcodeproj::XCodeProject) -> HashMap<String, TargetInfo> {
+    let mut targets = xcodeproj
+        .targets_info()
+        .into_iter()
+        .map(|(k, info)| (k, info.into()))
+        .collect();
+    metadata::apply(xcodeproj, &mut targets);
+    targets
+}
+
+/// Reload targets from xcodeproj, broadcasting added and removed targets.
+///
+/// Returns true if targets were added or removed.
+fn reload_targets(
+    name: &str,
+    targets: &mut HashMap<String, TargetInfo>,
+    xcodeproj: &xcodeproj::XCodeProject,
+    broadcast: &Arc<Broadcast>,
+) -> bool {
+    let latest = xcodeproj_targets(xcodeproj);
+    let (added, removed) = merge_targets(targets, latest);
+
+    if !added.is_empty() {
+        broadcast.info(format!("[{name}] Targets added: {}", added.join(", ")));
+    }
+    if !removed.is_empty() {
+        broadcast.info(format!("[{name}] Targets removed: {}", removed.join(", ")));
+    }
+
+    !added.is_empty() || !removed.is_empty()
+}
+
+/// Build errors reliably fixed by a clean build, matched case-insensitively:
+///
+/// - `could not build Objective-C module`: stale module cache
+/// - `has been modified since the module file`: module built from since changed headers
+/// - `is out of date and needs to be rebuilt`: outdated module file
+/// - `PCH was compiled with module cache path`: module cache moved, e.g. DerivedData changed
+/// - `malformed or corrupted AST file`: corrupted module cache
+pub const RECOVERABLE_BUILD_ERRORS: [&str; 5] = [
+    "could not build objective-c module",
+    "has been modified since the module file",
+    "is out of date and needs to be rebuilt",
+    "pch was compiled with module cache path",
+    "malformed or corrupted ast file",
+];
+
+/// Whether a build error is one of [`RECOVERABLE_BUILD_ERRORS`]
+fn is_recoverable_build_error(err: &Error) -> bool {
+    let diagnostics = match err {
+        Error::BuildFailed(_, diagnostics) => diagnostics,
+        _ => return false,
+    };
+
+    diagnostics.iter().any(|diagnostic| {
+        let message = diagnostic.message.to_lowercase();
+        RECOVERABLE_BUILD_ERRORS.iter().any(|e| message.contains(e))
+    })
+}
+
+/// Result receiver of a build that's skipped, as it's already built
+pub(crate) fn built() -> tokio::sync::mpsc::Receiver<Result<()>> {
+    let (send, recv) = tokio::sync::mpsc::channel(1);
+    send.try_send(Ok(())).ok();
+    recv
+}
+
+/// Get xcodebuild arguments disabling code signing, none for physical devices as they require
+/// signing, so signing settings of the project or build overrides apply
+fn disable_signing_args(device: Option<&Destination>) -> Vec<String> {
+    if device.map_or(false, Destination::is_physical) {
+        return vec![];
+    }
+    [
+        "CODE_SIGN_ENTITLEMENTS= ",
+        "CODE_SIGN_IDENTITY= ",
+        "CODE_SIGNING_REQUIRED=NO",
+        "CODE_SIGNING_ALLOWED=NO",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+/// Build target with xcodebuild, tracking compile commands it logs
+fn xcodebuild_build(
+    root: &Path,
+    target: &str,
+    args: Vec<String>,
+    broadcast: &Arc<Broadcast>,
+) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<Result<()>>)> {
+    // Identical build requests, e.g. from multiple clients, share the build in flight
+    let (started, recv) = broadcast.shared_builds().share(&args.join(" "), || {
+        let task = Task::new(TaskKind::Build, target, broadcast.clone());
+
+        task.debug(format!("[{target}] {}", shell_words::join(&args)));
+
+        let mut xclogger = XCLogger::new(root, &args)?;
+        xcodebuild::set_envs(&mut xclogger);
+        let commands = xclogger.compile_commands.clone();
+        let recv = task.consume_outcome(Box::new(xclogger))?;
+        let recv = broadcast.coordinator().track(target, commands, recv);
+        Ok(retry_with_clean(
+            root,
+            &args,
+            target,
+            task.build_result(recv),
+            broadcast,
+        ))
+    })?;
+
+    if !started {
+        broadcast.info(format!(
+            "[{target}] Waiting for identical build in progress"
+        ));
+    }
+
+    Ok((args, recv))
+}
+
+/// Retry a failed xcodebuild build once with `clean`, if it failed with a recoverable error.
+///
+/// Resolves with the original error if the retry fails too.
+fn retry_with_clean(
+    root: &Path,
+    args: &[String],
+    target: &str,
+    mut recv: tokio::sync::mpsc::Receiver<Result<()>>,
+    broadcast: &Arc<Broadcast>,
+) -> tokio::sync::mpsc::Receiver<Result<()>> {
+    let (root, target, broadcast) = (root.to_path_buf(), target.to_string(), broadcast.clone());
+    let mut args = args.to_vec();
+    let (send, result) = tokio::sync::mpsc::channel(1);
+
+    tokio::spawn(async move {
+        let result = match recv.recv().await {
+            Some(Err(err)) if is_recoverable_build_error(&err) => {
+                let task = Task::new(TaskKind::Build, &target, broadcast);
+                task.warn(format!(
+                    "[{target}] Detected stale cache, cleaning and retrying…"
+                ));
+                args.insert(0, "clean".into());
+
+                let retry = match XCLogger::new(&root, &args) {
+                    Ok(mut logger) => {
+                        xcodebuild::set_envs(&mut logger);
+                        task.consume_outcome(Box::new(logger))
+                    }
+                    Err(err) => Err(Error::from(err)),
+                };
+                match retry {
+                    Ok(recv) => match task.build_result(recv).recv().await {
+                        Some(Ok(())) => Ok(()),
+                        _ => Err(err),
+                    },
+                    Err(_) => Err(err),
+                }
+            }
+            Some(result) => result,
+            None => return,
+        };
+        send.send(result).await.ok();
+    });
+
+    result
+}
+
 /// Alias for Box Project
 pub type ProjectImpl = Box<dyn Project + Send + Sync>;
 
 /// Create a project from given client
 
 pub async fn project(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<ProjectImpl> {
+    let is_swift_package = root.join("Package.swift").exists();
+    let explicit_generator = if root.join("project.yml").exists() {
+        Some("project.yml")
+    } else if root.join("Project.swift").exists() {
+        Some("Project.swift")
+    } else {
+        None
+    };
+
+    if let (true, Some(generator)) = (is_swift_package, explicit_generator) {
+        broadcast.warn(format!(
+            "Found both {generator} and Package.swift, using {generator}"
+        ));
+    }
+
     Ok(if root.join("project.yml").exists() {
         Box::new(xcodegen::XCodeGenProject::new(root, broadcast).await?)
-    } else if root.join("Package.swift").exists() {
-        Box::new(swift::SwiftProject::new(root, broadcast).await?)
     } else if root.join("Project.swift").exists() {
         Box::new(tuist::TuistProject::new(root, broadcast).await?)
+    } else if bazel::BazelProject::is_workspace(root) {
+        Box::new(bazel::BazelProject::new(root, broadcast).await?)
+    } else if is_swift_package {
+        Box::new(swift::SwiftProject::new(root, broadcast).await?)
     } else {
         Box::new(barebone::BareboneProject::new(root, broadcast).await?)
     })
 }
 
-async fn generate_watchignore<P: AsRef<Path>>(root: P) -> Vec<String> {
+/// Environment variables passed on to project load hook, the rest is dropped
+const LOAD_HOOK_ENV: [&str; 9] = [
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "LC_ALL",
+    "TMPDIR",
+    "DEVELOPER_DIR",
+];
+
+/// Get `env` arguments to run a load hook command with a sanitized environment
+fn load_hook_args(command: &str) -> Vec<String> {
+    let env = LOAD_HOOK_ENV
+        .iter()
+        .flat_map(|key| Some(format!("{key}={}", std::env::var(key).ok()?)));
+
+    std::iter::once("-i".to_string())
+        .chain(env)
+        .chain(["/bin/sh".into(), "-c".into(), command.into()])
+        .collect()
+}
+
+/// Run `on_load` command set in project config, if any, streaming its output to clients.
+///
+/// Only ran when a project is first loaded, not when it's relocated. Hooks that go silent are
+/// killed by the process timeout, like other spawned processes.
+pub async fn run_load_hook(root: &Path, broadcast: &Arc<Broadcast>) -> Result<()> {
+    let command = match ProjectConfig::load(root).await.on_load {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+    let name = root.name().unwrap_or_default();
+    let task = Task::new(TaskKind::Hook, &name, broadcast.clone());
+    let mut process = Process::new("/usr/bin/env");
+
+    process.args(&load_hook_args(&command));
+    process.current_dir(root);
+
+    task.info(format!("[{name}] Running `{command}`"));
+    let outcome = task
+        .consume_outcome(Box::new(process))?
+        .recv()
+        .await
+        .unwrap_or_default();
+
+    if outcome.success {
+        Ok(())
+    } else {
+        Err(Error::LoadHook(command, outcome.summary()))
+    }
+}
+
+/// Get DerivedData path set in project config, relative to root, creating it if missing
+async fn derived_data_path(root: &Path, broadcast: &Arc<Broadcast>) -> Option<PathBuf> {
+    let path = root.join(ProjectConfig::load(root).await.derived_data_path?);
+
+    if let Err(err) = tokio::fs::create_dir_all(&path).await {
+        let path = path.display();
+        broadcast.warn(format!(
+            "Failed to create DerivedData {path}: {err}, using default"
+        ));
+        return None;
+    }
+
+    Some(path)
+}
+
+/// Select xcodeproj to use from paths found in root.
+///
+/// Warns clients when the choice is ambiguous, i.e. several paths exist and project config doesn't
+/// specify which one to use.
+async fn select_xcodeproj(
+    root: &Path,
+    paths: Vec<PathBuf>,
+    broadcast: &Arc<Broadcast>,
+) -> Option<PathBuf> {
+    let config = ProjectConfig::load(root).await;
+    let preferred = config.xcodeproj.as_deref();
+    let is_ambiguous = paths.len() > 1;
+    let path = pick_xcodeproj(root, paths, preferred)?;
+
+    if let Some(preferred) = preferred {
+        if !is_preferred_xcodeproj(root, &path, preferred) {
+            let msg = format!("No xcodeproj matches configured `{preferred}`");
+            broadcast.warn(format!("{msg}, using {}", path.display()));
+        }
+    } else if is_ambiguous {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let msg = format!("Found more then one xcodeproj, using {name}");
+        broadcast.warn(format!(
+            "{msg}, set `xcodeproj` in .xbase.json to choose another"
+        ));
+    }
+
+    Some(path)
+}
+
+/// Pick xcodeproj from paths found in root.
+///
+/// Prefers the configured xcodeproj, then one named after root directory, then the first sorted.
+fn pick_xcodeproj(
+    root: &Path,
+    mut paths: Vec<PathBuf>,
+    preferred: Option<&str>,
+) -> Option<PathBuf> {
+    paths.sort();
+
+    if let Some(preferred) = preferred {
+        if let Some(path) = paths
+            .iter()
+            .find(|p| is_preferred_xcodeproj(root, p, preferred))
+        {
+            return Some(path.clone());
+        }
+    }
+
+    let name = root.file_name();
+    paths
+        .iter()
+        .find(|p| p.file_stem() == name)
+        .or_else(|| paths.first())
+        .cloned()
+}
+
+/// Whether xcodeproj path matches a configured name or relative path
+fn is_preferred_xcodeproj(root: &Path, path: &Path, preferred: &str) -> bool {
+    let preferred = preferred.trim_end_matches('/');
+    path.strip_prefix(root)
+        .map_or(false, |p| p == Path::new(preferred))
+        || path.file_name().map_or(false, |name| name == preferred)
+        || path.file_stem().map_or(false, |name| name == preferred)
+}
+
+/// Add compile commands of clang units, unless commands already compile the same source file
+fn add_clang_units(commands: &mut Vec<serde_json::Value>, units: Vec<serde_json::Value>) {
+    for unit in units {
+        if !commands
+            .iter()
+            .any(|command| command["file"] == unit["file"])
+        {
+            commands.push(unit);
+        }
+    }
+}
+
+/// Serialize compile commands logged by xclog along with clang units missing from them
+fn compile_database<T>(logged: &T, units: Vec<serde_json::Value>) -> Result<Vec<u8>>
+where
+    T: serde::Serialize,
+{
+    let mut commands = match serde_json::to_value(logged)? {
+        serde_json::Value::Array(commands) => commands,
+        logged => return Ok(serde_json::to_vec_pretty(&logged)?),
+    };
+    add_clang_units(&mut commands, units);
+    Ok(serde_json::to_vec_pretty(&commands)?)
+}
+
+/// Get default watch ignore globs along with ones set in project configuration
+pub(crate) async fn generate_watchignore<P: AsRef<Path>>(root: P) -> Vec<String> {
+    let config = ProjectConfig::load(root.as_ref()).await;
     let mut default = vec![
         "**/.git/**".into(),
         "**/.*".into(),
@@ -281,13 +1233,177 @@ async fn generate_watchignore<P: AsRef<Path>>(root: P) -> Vec<String> {
         "**/Derived/**".into(),
     ];
 
+    // Written by the daemon, custom paths may not be ignored already
     default.extend(
-        fs::gitignore_to_glob_patterns(root)
-            .await
-            .unwrap_or_default(),
+        config
+            .compile_database_paths()
+            .iter()
+            .map(|path| path.display().to_string()),
     );
+    default.extend(config.watchignore_globs());
 
+    // With `gitignore` set, the watcher handles root .gitignore along with nested ones
+    if !config.gitignore {
+        default.extend(fs::root_gitignore_patterns(root.as_ref()).await);
+    }
     default.dedup();
 
     default
 }
+
+#[test]
+fn test_pick_xcodeproj() {
+    let root = Path::new("/projects/App");
+    let paths = vec![
+        root.join("Zoo.xcodeproj"),
+        root.join("App.xcodeproj"),
+        root.join("Bar.xcodeproj"),
+    ];
+
+    let pick = |preferred| pick_xcodeproj(root, paths.clone(), preferred).unwrap();
+
+    assert_eq!(pick(None), root.join("App.xcodeproj"));
+    assert_eq!(pick(Some("Zoo")), root.join("Zoo.xcodeproj"));
+    assert_eq!(pick(Some("Bar.xcodeproj")), root.join("Bar.xcodeproj"));
+    assert_eq!(pick(Some("Missing")), root.join("App.xcodeproj"));
+
+    let paths = vec![root.join("Zoo.xcodeproj"), root.join("Bar.xcodeproj")];
+    assert_eq!(
+        pick_xcodeproj(root, paths, None).unwrap(),
+        root.join("Bar.xcodeproj")
+    );
+    assert_eq!(pick_xcodeproj(root, vec![], None), None);
+}
+
+#[test]
+fn test_recoverable_build_error() {
+    let error = |message: &str| {
+        let diagnostic = BuildDiagnostic {
+            message: message.into(),
+            ..Default::default()
+        };
+        Error::BuildFailed("App".into(), vec![diagnostic])
+    };
+
+    assert!(is_recoverable_build_error(&error(
+        "could not build Objective-C module 'Kit'"
+    )));
+    assert!(!is_recoverable_build_error(&error(
+        "cannot find 'foo' in scope"
+    )));
+    assert!(!is_recoverable_build_error(&Error::Build("App".into())));
+}
+
+#[test]
+fn test_merge_targets() {
+    let info = |watching| TargetInfo {
+        platform: "iOS".into(),
+        configurations: vec![],
+        kind: BuildableKind::Target,
+        watching,
+        product_type: None,
+        bundle_id: None,
+    };
+    let mut targets = HashMap::from([("App".into(), info(true)), ("Old".into(), info(false))]);
+    let latest = HashMap::from([("App".into(), info(false)), ("Kit".into(), info(false))]);
+
+    let (added, removed) = merge_targets(&mut targets, latest);
+
+    assert_eq!(added, vec!["Kit"]);
+    assert_eq!(removed, vec!["Old"]);
+    assert!(targets["App"].watching);
+    assert!(!targets.contains_key("Old"));
+}
+
+#[test]
+fn test_load_hook_args() {
+    let args = load_hook_args("make generate");
+
+    assert_eq!(args[0], "-i");
+    assert_eq!(args[args.len() - 3..], ["/bin/sh", "-c", "make generate"]);
+    assert!(args[1..args.len() - 3].iter().all(|arg| {
+        let key = arg.split('=').next().unwrap();
+        LOAD_HOOK_ENV.contains(&key)
+    }));
+}
+
+#[test]
+fn test_watched_builds_supersede_compile_commands() {
+    use notify::event::{DataChange, EventKind, ModifyKind};
+    use std::fmt;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tokio::sync::mpsc::{channel, Receiver};
+
+    /// Watcher starting a tracked build whose outcome is sent by the test
+    struct TrackedWatcher(&'static str, Mutex<Option<Receiver<TaskOutcome>>>);
+
+    impl fmt::Display for TrackedWatcher {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "/tmp/Demo:Watch:{}", self.0)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Watchable for TrackedWatcher {
+        async fn trigger(&self, _: &mut ProjectImpl, _: &Event, b: &Arc<Broadcast>) -> Result<()> {
+            let outcome = self.1.lock().unwrap().take().unwrap();
+            b.coordinator().track(self.0, Default::default(), outcome);
+            Ok(())
+        }
+        async fn should_trigger(&self, _: &Event) -> bool {
+            true
+        }
+        async fn should_discard(&self, _: &Event) -> bool {
+            false
+        }
+        async fn discard(&self) {}
+        fn target(&self) -> &str {
+            self.0
+        }
+        fn kind(&self) -> TaskKind {
+            TaskKind::Build
+        }
+        fn relocate(&mut self, _: &Path) {}
+    }
+
+    let (runtime, broadcast, base) =
+        broadcast::test_broadcast("coordinate", "Demo", Default::default());
+    let root = base.join("Demo");
+
+    runtime.block_on(async {
+        let mut project: ProjectImpl = Box::new(barebone::BareboneProject::default());
+        let (send_app, recv_app) = channel(1);
+        let (send_widget, recv_widget) = channel(1);
+
+        // Build watcher and run watcher of the same project, reacting to the same change
+        let mut subscribers = WatchSubscribers::new(&"Demo".to_string());
+        subscribers.add(TrackedWatcher("App", Mutex::new(Some(recv_app))));
+        subscribers.add(TrackedWatcher("Widget", Mutex::new(Some(recv_widget))));
+
+        let ignore = wax::any::<wax::Glob, _>(vec!["**/.git/**"]).unwrap();
+        let state = WatcherState::new();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let kind = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        let event = notify::Event::new(kind).add_path(root.join("main.swift"));
+        let event = Event::new(&root, &ignore, &state, event).unwrap();
+
+        let mut triggered = subscribers.trigger(&mut project, &event, &broadcast).await;
+        triggered.sort();
+        assert_eq!(triggered, ["App", "Widget"]);
+
+        // Compile commands are only taken once both builds finished
+        send_app.send(TaskOutcome::default()).await.unwrap();
+        let coordinator = broadcast.coordinator();
+        let wait = coordinator.wait(&triggered);
+        tokio::pin!(wait);
+        let pending = tokio::time::timeout(Duration::from_millis(50), &mut wait).await;
+        assert!(pending.is_err(), "resolved before all builds finished");
+
+        send_widget.send(TaskOutcome::default()).await.unwrap();
+        assert_eq!(wait.await, Some(vec![]));
+        broadcast.abort();
+    });
+
+    std::fs::remove_dir_all(base).ok();
+}
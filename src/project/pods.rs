@@ -0,0 +1,67 @@
+//! CocoaPods integration, installing pods when Podfile.lock is outdated
+use crate::util::fs::which;
+use crate::*;
+use process_stream::Process;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Whether the project at root uses CocoaPods
+pub fn is_used(root: &Path) -> bool {
+    root.join("Podfile").exists()
+}
+
+/// Whether `pod install` should run, i.e. Podfile.lock is missing, older than Podfile or doesn't
+/// match the installed `Pods/Manifest.lock`
+pub fn is_outdated(root: &Path) -> bool {
+    let modified = |name: &str| std::fs::metadata(root.join(name)).and_then(|m| m.modified());
+    let podfile_changed = match (modified("Podfile"), modified("Podfile.lock")) {
+        (Ok(podfile), Ok(lock)) => podfile > lock,
+        _ => true,
+    };
+
+    let lock = std::fs::read(root.join("Podfile.lock")).ok();
+    let manifest = std::fs::read(root.join("Pods").join("Manifest.lock")).ok();
+
+    is_outdated_lock(podfile_changed, lock.as_deref(), manifest.as_deref())
+}
+
+fn is_outdated_lock(podfile_changed: bool, lock: Option<&[u8]>, manifest: Option<&[u8]>) -> bool {
+    match (lock, manifest) {
+        (Some(lock), Some(manifest)) => podfile_changed || lock != manifest,
+        _ => true,
+    }
+}
+
+/// Run `pod install` in project root, streaming output to clients
+pub async fn install(root: &Path, name: &str, broadcast: &Arc<Broadcast>) -> Result<()> {
+    let pod = which("pod").map_err(|_| Error::generator_missing("pod"))?;
+    let task = Task::new(TaskKind::Install, name, broadcast.clone());
+    let mut process: Process = vec![pod.as_str(), "install"].into();
+    process.current_dir(root);
+
+    task.debug(format!("[{name}] pod install"));
+
+    let success = task
+        .consume(Box::new(process))?
+        .recv()
+        .await
+        .unwrap_or_default();
+
+    if success {
+        Ok(())
+    } else {
+        Err(Error::PodInstall(format!(
+            "`pod install` failed for {name}, see logs for details"
+        )))
+    }
+}
+
+#[test]
+fn test_is_outdated_lock() {
+    let (lock, other): (&[u8], &[u8]) = (b"PODS: []", b"PODS: [A]");
+    assert!(is_outdated_lock(false, None, None));
+    assert!(is_outdated_lock(false, Some(lock), None));
+    assert!(is_outdated_lock(false, Some(lock), Some(other)));
+    assert!(is_outdated_lock(true, Some(lock), Some(lock)));
+    assert!(!is_outdated_lock(false, Some(lock), Some(lock)));
+}
@@ -3,6 +3,8 @@ use crate::watcher::Event;
 use crate::{Error, Result};
 use process_stream::Process;
 use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::{collections::HashMap, path::PathBuf};
 use tokio::process::Command;
 use xcodeproj::pbxproj::PBXTargetPlatform;
@@ -15,6 +17,12 @@ pub struct SwiftProject {
     targets: HashMap<String, TargetInfo>,
     num_clients: i32,
     watchignore: Vec<String>,
+    /// Hash of Package.swift content dependencies were last resolved with
+    #[serde(skip)]
+    resolved_manifest: Option<u64>,
+    /// Executable products, ran with `swift run`
+    #[serde(skip)]
+    executables: Vec<String>,
 }
 
 impl ProjectData for SwiftProject {
@@ -41,6 +49,10 @@ impl ProjectData for SwiftProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::Swift
+    }
 }
 
 #[async_trait::async_trait]
@@ -48,63 +60,158 @@ impl ProjectBuild for SwiftProject {
     fn build(
         &self,
         cfg: &BuildSettings,
-        _device: Option<&Device>,
+        _device: Option<&Destination>,
+        _flags: &[String],
         broadcast: &Arc<Broadcast>,
-    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<bool>)> {
-        let args = vec!["build", "--target", &cfg.target];
+    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<Result<()>>)> {
+        let args = self.args_for(cfg, None)?;
         let mut process = Process::new("/usr/bin/swift");
 
         process.args(&args);
         process.current_dir(self.root());
         let task = Task::new(TaskKind::Build, cfg.target.as_str(), broadcast.clone());
-        let recv = task.consume(Box::new(process))?;
+        let recv = task.consume_outcome_with(Box::new(process), Formatter::SwiftPM)?;
+
+        Ok((vec![], task.build_result(recv)))
+    }
 
-        Ok((vec![], recv))
+    fn args_for(&self, cfg: &BuildSettings, _device: Option<&Destination>) -> Result<Vec<String>> {
+        self.validate_configuration(cfg)?;
+
+        let mut args = vec!["build".to_string(), "--target".into(), cfg.target.clone()];
+        if let Some(ref configuration) = cfg.configuration {
+            args.extend_from_slice(&["-c".into(), configuration.to_lowercase()]);
+        }
+        Ok(args)
+    }
+
+    async fn clean(
+        &self,
+        cfg: &BuildSettings,
+        deep: bool,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<()> {
+        let target = &cfg.target;
+        let task = Task::new(TaskKind::Clean, target, broadcast.clone());
+
+        if deep {
+            task.info(format!("[{target}] Removing build cache"));
+            self.remove_build_cache().await?;
+        }
+
+        let mut process = Process::new("/usr/bin/swift");
+        process.args(&["package", if deep { "reset" } else { "clean" }]);
+        process.current_dir(self.root());
+
+        let success = task.consume(Box::new(process))?.recv().await;
+        if success.unwrap_or_default() {
+            Ok(())
+        } else {
+            Err(Error::Build(format!("Failed to clean {target}")))
+        }
+    }
+
+    fn test(
+        &self,
+        cfg: &BuildSettings,
+        _device: Option<&Destination>,
+        only_testing: &[String],
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<tokio::sync::mpsc::Receiver<Result<()>>> {
+        self.validate_configuration(cfg)?;
+
+        let mut args = vec!["test".to_string()];
+        if let Some(ref configuration) = cfg.configuration {
+            args.extend_from_slice(&["-c".into(), configuration.to_lowercase()]);
+        }
+        if !only_testing.is_empty() {
+            args.extend_from_slice(&["--filter".into(), only_testing.join("|")]);
+        }
+        let mut process = Process::new("/usr/bin/swift");
+
+        process.args(&args);
+        process.current_dir(self.root());
+        let task = Task::new(TaskKind::Test, cfg.target.as_str(), broadcast.clone());
+        let recv = task.consume_outcome_with(Box::new(process), Formatter::SwiftPM)?;
+
+        Ok(task.build_result(recv))
     }
 }
 
 #[async_trait::async_trait]
 impl ProjectRun for SwiftProject {
-    fn get_runner(
+    async fn get_runner(
         &self,
         cfg: &BuildSettings,
-        _device: Option<&Device>,
+        _device: Option<&Destination>,
+        build: bool,
         broadcast: &Arc<Broadcast>,
     ) -> Result<(
         Box<dyn Runner + Send + Sync>,
         Vec<String>,
-        tokio::sync::mpsc::Receiver<bool>,
+        tokio::sync::mpsc::Receiver<Result<()>>,
     )> {
-        let (args, recv) = self.build(cfg, None, broadcast)?;
+        self.validate_configuration(cfg)?;
+        let product = self.run_product(&cfg.target)?;
 
-        let output = std::process::Command::new("/usr/bin/swift")
-            .args(["build", "--show-bin-path"])
-            .current_dir(self.root())
-            .output()?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8(output.stderr).unwrap();
-            broadcast.open_logger();
-            return Err(Error::Run(format!(
-                "Getting target bin path failed {stderr}"
-            )));
+        let mut args = vec!["build".to_string(), "--product".into(), product.clone()];
+        if let Some(ref configuration) = cfg.configuration {
+            args.extend_from_slice(&["-c".into(), configuration.to_lowercase()]);
         }
 
-        // WARN: THIS MIGHT FAIL BECAUSE BUILD IS NOT YET RAN
-        let output = String::from_utf8(output.stdout).unwrap();
-        let bin_path = PathBuf::from(output.trim()).join(&cfg.target);
+        let recv = if build {
+            let mut process = Process::new("/usr/bin/swift");
+            process.args(&args);
+            process.current_dir(self.root());
+            let task = Task::new(TaskKind::Build, cfg.target.as_str(), broadcast.clone());
+            let process = Box::new(process);
+            task.build_result(task.consume_outcome_with(process, Formatter::SwiftPM)?)
+        } else {
+            built()
+        };
+
+        tracing::info!("Running {:?} product {product:?}", self.name());
 
-        tracing::info!("Running {:?} via {bin_path:?}", self.name());
+        let runner = SwiftRunner {
+            root: self.root().clone(),
+            product,
+            configuration: cfg.configuration.clone(),
+        };
 
-        Ok((Box::new(BinRunner::from_path(&bin_path)), args, recv))
+        Ok((Box::new(runner), args, recv))
     }
 }
 
 #[async_trait::async_trait]
 impl ProjectCompile for SwiftProject {
-    async fn update_compile_database(&self, _logger: &Arc<Broadcast>) -> Result<()> {
-        // No Compile database needed for swif projects
-        Ok(())
+    /// Generate compile database from compiler invocations logged by `swift build --verbose`
+    async fn update_compile_database(&self, broadcast: &Arc<Broadcast>) -> Result<()> {
+        let name = self.name();
+        let task = Task::new(TaskKind::Compile, name, broadcast.clone());
+
+        let mut command = Command::new("/usr/bin/swift");
+        command.args(["build", "--build-tests", "--verbose"]);
+        command.current_dir(self.root());
+        command.envs(xcodebuild::envs());
+
+        let output = command.output().await?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let commands = compile::swiftpm_commands(&stdout, self.root());
+        let success = output.status.success();
+        task.finish(success);
+
+        if commands.is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let msg = format!(
+                "[{name}] No compile command was generated: {}",
+                stderr.trim()
+            );
+            return Err(Error::Unexpected(msg));
+        }
+
+        let content = serde_json::to_vec_pretty(&commands)?;
+        self.replace_compile_database(success, content, broadcast)
+            .await
     }
 }
 #[async_trait::async_trait]
@@ -120,21 +227,28 @@ impl ProjectGenerate for SwiftProject {
             || event.is_rename_event()
     }
 
-    /// Generate xcodeproj
+    /// Resolve package dependencies when Package.swift changed and update targets
     async fn generate(&mut self, broadcast: &Arc<Broadcast>) -> Result<()> {
-        let mut process: Process = vec!["/usr/bin/swift", "build"].into();
-        let name = self.root().name().unwrap();
-        process.current_dir(self.root());
-
-        let task = Task::new(TaskKind::Compile, &name, broadcast.clone());
-        let success = task
-            .consume(Box::new(process))?
-            .recv()
-            .await
-            .unwrap_or_default();
-
-        if !success {
-            return Err(Error::Generate);
+        let manifest = self.manifest_hash().await;
+
+        // Skip saves that don't change the manifest, e.g. while still editing it
+        if manifest.is_none() || manifest != self.resolved_manifest {
+            let mut process: Process = vec!["/usr/bin/swift", "package", "resolve"].into();
+            let name = self.root().name().unwrap();
+            process.current_dir(self.root());
+
+            let task = Task::new(TaskKind::Install, &name, broadcast.clone());
+            let success = task
+                .consume(Box::new(process))?
+                .recv()
+                .await
+                .unwrap_or_default();
+
+            if !success {
+                return Err(Error::Generate);
+            }
+
+            self.resolved_manifest = manifest;
         }
 
         self.update_project_info().await?;
@@ -162,6 +276,7 @@ impl Project for SwiftProject {
             project.generate(broadcast).await?;
             return Ok(project);
         } else {
+            project.resolved_manifest = project.manifest_hash().await;
             project.update_project_info().await?;
             tracing::info!(
                 "(name: {:?}, targets: {:?})",
@@ -175,6 +290,37 @@ impl Project for SwiftProject {
 }
 
 impl SwiftProject {
+    /// Package manifest target kinds to register as project targets
+    const TARGET_KINDS: [&'static str; 2] = ["executable", "regular"];
+
+    /// Get executable product to run for a target.
+    ///
+    /// Target can either be the product name or, with a single executable product, any target.
+    fn run_product(&self, target: &str) -> Result<String> {
+        match self.executables.as_slice() {
+            executables if executables.iter().any(|name| name == target) => Ok(target.into()),
+            [product] => Ok(product.clone()),
+            [] => Err(Error::Run(format!(
+                "{} has no executable products",
+                self.name
+            ))),
+            executables => Err(Error::Run(format!(
+                "{target} is not an executable product, expected one of: {}",
+                executables.join(", ")
+            ))),
+        }
+    }
+
+    /// Get hash of Package.swift content
+    async fn manifest_hash(&self) -> Option<u64> {
+        let content = tokio::fs::read(self.root().join("Package.swift"))
+            .await
+            .ok()?;
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
     /// Read Package.swift and update internal state
     async fn update_project_info(&mut self) -> Result<()> {
         use anyhow::anyhow;
@@ -202,7 +348,6 @@ impl SwiftProject {
             return Err(Error::DefinitionParsing(error));
         };
 
-        // TODO(swift-package): only provide run service for executables
         self.name = map
             .get("name")
             .and_then(|v| v.as_str())
@@ -217,18 +362,25 @@ impl SwiftProject {
             .flat_map(|v| v.as_object())
             .flat_map(|target_info| {
                 let name = target_info.get("name")?.as_str()?.to_string();
-                if !target_info
-                    .get("type")
-                    .and_then(|s| s.as_str())
-                    .map(|s| s == "test")
-                    .unwrap_or_default()
-                {
+                let kind = target_info.get("type")?.as_str()?;
+                // Only executable and library (regular) targets can be built/ran
+                if Self::TARGET_KINDS.contains(&kind) {
                     Some((
                         name,
                         TargetInfo {
                             platform: PBXTargetPlatform::MacOS.to_string(),
-                            // TODO: get swift configurations
-                            configurations: vec!["Debug".into()],
+                            configurations: BuildSettings::DEFAULT_CONFIGURATIONS
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect(),
+                            kind: BuildableKind::Target,
+                            watching: false,
+                            product_type: Some(if kind == "executable" {
+                                ProductType::Tool
+                            } else {
+                                ProductType::StaticLibrary
+                            }),
+                            bundle_id: None,
                         },
                     ))
                 } else {
@@ -237,6 +389,44 @@ impl SwiftProject {
             })
             .collect();
 
+        self.executables = executable_products(&map);
+
         Ok(())
     }
 }
+
+/// Get names of executable products declared in `swift package dump-package` output
+fn executable_products(package: &serde_json::Map<String, serde_json::Value>) -> Vec<String> {
+    package
+        .get("products")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|product| product["type"].get("executable").is_some())
+        .flat_map(|product| product["name"].as_str())
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn test_run_product() {
+    let package = serde_json::json!({
+        "name": "Server",
+        "products": [
+            { "name": "server", "type": { "executable": null }, "targets": ["Server"] },
+            { "name": "migrate", "type": { "executable": null }, "targets": ["Migrate"] },
+            { "name": "ServerKit", "type": { "library": ["automatic"] }, "targets": ["Kit"] }
+        ]
+    });
+    let mut project = SwiftProject {
+        name: "Server".into(),
+        executables: executable_products(package.as_object().unwrap()),
+        ..Default::default()
+    };
+    assert_eq!(project.executables, vec!["server", "migrate"]);
+    assert_eq!(project.run_product("migrate").unwrap(), "migrate");
+    assert!(project.run_product("Server").is_err());
+
+    project.executables.truncate(1);
+    assert_eq!(project.run_product("Server").unwrap(), "server");
+}
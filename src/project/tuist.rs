@@ -3,9 +3,10 @@ use crate::util::fs::which;
 use crate::watcher::Event;
 use crate::{Error, Result};
 use futures::future::try_join_all;
-use futures::StreamExt;
-use process_stream::{Process, ProcessExt};
+use process_stream::Process;
 use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::{collections::HashMap, path::PathBuf};
 use tap::Pipe;
 use xcodeproj::XCodeProject;
@@ -18,15 +19,27 @@ pub struct TuistProject {
     num_clients: i32,
     watchignore: Vec<String>,
     #[serde(skip)]
-    xcodeproj: XCodeProject,
+    xcodeproj: Arc<XCodeProject>,
+    /// DerivedData path set in project config
+    #[serde(skip)]
+    derived_data: Option<PathBuf>,
     #[serde(skip)]
     xcodeproj_path: PathBuf,
     #[serde(skip)]
-    manifest: XCodeProject,
+    manifest: Arc<XCodeProject>,
     #[serde(skip)]
     manifest_path: PathBuf,
     #[serde(skip)]
     manifest_files: Vec<String>,
+    /// Directory relative to root containing Tuist manifests, set in project config
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    /// Whether to run `tuist fetch` when dependencies changed, set in project config
+    #[serde(skip)]
+    fetch: bool,
+    /// Hash of Tuist/Dependencies.swift content dependencies were last fetched with
+    #[serde(skip)]
+    fetched_dependencies: Option<u64>,
 }
 
 impl ProjectData for TuistProject {
@@ -53,6 +66,22 @@ impl ProjectData for TuistProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn derived_data(&self) -> Option<&PathBuf> {
+        self.derived_data.as_ref()
+    }
+
+    fn xcodeproj_path(&self) -> Option<&PathBuf> {
+        Some(&self.xcodeproj_path)
+    }
+
+    fn xcodeproj(&self) -> Option<&Arc<XCodeProject>> {
+        Some(&self.xcodeproj)
+    }
+
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::Tuist
+    }
 }
 #[async_trait::async_trait]
 impl ProjectCompile for TuistProject {
@@ -79,11 +108,12 @@ impl ProjectCompile for TuistProject {
                 "Manifests".into(),
             ]);
 
-            let xclogger = XCLogger::new(&root, &args)?;
+            let mut xclogger = XCLogger::new(&root, &args)?;
+            xcodebuild::set_envs(&mut xclogger);
             xccommands.push(xclogger.compile_commands.clone());
             tasks_recvs.push(task.consume(Box::new(xclogger))?);
 
-            let argsstr = args.join(" ");
+            let argsstr = shell_words::join(&args);
             tracing::info!("Building Manifest ...");
             tracing::trace!("\n\n xcodebuild {argsstr}\n\n");
             task.debug(format!("[{name}] {argsstr}"));
@@ -100,16 +130,17 @@ impl ProjectCompile for TuistProject {
                 scheme.name.clone(),
             ]);
 
-            let xclogger = XCLogger::new(&root, &args)?;
+            let mut xclogger = XCLogger::new(&root, &args)?;
+            xcodebuild::set_envs(&mut xclogger);
             xccommands.push(xclogger.compile_commands.clone());
             tasks_recvs.push(task.consume(Box::new(xclogger))?);
-            let argsstr = args.join(" ");
+            let argsstr = shell_words::join(&args);
             tracing::info!("Building {} ...", scheme.name);
             tracing::trace!("\n\n xcodebuild {argsstr}\n\n");
             task.debug(format!("[{name}] {argsstr}"));
         }
 
-        let _all_pass = tasks_recvs
+        let all_pass = tasks_recvs
             .into_iter()
             .map(|mut t| tokio::spawn(async move { t.recv().await.unwrap_or_default() }))
             .pipe(try_join_all)
@@ -129,12 +160,14 @@ impl ProjectCompile for TuistProject {
             .collect::<Vec<_>>();
 
         xccommands.dedup();
-        if xccommands.is_empty() {
+        let units = task.clang_units().await;
+        if xccommands.is_empty() && units.is_empty() {
             broadcast.warn("No compile command was generated!");
         }
 
-        let json = serde_json::to_vec_pretty(&xccommands)?;
-        tokio::fs::write(root.join(".compile"), &json).await?;
+        let json = compile_database(&xccommands, units)?;
+        self.replace_compile_database(all_pass, json, broadcast)
+            .await?;
 
         Ok(())
     }
@@ -157,23 +190,33 @@ impl ProjectGenerate for TuistProject {
     /// Generate xcodeproj
     async fn generate(&mut self, broadcast: &Arc<Broadcast>) -> Result<()> {
         let task = Task::new(TaskKind::Generate, self.name(), broadcast.clone());
+        if self.fetch {
+            let dependencies = self.dependencies_hash().await;
+            if dependencies.is_some() && dependencies != self.fetched_dependencies {
+                self.tuist(&task, &["fetch"]).await?;
+                self.fetched_dependencies = dependencies;
+            }
+        }
+
         self.tuist(&task, &["edit", "--permanent"]).await?;
         self.tuist(&task, &["generate", "--no-open"]).await?;
 
         let (xcodeproj_path, manifest_path) = self.xcodeproj_paths()?;
         let (xcodeproj_path, manifest_path) = (xcodeproj_path.unwrap(), manifest_path.unwrap());
 
-        self.manifest = XCodeProject::new(&manifest_path)?;
+        cache::invalidate_xcodeproj(&manifest_path);
+        cache::invalidate_xcodeproj(&xcodeproj_path);
+        self.manifest = cache::xcodeproj(&manifest_path)?;
         self.manifest_path = manifest_path;
-        self.xcodeproj = XCodeProject::new(&xcodeproj_path)?;
+        self.xcodeproj = cache::xcodeproj(&xcodeproj_path)?;
         self.xcodeproj_path = xcodeproj_path;
 
-        for (key, info) in self.xcodeproj.targets_info().into_iter() {
+        for (key, info) in xcodeproj_targets(&self.xcodeproj) {
             if self.targets.contains_key(&key) {
                 let existing_info = self.targets.get_mut(&key).unwrap();
-                *existing_info = info.into()
+                *existing_info = info
             } else {
-                self.targets.insert(key, info.into());
+                self.targets.insert(key, info);
             }
         }
 
@@ -182,13 +225,53 @@ impl ProjectGenerate for TuistProject {
 }
 
 impl TuistProject {
+    /// Markers of tuist errors about the installed version not matching the project's
+    const VERSION_ERRORS: [&'static str; 4] = [
+        "not compatible",
+        "incompatible",
+        "doesn't match",
+        "does not match",
+    ];
+
+    /// Directory containing Tuist manifests
+    fn manifest_root(&self) -> PathBuf {
+        match self.path {
+            Some(ref path) => self.root().join(path),
+            None => self.root().clone(),
+        }
+    }
+
+    /// Get hash of Tuist/Dependencies.swift content, if any
+    async fn dependencies_hash(&self) -> Option<u64> {
+        let path = self
+            .manifest_root()
+            .join("Tuist")
+            .join("Dependencies.swift");
+        let content = tokio::fs::read(path).await.ok()?;
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Get tuist error line about the installed version being incompatible, if any
+    fn version_error(lines: &[String]) -> Option<&String> {
+        lines.iter().find(|line| {
+            let line = line.to_lowercase();
+            line.contains("version") && Self::VERSION_ERRORS.iter().any(|m| line.contains(m))
+        })
+    }
+
     pub fn xcodeproj_paths(&self) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
-        let paths = self.get_xcodeproj_paths()?;
+        let mut paths = wax::walk("*.xcodeproj", self.manifest_root())
+            .map_err(|e| Error::Unexpected(e.to_string()))?
+            .flatten()
+            .map(|entry| entry.into_path())
+            .collect::<Vec<PathBuf>>();
+        paths.sort();
         if paths.is_empty() {
             return Ok((None, None));
         }
 
-        let (mut xcodeproj, mut manifest) = (None, None);
         if paths.len() > 2 {
             tracing::warn!(
                 "Expected `2` xcodeproj Manifest and Main but found `{}`",
@@ -196,37 +279,40 @@ impl TuistProject {
             )
         }
 
-        paths.into_iter().for_each(|p| {
-            if p.file_name()
+        let (manifests, paths): (Vec<_>, Vec<_>) = paths.into_iter().partition(|p| {
+            p.file_name()
                 .and_then(|s| s.to_str())
                 .map(|s| s.starts_with("Manifests"))
                 .unwrap_or_default()
-            {
-                manifest = p.into();
-            } else {
-                xcodeproj = p.into();
-            }
         });
-        Ok((xcodeproj, manifest))
+
+        let xcodeproj = pick_xcodeproj(self.root(), paths, None);
+        Ok((xcodeproj, manifests.into_iter().next()))
     }
 
     /// Run tuist command with given args
     async fn tuist(&mut self, task: &Task, args: &[&str]) -> Result<()> {
-        let mut process = Process::new(which("tuist")?);
+        let tuist = which("tuist").map_err(|_| Error::generator_missing("tuist"))?;
+        let mut process = Process::new(tuist);
 
         process.args(args);
+        if let Some(ref path) = self.path {
+            process.args(["--path".into(), path.display().to_string()]);
+        }
         process.current_dir(self.root());
-        let mut logs = process.spawn_and_stream()?.collect::<Vec<_>>().await;
-        let success = logs.pop().unwrap().is_success().unwrap_or_default();
 
-        if !success {
-            task.error("Tuist Project Generation failed ");
-            let logs = logs.into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        let outcome = task
+            .consume_outcome(Box::new(process))?
+            .recv()
+            .await
+            .unwrap_or_default();
 
-            for log in logs {
-                task.error(log)
+        if !outcome.success {
+            if let Some(line) = Self::version_error(&outcome.tail) {
+                return Err(Error::GeneratorIncompatible("tuist".into(), line.clone()));
             }
-
+            let args = shell_words::join(&args);
+            task.inner().error(format!("tuist {args} failed "));
             return Err(Error::Generate);
         }
 
@@ -237,6 +323,8 @@ impl TuistProject {
 #[async_trait::async_trait]
 impl Project for TuistProject {
     async fn new(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<Self> {
+        check_generator(root, "tuist", broadcast);
+        let config = ProjectConfig::load(root).await;
         let mut watchignore = generate_watchignore(root).await;
 
         watchignore.extend([
@@ -249,9 +337,17 @@ impl Project for TuistProject {
             root: root.clone(),
             watchignore,
             num_clients: 1,
+            derived_data: derived_data_path(root, broadcast).await,
+            path: config.tuist_path,
+            fetch: config.tuist_fetch,
             ..Self::default()
         };
 
+        // Dependencies were fetched before, only fetch again once they change
+        if project.manifest_root().join("Tuist/Dependencies").exists() {
+            project.fetched_dependencies = project.dependencies_hash().await;
+        }
+
         let (xcodeproj_path, manifest_path) = match project.xcodeproj_paths()? {
             (Some(xcodeproj_path), Some(manifest_path)) => (xcodeproj_path, manifest_path),
             (Some(_), None) => {
@@ -279,23 +375,29 @@ impl Project for TuistProject {
             }
         };
 
-        project.manifest = XCodeProject::new(&manifest_path)?;
+        project.manifest = cache::xcodeproj(&manifest_path)?;
         project.manifest_path = manifest_path;
         project.manifest_files = project.manifest.build_file_names();
 
-        project.xcodeproj = XCodeProject::new(&xcodeproj_path)?;
+        project.xcodeproj = cache::xcodeproj(&xcodeproj_path)?;
         project.xcodeproj_path = xcodeproj_path;
-        project.targets = project
-            .xcodeproj
-            .targets_info()
-            .into_iter()
-            .map(|(k, info)| (k, info.into()))
-            .collect();
+        project.targets = xcodeproj_targets(&project.xcodeproj);
 
         tracing::info!("[{}] targets: {:?}", project.name(), project.targets());
 
         Ok(project)
     }
+
+    async fn reload(&mut self, broadcast: &Arc<Broadcast>) -> Result<bool> {
+        self.xcodeproj = cache::xcodeproj(&self.xcodeproj_path)?;
+        let name = self.name().to_string();
+        Ok(reload_targets(
+            &name,
+            &mut self.targets,
+            &self.xcodeproj,
+            broadcast,
+        ))
+    }
 }
 
 #[async_trait::async_trait]
@@ -303,3 +405,13 @@ impl ProjectBuild for TuistProject {}
 
 #[async_trait::async_trait]
 impl ProjectRun for TuistProject {}
+
+#[test]
+fn test_tuist_version_error() {
+    let lines = vec![
+        "Loading manifests".to_string(),
+        "The project's Tuist version 3.2.0 does not match the installed 3.10.0".to_string(),
+    ];
+    assert_eq!(TuistProject::version_error(&lines), Some(&lines[1]));
+    assert_eq!(TuistProject::version_error(&lines[..1]), None);
+}
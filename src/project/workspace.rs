@@ -0,0 +1,79 @@
+//! Functions to discover workspace schemes
+use crate::Result;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Schemes last listed by `xcodebuild -list`, keyed by workspace path
+static LISTED_SCHEMES: Lazy<Mutex<HashMap<PathBuf, Vec<String>>>> = Lazy::new(Default::default);
+
+/// Get shared schemes of a workspace and the xcodeproj files next to it
+pub fn shared_schemes(root: &Path, workspace: &Path) -> Vec<String> {
+    let mut dirs = vec![workspace.join("xcshareddata").join("xcschemes")];
+    if let Ok(walk) = wax::walk("*.xcodeproj", root) {
+        dirs.extend(
+            walk.flatten()
+                .map(|entry| entry.into_path().join("xcshareddata").join("xcschemes")),
+        );
+    }
+
+    let mut schemes = dirs
+        .iter()
+        .flat_map(std::fs::read_dir)
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "xcscheme"))
+        .flat_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect::<Vec<_>>();
+
+    schemes.sort();
+    schemes.dedup();
+    schemes
+}
+
+/// Get workspace schemes listed by `xcodebuild -list`, remembering them for [`listed_schemes`]
+pub async fn list_schemes(workspace: &Path) -> Result<Vec<String>> {
+    let output = tokio::process::Command::new(crate::xcodebuild::path())
+        .envs(crate::xcodebuild::envs())
+        .args(["-list", "-json", "-workspace"])
+        .arg(workspace)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(crate::Error::Unexpected(stderr.trim().to_string()));
+    }
+
+    let schemes = parse_list_output(&serde_json::from_slice(&output.stdout)?);
+    let mut listed = LISTED_SCHEMES.lock().unwrap();
+    listed.insert(workspace.to_path_buf(), schemes.clone());
+    Ok(schemes)
+}
+
+/// Get workspace schemes last listed by [`list_schemes`], if any
+pub fn listed_schemes(workspace: &Path) -> Option<Vec<String>> {
+    LISTED_SCHEMES.lock().unwrap().get(workspace).cloned()
+}
+
+/// Get schemes from `xcodebuild -list -json` output
+fn parse_list_output(value: &serde_json::Value) -> Vec<String> {
+    value
+        .pointer("/workspace/schemes")
+        .and_then(|schemes| schemes.as_array())
+        .into_iter()
+        .flatten()
+        .flat_map(|scheme| scheme.as_str().map(ToString::to_string))
+        .collect()
+}
+
+#[test]
+fn test_parse_list_output() {
+    let value = serde_json::json!({
+        "workspace": { "name": "App", "schemes": ["App", "Pods-App"] }
+    });
+    assert_eq!(parse_list_output(&value), vec!["App", "Pods-App"]);
+    assert!(parse_list_output(&serde_json::json!({ "project": {} })).is_empty());
+}
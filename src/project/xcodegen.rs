@@ -2,8 +2,7 @@ use super::*;
 use crate::util::fs::which;
 use crate::watcher::Event;
 use crate::Result;
-use futures::StreamExt;
-use process_stream::{Process, ProcessExt};
+use process_stream::Process;
 use serde::Serialize;
 use std::{collections::HashMap, path::PathBuf};
 use xcodeproj::XCodeProject;
@@ -16,7 +15,12 @@ pub struct XCodeGenProject {
     num_clients: i32,
     watchignore: Vec<String>,
     #[serde(skip)]
-    xcodeproj: xcodeproj::XCodeProject,
+    xcodeproj: Arc<XCodeProject>,
+    #[serde(skip)]
+    xcodeproj_path: PathBuf,
+    /// DerivedData path set in project config
+    #[serde(skip)]
+    derived_data: Option<PathBuf>,
 }
 
 impl ProjectData for XCodeGenProject {
@@ -43,6 +47,22 @@ impl ProjectData for XCodeGenProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn derived_data(&self) -> Option<&PathBuf> {
+        self.derived_data.as_ref()
+    }
+
+    fn xcodeproj_path(&self) -> Option<&PathBuf> {
+        Some(&self.xcodeproj_path)
+    }
+
+    fn xcodeproj(&self) -> Option<&Arc<XCodeProject>> {
+        Some(&self.xcodeproj)
+    }
+
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::XcodeGen
+    }
 }
 
 #[async_trait::async_trait]
@@ -57,9 +77,10 @@ impl ProjectCompile for XCodeGenProject {
         let task = Task::new(TaskKind::Compile, &name, broadcast.clone());
 
         arguments.push(format!("SYMROOT={cache_root}"));
-        task.debug(format!("xcodebuild {}", arguments.join(" ")));
+        task.debug(format!("xcodebuild {}", shell_words::join(&arguments)));
 
-        let xclogger = XCLogger::new(&root, &arguments)?;
+        let mut xclogger = XCLogger::new(&root, &arguments)?;
+        xcodebuild::set_envs(&mut xclogger);
         let compile_commands = xclogger.compile_commands.clone();
 
         let success = task
@@ -67,18 +88,17 @@ impl ProjectCompile for XCodeGenProject {
             .recv()
             .await
             .unwrap_or_default();
-        if success {
-            let compile_db = CC::new(compile_commands.lock().await.to_vec());
-            if compile_db.is_empty() {
-                broadcast.warn("No compile command was generated!");
-            }
-            let json = serde_json::to_vec_pretty(&compile_db)?;
-            tokio::fs::write(root.join(".compile"), &json).await?;
-            broadcast.reload_lsp_server();
-            Ok(())
-        } else {
-            Err(Error::Compile)
+
+        let compile_db = CC::new(compile_commands.lock().await.to_vec());
+        let units = task.clang_units().await;
+        if success && compile_db.is_empty() && units.is_empty() {
+            broadcast.warn("No compile command was generated!");
         }
+        let json = compile_database(&compile_db, units)?;
+        self.replace_compile_database(success, json, broadcast)
+            .await?;
+        broadcast.reload_lsp_server();
+        Ok(())
     }
 }
 
@@ -97,41 +117,36 @@ impl ProjectGenerate for XCodeGenProject {
 
     /// Generate xcodeproj
     async fn generate(&mut self, broadcast: &Arc<Broadcast>) -> Result<()> {
-        let mut process: Process = vec![which("xcodegen")?.as_str(), "generate", "-c"].into();
+        let xcodegen = which("xcodegen").map_err(|_| Error::generator_missing("xcodegen"))?;
+        let mut process: Process = vec![xcodegen.as_str(), "generate", "-c"].into();
         let name = self.root().name().unwrap();
         let task = Task::new(TaskKind::Generate, &name, broadcast.clone());
         process.current_dir(self.root());
 
-        let mut logs = process
-            .spawn_and_stream()
-            .context("Spawn xcodegen")?
-            .collect::<Vec<_>>()
-            .await;
-        let success = logs.pop().unwrap().is_success().unwrap_or_default();
+        let success = task
+            .consume_with(Box::new(process), Formatter::XcodeGen)?
+            .recv()
+            .await
+            .unwrap_or_default();
 
         if !success {
-            let logs = logs.into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
-
-            for log in logs {
-                tracing::error!("{log}");
-                task.error(log)
-            }
+            return Err(Error::Generate);
         }
 
         let xcodeproj_paths = self.get_xcodeproj_paths()?;
+        let xcodeproj_path = select_xcodeproj(self.root(), xcodeproj_paths, broadcast)
+            .await
+            .ok_or(Error::Generate)?;
 
-        if xcodeproj_paths.len() > 1 {
-            let using = xcodeproj_paths[0].display();
-            tracing::warn!("[{name}] Found more then on xcodeproj, using {using}",);
-        }
-
-        self.xcodeproj = XCodeProject::new(&xcodeproj_paths[0]).context("Reading Project")?;
-        for (key, info) in self.xcodeproj.targets_info().into_iter() {
+        cache::invalidate_xcodeproj(&xcodeproj_path);
+        self.xcodeproj = cache::xcodeproj(&xcodeproj_path)?;
+        self.xcodeproj_path = xcodeproj_path;
+        for (key, info) in xcodeproj_targets(&self.xcodeproj) {
             if self.targets.contains_key(&key) {
                 let existing_info = self.targets.get_mut(&key).unwrap();
-                *existing_info = info.into();
+                *existing_info = info;
             } else {
-                self.targets.insert(key, info.into());
+                self.targets.insert(key, info);
             }
         }
 
@@ -144,6 +159,7 @@ impl Project for XCodeGenProject {
     #[tracing::instrument(parent = None, name = "Project", skip_all, fields(name = root.name().unwrap(), kind = "xcodegen"))]
     async fn new(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<Self> {
         tracing::info!("Processing");
+        check_generator(root, "xcodegen", broadcast);
         let mut watchignore = generate_watchignore(root).await;
         watchignore.extend(["**/*.xcodeproj/**".into(), "**/*.xcworkspace/**".into()]);
 
@@ -151,30 +167,19 @@ impl Project for XCodeGenProject {
             root: root.clone(),
             watchignore,
             num_clients: 1,
+            derived_data: derived_data_path(root, broadcast).await,
             ..Self::default()
         };
 
         tracing::debug!("Searching for *.xcodeproj");
         let xcodeproj_paths = project.get_xcodeproj_paths()?;
 
-        if xcodeproj_paths.len() > 1 {
-            tracing::warn!(
-                "Found more then one *.xcodeproj, using {:?}",
-                xcodeproj_paths[0]
-            );
-        }
-
-        if !xcodeproj_paths.is_empty() {
-            let xcpath = &xcodeproj_paths[0];
+        if let Some(ref xcpath) = select_xcodeproj(root, xcodeproj_paths, broadcast).await {
             tracing::debug!("Using {}", xcpath.abbrv().unwrap().display());
-            project.xcodeproj = XCodeProject::new(xcpath).context("Reading XCodeProject")?;
+            project.xcodeproj = cache::xcodeproj(xcpath)?;
+            project.xcodeproj_path = xcpath.clone();
             tracing::debug!("Identifying targets");
-            project.targets = project
-                .xcodeproj
-                .targets_info()
-                .into_iter()
-                .map(|(k, info)| (k, info.into()))
-                .collect();
+            project.targets = xcodeproj_targets(&project.xcodeproj);
             tracing::debug!("Targets: {:?} ", project.targets);
         } else {
             tracing::info!("Generating xcodeproj ...");
@@ -189,6 +194,17 @@ impl Project for XCodeGenProject {
         tracing::info!("Created");
         Ok(project)
     }
+
+    async fn reload(&mut self, broadcast: &Arc<Broadcast>) -> Result<bool> {
+        self.xcodeproj = cache::xcodeproj(&self.xcodeproj_path)?;
+        let name = self.name().to_string();
+        Ok(reload_targets(
+            &name,
+            &mut self.targets,
+            &self.xcodeproj,
+            broadcast,
+        ))
+    }
 }
 
 #[async_trait::async_trait]
@@ -1,4 +1,4 @@
-use crate::{Error, Result, Runner, Task};
+use crate::{Error, LaunchOptions, Result, Runner, Task};
 use process_stream::Process;
 use std::path::{Path, PathBuf};
 use xclog::XCBuildSettings;
@@ -21,11 +21,15 @@ impl BinRunner {
 
 #[async_trait::async_trait]
 impl Runner for BinRunner {
-    async fn run<'a>(&self, _task: &Task) -> Result<Process> {
+    async fn run<'a>(&self, _task: &Task, options: &LaunchOptions) -> Result<Process> {
         if !self.path.exists() {
             return Err(Error::Run(format!("{:?} doesn't exist!", self.path)));
         }
 
-        Ok(Process::new(&self.path))
+        let mut process = Process::new(&self.path);
+        process.args(&options.args);
+        process.envs(&options.env);
+
+        Ok(process)
     }
 }
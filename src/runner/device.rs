@@ -1,12 +1,40 @@
 use derive_deref_rs::Deref;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, hash::Hash};
 use tap::Pipe;
+use tokio::process::Command;
 use typescript_type_def::TypeDef;
 use xcodeproj::pbxproj::PBXTargetPlatform;
 
-use crate::DeviceLookup;
+use crate::{DeviceLookup, Error, Platform, Result};
+
+/// Get platform of a simulator runtime identifier, e.g. `iOS` for
+/// `com.apple.CoreSimulator.SimRuntime.iOS-17-0`
+pub fn runtime_platform(runtime: &str) -> Option<&'static str> {
+    Platform::of_runtime(runtime).map(|platform| platform.name)
+}
+
+/// Get target platform from its name, e.g. `iOS`.
+///
+/// visionOS has no [`PBXTargetPlatform`], so it's `Unknown`.
+fn target_platform(platform: &str) -> PBXTargetPlatform {
+    use PBXTargetPlatform::*;
+    [IOS, WatchOS, TvOS, MacOS]
+        .into_iter()
+        .find(|target| target.to_string() == platform)
+        .unwrap_or(Unknown)
+}
+
+/// Get sdk arguments to build for simulators of a given runtime, e.g. `-sdk iphonesimulator`
+fn simulator_sdk_args(runtime: &str) -> Vec<String> {
+    Platform::of_runtime(runtime)
+        .and_then(|platform| platform.simulator_sdk)
+        .map_or_else(Vec::new, |sdk| vec!["-sdk".into(), sdk.into()])
+}
 
 #[derive(Clone, Debug, Serialize, derive_deref_rs::Deref)]
 pub struct Device {
@@ -19,6 +47,21 @@ pub struct Device {
 #[derive(Debug, Serialize, Deref)]
 pub struct Devices(HashMap<String, Device>);
 
+/// Connected physical device
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PhysicalDevice {
+    pub udid: String,
+    pub name: String,
+    pub platform: PBXTargetPlatform,
+}
+
+/// Device to build and run a target on
+#[derive(Clone, Debug, PartialEq)]
+pub enum Destination {
+    Simulator(Device),
+    Physical(PhysicalDevice),
+}
+
 impl std::fmt::Display for Device {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.name)
@@ -41,8 +84,8 @@ impl Hash for Device {
 
 impl From<simctl::Device> for Device {
     fn from(inner: simctl::Device) -> Self {
-        let ref id = inner.runtime_identifier;
-        let platform = PBXTargetPlatform::from_identifer(id);
+        let platform = runtime_platform(&inner.runtime_identifier)
+            .map_or(PBXTargetPlatform::Unknown, target_platform);
         Self { inner, platform }
     }
 }
@@ -51,43 +94,312 @@ impl Device {
     /// Get special build arguments to run on current device.
     // -sdk driverkit -sdk iphoneos -sdk macosx -sdk appletvos -sdk watchos
     pub fn special_build_args(&self) -> Vec<String> {
-        match self.platform {
-            PBXTargetPlatform::IOS => vec!["-sdk".into(), "iphonesimulator".into()],
-            PBXTargetPlatform::WatchOS => vec!["-sdk".into(), "watchsimulator".into()],
-            PBXTargetPlatform::TvOS => vec!["-sdk".into(), "appletvsimulator".into()],
-            PBXTargetPlatform::MacOS => vec!["-sdk".into(), "macosx".into()],
-            PBXTargetPlatform::Unknown => vec![],
+        simulator_sdk_args(&self.runtime_identifier)
+    }
+
+    /// Max time to wait for the device to boot or to finish shutting down
+    const BOOT_TIMEOUT: Duration = Duration::from_secs(120);
+
+    /// Get current device state as reported by simctl (e.g. `Booted`, `Shutdown`)
+    pub async fn state(&self) -> Result<String> {
+        let output = Command::new("xcrun")
+            .envs(crate::xcodebuild::envs())
+            .args(["simctl", "list", "devices", "--json"])
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Run(stderr.trim().to_string()));
+        }
+
+        serde_json::from_slice::<serde_json::Value>(&output.stdout)?
+            .get("devices")
+            .and_then(|v| v.as_object())
+            .into_iter()
+            .flat_map(|runtimes| runtimes.values())
+            .flat_map(|devices| devices.as_array())
+            .flatten()
+            .find(|device| device.get("udid").and_then(|v| v.as_str()) == Some(self.udid.as_str()))
+            .and_then(|device| device.get("state")?.as_str().map(ToString::to_string))
+            .ok_or_else(|| Error::Run(format!("No device found with udid `{}`", self.udid)))
+    }
+
+    /// Whether the device is booted
+    pub async fn is_booted(&self) -> bool {
+        matches!(self.state().await.as_deref(), Ok("Booted"))
+    }
+
+    /// Boot the device if it isn't booted and wait for the boot to complete
+    pub async fn boot(&self) -> Result<()> {
+        let started = Instant::now();
+
+        loop {
+            match self.state().await?.as_str() {
+                "Booted" => return Ok(()),
+                "Shutting Down" if started.elapsed() < Self::BOOT_TIMEOUT => {
+                    tokio::time::sleep(Duration::from_millis(500)).await
+                }
+                "Shutting Down" => {
+                    let msg = format!("[{}] Timed out waiting for shutdown", self.name);
+                    return Err(Error::Run(msg));
+                }
+                _ => break,
+            }
+        }
+
+        let bootstatus = Command::new("xcrun")
+            .envs(crate::xcodebuild::envs())
+            .args(["simctl", "bootstatus", &self.udid, "-b"])
+            .output();
+
+        let output = tokio::time::timeout(Self::BOOT_TIMEOUT, bootstatus)
+            .await
+            .map_err(|_| Error::Run(format!("[{}] Timed out waiting for boot", self.name)))??;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(Error::Run(format!("[{}] {}", self.name, stderr.trim())))
         }
     }
 }
 
+impl PhysicalDevice {
+    /// List connected physical devices using devicectl.
+    ///
+    /// Returns an empty list if devicectl is unavailable (i.e. Xcode < 15).
+    pub async fn list() -> Vec<PhysicalDevice> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        // Unique per listing, so concurrent listings don't read each other's output
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let name = format!("xbase-devicectl-{}-{count}.json", std::process::id());
+        let output_path = std::env::temp_dir().join(name);
+        let output = Command::new("xcrun")
+            .envs(crate::xcodebuild::envs())
+            .args(["devicectl", "list", "devices", "--json-output"])
+            .arg(&output_path)
+            .output()
+            .await;
+
+        match output {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                tracing::debug!("Failed to list physical devices: {}", stderr.trim());
+                return vec![];
+            }
+            Err(err) => {
+                tracing::debug!("Failed to list physical devices: {err}");
+                return vec![];
+            }
+        }
+
+        let content = tokio::fs::read(&output_path).await;
+        tokio::fs::remove_file(&output_path).await.ok();
+        content
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .map(|value| Self::parse_list(&value))
+            .unwrap_or_default()
+    }
+
+    /// Parse paired devices from `devicectl list devices` json output
+    fn parse_list(value: &serde_json::Value) -> Vec<PhysicalDevice> {
+        let devices = match value.pointer("/result/devices").and_then(|v| v.as_array()) {
+            Some(devices) => devices,
+            None => return vec![],
+        };
+
+        devices
+            .iter()
+            .filter(|device| {
+                let pairing_state = device.pointer("/connectionProperties/pairingState");
+                pairing_state.and_then(|v| v.as_str()) == Some("paired")
+            })
+            .flat_map(|device| {
+                let get = |pointer: &str| device.pointer(pointer)?.as_str().map(String::from);
+                Some(PhysicalDevice {
+                    udid: get("/hardwareProperties/udid")?,
+                    name: get("/deviceProperties/name")?,
+                    platform: target_platform(&get("/hardwareProperties/platform")?),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Destination {
+    /// Device name
+    pub fn name(&self) -> &str {
+        match self {
+            Destination::Simulator(device) => &device.name,
+            Destination::Physical(device) => &device.name,
+        }
+    }
+
+    /// Device udid
+    pub fn udid(&self) -> &str {
+        match self {
+            Destination::Simulator(device) => &device.udid,
+            Destination::Physical(device) => &device.udid,
+        }
+    }
+
+    /// Whether the destination is a physical device
+    pub fn is_physical(&self) -> bool {
+        matches!(self, Destination::Physical(_))
+    }
+
+    /// Device platform, e.g. `iOS`
+    pub fn platform(&self) -> String {
+        match self {
+            Destination::Simulator(device) => runtime_platform(&device.runtime_identifier)
+                .map_or_else(|| device.platform.to_string(), String::from),
+            Destination::Physical(device) => device.platform.to_string(),
+        }
+    }
+
+    /// Whether the destination can run targets of a given platform.
+    ///
+    /// Platforms without simulators (e.g. macOS) or unknown ones aren't checked.
+    pub fn supports(&self, platform: &str) -> bool {
+        let has_devices = Platform::simulated().any(|p| p.name == platform);
+        !has_devices || self.platform() == platform
+    }
+
+    /// Get special build arguments to run on destination
+    pub fn special_build_args(&self) -> Vec<String> {
+        match self {
+            Destination::Simulator(device) => device.special_build_args(),
+            Destination::Physical(device) => {
+                vec!["-destination".into(), format!("id={}", device.udid)]
+            }
+        }
+    }
+}
+
+/// Run destination as reported by simctl or devicectl
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub struct DeviceInfo {
+    pub udid: String,
+    pub name: String,
+    /// Simulator runtime identifier, empty for physical devices
+    pub runtime: String,
+    /// Device state, e.g. `Booted`, `Shutdown` or `Connected`
+    pub state: String,
+    pub platform: String,
+    pub physical: bool,
+}
+
+impl DeviceInfo {
+    /// Duration to reuse listed devices for, as listing devices is slow
+    const CACHE_DURATION: Duration = Duration::from_secs(10);
+
+    /// List available simulators and connected physical devices
+    pub async fn list() -> Result<Vec<DeviceInfo>> {
+        static CACHE: Lazy<Mutex<Option<(Instant, Vec<DeviceInfo>)>>> = Lazy::new(Default::default);
+
+        if let Some((listed_at, devices)) = CACHE.lock().unwrap().as_ref() {
+            if listed_at.elapsed() < Self::CACHE_DURATION {
+                return Ok(devices.clone());
+            }
+        }
+
+        let output = Command::new("xcrun")
+            .envs(crate::xcodebuild::envs())
+            .args(["simctl", "list", "devices", "available", "--json"])
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Run(stderr.trim().to_string()));
+        }
+
+        let mut devices = Self::parse_simctl(&serde_json::from_slice(&output.stdout)?);
+        devices.extend(
+            PhysicalDevice::list()
+                .await
+                .into_iter()
+                .map(|device| DeviceInfo {
+                    udid: device.udid,
+                    name: device.name,
+                    runtime: String::default(),
+                    state: "Connected".into(),
+                    platform: device.platform.to_string(),
+                    physical: true,
+                }),
+        );
+
+        *CACHE.lock().unwrap() = Some((Instant::now(), devices.clone()));
+
+        Ok(devices)
+    }
+
+    /// Parse simulators from `simctl list devices --json` output
+    fn parse_simctl(value: &serde_json::Value) -> Vec<DeviceInfo> {
+        let runtimes = match value.get("devices").and_then(|v| v.as_object()) {
+            Some(runtimes) => runtimes,
+            None => return vec![],
+        };
+
+        runtimes
+            .iter()
+            .flat_map(|(runtime, devices)| {
+                let platform = runtime_platform(runtime)
+                    .map_or_else(|| PBXTargetPlatform::Unknown.to_string(), String::from);
+                devices
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .flat_map(move |device| {
+                        let get = |key: &str| device.get(key)?.as_str().map(String::from);
+                        Some(DeviceInfo {
+                            udid: get("udid")?,
+                            name: get("name")?,
+                            runtime: runtime.clone(),
+                            state: get("state").unwrap_or_default(),
+                            platform: platform.clone(),
+                            physical: false,
+                        })
+                    })
+            })
+            .collect()
+    }
+}
+
 static DEVICES: Lazy<Devices> = Lazy::new(Default::default);
 
 /// Represntaiton of Project runners index by Platfrom
 #[derive(Debug, Serialize, Deserialize, TypeDef)]
 pub struct Runners(HashMap<String, Vec<DeviceLookup>>);
 
-impl Default for Runners {
-    fn default() -> Self {
+impl Runners {
+    /// List simulators and connected physical devices by platform
+    pub async fn list() -> Self {
         let devices = &*DEVICES;
-        vec![
-            PBXTargetPlatform::IOS,
-            PBXTargetPlatform::WatchOS,
-            PBXTargetPlatform::TvOS,
-        ]
-        .into_iter()
-        .map(|p| {
-            (
-                p.to_string(),
-                devices
-                    .iter()
-                    .filter(|(_, d)| d.platform == p)
-                    .map(|(id, d)| DeviceLookup::new(d.name.clone(), id.clone()))
-                    .collect::<Vec<_>>(),
-            )
-        })
-        .collect::<HashMap<String, _>>()
-        .pipe(Self)
+        let physical_devices = PhysicalDevice::list().await;
+        Platform::simulated()
+            .map(|&Platform { name: p, .. }| {
+                (
+                    p.to_string(),
+                    devices
+                        .iter()
+                        .filter(|(_, d)| runtime_platform(&d.runtime_identifier) == Some(p))
+                        .map(|(id, d)| DeviceLookup::new(d.name.clone(), id.clone()))
+                        .chain(
+                            physical_devices
+                                .iter()
+                                .filter(|d| d.platform.to_string() == p)
+                                .map(|d| DeviceLookup::physical(d.name.clone(), d.udid.clone())),
+                        )
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<HashMap<String, _>>()
+            .pipe(Self)
     }
 }
 
@@ -108,8 +420,107 @@ impl Default for Devices {
 }
 
 impl Devices {
-    /// Get Device from Device lookup
-    pub fn from_lookup(lookup: Option<DeviceLookup>) -> Option<Device> {
-        lookup.and_then(|d| DEVICES.get(&d.id)).cloned()
+    /// Get Destination from Device lookup, matching by id first and then by name.
+    pub async fn from_lookup(lookup: Option<DeviceLookup>) -> Result<Option<Destination>> {
+        let lookup = match lookup {
+            Some(lookup) => lookup,
+            None => return Ok(None),
+        };
+
+        if !lookup.physical {
+            let simulator = DEVICES
+                .get(&lookup.id)
+                .or_else(|| DEVICES.values().find(|d| d.name == lookup.name));
+            if let Some(simulator) = simulator {
+                return Ok(Some(Destination::Simulator(simulator.clone())));
+            }
+        }
+
+        let physical_devices = PhysicalDevice::list().await;
+        physical_devices
+            .iter()
+            .find(|d| d.udid == lookup.id)
+            .or_else(|| physical_devices.iter().find(|d| d.name == lookup.name))
+            .cloned()
+            .map(|device| Some(Destination::Physical(device)))
+            .ok_or_else(|| {
+                let DeviceLookup { id, name, .. } = lookup;
+                Error::Run(format!("No device found with id `{id}` or name `{name}`"))
+            })
     }
 }
+
+#[test]
+fn test_parse_simctl_devices() {
+    let runtime = "com.apple.CoreSimulator.SimRuntime.iOS-16-0";
+    let value = serde_json::json!({
+        "devices": {
+            runtime: [
+                { "udid": "A1", "name": "iPhone 14", "state": "Booted", "isAvailable": true }
+            ]
+        }
+    });
+
+    assert_eq!(
+        DeviceInfo::parse_simctl(&value),
+        vec![DeviceInfo {
+            udid: "A1".into(),
+            name: "iPhone 14".into(),
+            runtime: runtime.into(),
+            state: "Booted".into(),
+            platform: PBXTargetPlatform::IOS.to_string(),
+            physical: false,
+        }]
+    );
+}
+
+#[test]
+fn test_runtime_platform() {
+    let platform =
+        |runtime| runtime_platform(&format!("com.apple.CoreSimulator.SimRuntime.{runtime}"));
+    assert_eq!(platform("iOS-17-0"), Some("iOS"));
+    assert_eq!(platform("watchOS-10-0"), Some("watchOS"));
+    assert_eq!(platform("tvOS-17-0"), Some("tvOS"));
+    assert_eq!(platform("xrOS-1-0"), Some("visionOS"));
+    assert_eq!(platform("iOSX-1-0"), None);
+}
+
+#[test]
+fn test_simulator_sdk_args() {
+    let args =
+        |runtime| simulator_sdk_args(&format!("com.apple.CoreSimulator.SimRuntime.{runtime}"));
+    assert_eq!(args("iOS-17-0"), vec!["-sdk", "iphonesimulator"]);
+    assert_eq!(args("xrOS-1-0"), vec!["-sdk", "xrsimulator"]);
+    assert!(args("iOSX-1-0").is_empty());
+    assert_eq!(target_platform("visionOS"), PBXTargetPlatform::Unknown);
+    assert_eq!(target_platform("tvOS"), PBXTargetPlatform::TvOS);
+}
+
+#[test]
+fn test_parse_physical_devices() {
+    let value = serde_json::json!({
+        "result": {
+            "devices": [
+                {
+                    "connectionProperties": { "pairingState": "paired" },
+                    "deviceProperties": { "name": "iPhone" },
+                    "hardwareProperties": { "platform": "iOS", "udid": "00008110-001" }
+                },
+                {
+                    "connectionProperties": { "pairingState": "unpaired" },
+                    "deviceProperties": { "name": "iPad" },
+                    "hardwareProperties": { "platform": "iOS", "udid": "00008110-002" }
+                }
+            ]
+        }
+    });
+
+    assert_eq!(
+        PhysicalDevice::parse_list(&value),
+        vec![PhysicalDevice {
+            udid: "00008110-001".into(),
+            name: "iPhone".into(),
+            platform: PBXTargetPlatform::IOS,
+        }]
+    );
+}
@@ -0,0 +1,93 @@
+use crate::{Error, LaunchOptions, Result, Runner, Task};
+use process_stream::Process;
+use std::path::{Path, PathBuf};
+use xclog::XCBuildSettings;
+
+/// macOS app runner, executing the built app bundle binary directly
+pub struct MacRunner {
+    pub app_id: String,
+    pub bundle: PathBuf,
+}
+
+impl MacRunner {
+    /// Create runner from build settings, if the build product is an app bundle
+    pub fn from_build_info(info: &XCBuildSettings) -> Option<Self> {
+        let bundle = app_bundle(info.built_products_dir.as_ref(), &info.full_product_name)?;
+        Some(Self {
+            app_id: info.product_bundle_identifier.clone(),
+            bundle,
+        })
+    }
+
+    /// Get app bundle executable, named after the bundle unless set otherwise with
+    /// `EXECUTABLE_NAME`, in which case it's the only file in `Contents/MacOS`
+    fn executable(&self) -> Option<PathBuf> {
+        let dir = self.bundle.join("Contents/MacOS");
+        let named = dir.join(self.bundle.file_stem()?);
+        if named.is_file() {
+            return Some(named);
+        }
+
+        std::fs::read_dir(dir)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.is_file())
+    }
+}
+
+#[async_trait::async_trait]
+impl Runner for MacRunner {
+    async fn run<'a>(&self, task: &Task, options: &LaunchOptions) -> Result<Process> {
+        let executable = self
+            .executable()
+            .ok_or_else(|| Error::Run(format!("No executable found in {:?}", self.bundle)))?;
+
+        task.info(format!("[macOS] Launching {}", self.app_id));
+
+        // Executing the binary rather than `open`, to stream its output and stop it on abort
+        let mut process = Process::new(&executable);
+        process.args(&options.args);
+        process.envs(&options.env);
+
+        Ok(process)
+    }
+
+    fn log_stream(&self) -> Option<Process> {
+        // Most apps log without a subsystem, so their process is matched instead
+        let executable = self.executable()?;
+        let name = executable.file_name()?.to_string_lossy();
+        let predicate = format!("process == \"{name}\"");
+        let mut process = Process::new("/usr/bin/log");
+        process.args(&[
+            "stream",
+            "--style",
+            "compact",
+            "--level",
+            "debug",
+            "--predicate",
+            &predicate,
+        ]);
+        Some(process)
+    }
+}
+
+/// Get app bundle built as `FULL_PRODUCT_NAME` in `BUILT_PRODUCTS_DIR`, if the product is one
+fn app_bundle(built_products_dir: &Path, full_product_name: &str) -> Option<PathBuf> {
+    let bundle = built_products_dir.join(full_product_name);
+    if bundle.extension().map_or(false, |ext| ext == "app") {
+        Some(bundle)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_app_bundle() {
+    let dir = Path::new("/tmp/Build/Products/Debug");
+    assert_eq!(
+        app_bundle(dir, "Demo.app"),
+        Some(PathBuf::from("/tmp/Build/Products/Debug/Demo.app"))
+    );
+    assert_eq!(app_bundle(dir, "tool"), None);
+}
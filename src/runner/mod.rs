@@ -1,79 +1,313 @@
 mod bin;
 mod device;
+mod mac;
+mod physical;
+mod session;
 mod simulator;
+mod swift;
 
 use crate::*;
 use async_trait::async_trait;
 use process_stream::{Process, ProcessExt, StreamExt};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::Weak;
+use std::time::Duration;
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::Notify;
 use tokio::task::JoinHandle;
 
-pub use {bin::*, device::*, simulator::*};
+pub use {bin::*, device::*, mac::*, physical::*, session::*, simulator::*, swift::*};
 
 /// Run Service
+#[derive(Clone)]
 pub struct RunService {
     pub key: String,
+    /// Id of the client that requested the run, if known
+    pub client: Option<u32>,
     pub root: PathBuf,
     pub handler: Arc<Mutex<Option<RunHandler>>>,
     pub settings: BuildSettings,
-    pub device: Option<Device>,
+    pub device: Option<Destination>,
+    pub options: LaunchOptions,
+    /// Number of builds started to launch the app with, only the latest one is launched
+    builds: Arc<AtomicUsize>,
+}
+
+/// Environment variables and arguments to launch the built app with
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    pub env: HashMap<String, String>,
+    pub args: Vec<String>,
+    /// Bring Simulator.app to front once the app is launched
+    pub focus_simulator: bool,
+    /// Times to relaunch the app if it crashes, capped to [`Relaunch::MAX`]
+    pub relaunch_on_crash: u32,
+    /// Build before launching even if the app is up to date
+    pub force_build: bool,
+}
+
+/// Relaunch an app that crashed, i.e. exited with non-zero code without being stopped
+pub struct Relaunch {
+    runner: Arc<dyn Runner + Send + Sync>,
+    options: LaunchOptions,
+    /// Relaunches left
+    remaining: u32,
+    /// Relaunches so far
+    count: u32,
+}
+
+impl Relaunch {
+    /// Max relaunches after successive crashes, to avoid crash loops
+    pub const MAX: u32 = 5;
+    /// Time to wait before relaunching
+    const DELAY: Duration = Duration::from_secs(1);
+
+    pub fn new(runner: Arc<dyn Runner + Send + Sync>, options: LaunchOptions) -> Option<Self> {
+        let remaining = options.relaunch_on_crash.min(Self::MAX);
+        (remaining > 0).then(|| Self {
+            runner,
+            options,
+            remaining,
+            count: 0,
+        })
+    }
+
+    /// Launch app again, returning None if no relaunches are left or launching failed
+    async fn next(
+        &mut self,
+        target: &str,
+        code: &str,
+        broadcast: &Arc<Broadcast>,
+    ) -> Option<Process> {
+        if self.remaining == 0 {
+            broadcast.log_error(format!(
+                "[{target}] Crashed too many times, not relaunching"
+            ));
+            return None;
+        }
+        self.remaining -= 1;
+        self.count += 1;
+
+        let count = self.count;
+        let total = count + self.remaining;
+        broadcast.warn(format!(
+            "[{target}] Crashed with exit code {code}, relaunching ({count}/{total})"
+        ));
+        tokio::time::sleep(Self::DELAY).await;
+
+        let task = Task::new(TaskKind::Run, target, broadcast.clone());
+        match self.runner.run(&task, &self.options).await {
+            Ok(process) => Some(process),
+            Err(err) => {
+                broadcast.error(format!("[{target}] Failed to relaunch: {err}"));
+                None
+            }
+        }
+    }
 }
 
 impl RunService {
     pub fn new(
-        device: Option<Device>,
+        device: Option<Destination>,
         root: PathBuf,
         settings: BuildSettings,
+        options: LaunchOptions,
         key: String,
+        client: Option<u32>,
     ) -> Self {
         Self {
             key,
+            client,
             root,
             handler: Arc::new(Mutex::new(None)),
             settings,
             device,
+            options,
+            builds: Default::default(),
         }
     }
-}
 
-impl std::fmt::Display for RunService {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.key)
+    /// Get key of the run session
+    pub fn session_key(&self) -> RunSessionKey {
+        RunSessionKey {
+            target: self.settings.target.clone(),
+            device: self.device.as_ref().map(|d| d.udid().to_string()),
+        }
     }
-}
 
-#[async_trait::async_trait]
-impl Watchable for RunService {
-    async fn trigger(
+    /// Whether the service has a running process
+    pub async fn is_running(&self) -> bool {
+        match self.handler.lock().await.as_ref() {
+            Some(handler) => !handler.inner().is_finished(),
+            None => false,
+        }
+    }
+
+    /// Stop running process, if any
+    pub async fn stop(&self) {
+        if let Some(handler) = self.handler.lock().await.take() {
+            handler.abort();
+        }
+    }
+
+    /// Whether the app was built successfully and no file changed since, any doubt means it's not
+    async fn is_up_to_date(
         &self,
-        project: &mut ProjectImpl,
-        _event: &Event,
+        project: &ProjectImpl,
+        config: &ProjectConfig,
+        settings: &BuildSettings,
         broadcast: &Arc<Broadcast>,
-    ) -> Result<()> {
-        let Self { settings, .. } = self;
+    ) -> bool {
+        if self.options.force_build || config.always_build_before_run {
+            return false;
+        }
+        let since = match project.last_build(settings, self.device.as_ref(), broadcast) {
+            Some(since) => since,
+            None => return false,
+        };
 
-        let mut handler = self.handler.clone().lock_owned().await;
+        // Changes not seen yet by the watcher, e.g. saved right before running
+        let root = project.root();
+        let roots = std::iter::once(root.clone())
+            .chain(config.watch_roots(root))
+            .collect::<Vec<_>>();
+        let ignore = project.watchignore().clone();
+        tokio::task::spawn_blocking(move || !fs::modified_since(&roots, since, &ignore))
+            .await
+            .unwrap_or_default()
+    }
 
-        handler.take().map(|v| {
-            v.process().abort();
-            v.inner().abort();
-        });
+    /// Build the app if it's not up to date, returning the runner to launch it with and the
+    /// build result receiver
+    async fn build(
+        &self,
+        project: &mut ProjectImpl,
+        config: &ProjectConfig,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<(Arc<dyn Runner + Send + Sync>, Receiver<Result<()>>)> {
+        let settings = config.settings_for(&self.settings);
 
         let device = self.device.as_ref();
         let target = &settings.target;
-        let (runner, _args, mut recv) = project.get_runner(&settings, device, broadcast)?;
+        let build = !self
+            .is_up_to_date(project, config, &settings, broadcast)
+            .await;
+        let (runner, _args, recv) = project
+            .get_runner(&settings, device, build, broadcast)
+            .await?;
 
-        if !recv.recv().await.unwrap_or_default() {
-            return Err(crate::Error::Run(format!("{target} build failed")));
+        if !build {
+            broadcast.info(format!("[{target}] Up to date, launching"));
         }
 
+        Ok((runner.into(), recv))
+    }
+
+    /// Replace the running app with the one built by a build, once the build succeeds.
+    ///
+    /// Builds superseded by a newer one meanwhile are ignored, so that only the latest build is
+    /// launched. The handler is only locked once the build finished.
+    pub fn launch_when_built(
+        &self,
+        runner: Arc<dyn Runner + Send + Sync>,
+        mut built: Receiver<Result<()>>,
+        stop_on_failure: bool,
+        broadcast: &Arc<Broadcast>,
+    ) {
+        let build = self.builds.fetch_add(1, Ordering::SeqCst) + 1;
+        let (this, broadcast) = (self.clone(), broadcast.clone());
+
+        tokio::spawn(async move {
+            let target = &this.settings.target;
+            let built = built
+                .recv()
+                .await
+                .unwrap_or_else(|| Err(crate::Error::Run(format!("{target} build failed"))));
+            if this.builds.load(Ordering::SeqCst) != build {
+                tracing::debug!("[{target}] Superseded by a newer build, not launching");
+                return;
+            }
+
+            let mut handler = this.handler.lock().await;
+            let result = match built {
+                Ok(_) => {
+                    if let Some(handler) = handler.take() {
+                        handler.abort();
+                    }
+                    this.launch(runner, &mut handler, &broadcast).await
+                }
+                Err(err) => {
+                    if keep_on_build_failure(&mut handler, stop_on_failure) {
+                        broadcast.warn(format!("[{target}] Build failed, keeping previous run"));
+                    }
+                    Err(err)
+                }
+            };
+
+            if let Err(err) = result.as_ref() {
+                broadcast.error(format!("[{target}] {err}"));
+            }
+            broadcast.task_finished(TaskKind::Run, target, result.is_ok());
+        });
+    }
+
+    /// Launch the built app, storing its handler
+    async fn launch(
+        &self,
+        runner: Arc<dyn Runner + Send + Sync>,
+        handler: &mut Option<RunHandler>,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<()> {
+        let target = &self.settings.target;
+        let key = self.session_key();
         let task = Task::new(TaskKind::Run, target, broadcast.clone());
 
-        let runner = runner.run(&task).await?;
+        let process = runner.run(&task, &self.options).await?;
+        let logs = runner.log_stream();
+        let relaunch = Relaunch::new(runner.clone(), self.options.clone());
         let broadcast = Arc::downgrade(broadcast);
 
-        *handler = Some(RunHandler::new(target, runner, broadcast)?);
+        *handler = Some(RunHandler::new(&key, process, logs, relaunch, broadcast)?);
+
+        if self.options.focus_simulator {
+            runner.focus(&task).await;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for RunService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key)
+    }
+}
+
+#[async_trait::async_trait]
+impl Watchable for RunService {
+    async fn trigger(
+        &self,
+        project: &mut ProjectImpl,
+        _event: &Event,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<()> {
+        let target = &self.settings.target;
+        let config = ProjectConfig::load(project.root()).await;
+        broadcast.task_started(TaskKind::Run, target);
+
+        // The running app is only replaced once the new one is built
+        let (runner, built) = match self.build(project, &config, broadcast).await {
+            Ok(build) => build,
+            Err(err) => {
+                broadcast.task_finished(TaskKind::Run, target, false);
+                return Err(err);
+            }
+        };
+        let stop_on_failure = config.stop_run_on_build_failure;
+        self.launch_when_built(runner, built, stop_on_failure, broadcast);
 
         Ok(())
     }
@@ -90,27 +324,79 @@ impl Watchable for RunService {
 
     /// Drop watchable for watching a given file system
     async fn discard(&self) {
-        self.handler.clone().lock_owned().await.take().map(|v| {
-            v.process().abort();
-            v.inner().abort();
-        });
+        self.stop().await;
+    }
+
+    fn target(&self) -> &str {
+        &self.settings.target
+    }
+
+    fn kind(&self) -> TaskKind {
+        TaskKind::Run
+    }
+
+    fn relocate(&mut self, root: &Path) {
+        let old_root = self.root.display().to_string();
+        if let Some(rest) = self.key.strip_prefix(&old_root) {
+            self.key = format!("{}{rest}", root.display());
+        }
+        self.root = root.to_path_buf();
     }
 }
 
+/// Stop the running app after its rebuild failed, unless configured to keep it running.
+///
+/// Returns whether the running app was kept.
+fn keep_on_build_failure(handler: &mut Option<RunHandler>, stop_on_failure: bool) -> bool {
+    if handler.is_some() && !stop_on_failure {
+        return true;
+    }
+    if let Some(handler) = handler.take() {
+        handler.abort();
+    }
+    false
+}
+
 /// Run Service Task Handler
 pub struct RunHandler {
     process: Process,
     inner: JoinHandle<Result<()>>,
+    /// Running app log stream
+    logs: Option<Process>,
+    /// Whether the process was stopped, to tell crashes apart from stops
+    stopped: Arc<AtomicBool>,
+    /// Aborter of the process relaunched after a crash, if any
+    relaunched: Arc<std::sync::Mutex<Option<Arc<Notify>>>>,
 }
 
 impl RunHandler {
     // Change the status of the process to running
-    pub fn new(target: &String, mut process: Process, broadcast: Weak<Broadcast>) -> Result<Self> {
-        let target = target.clone();
+    pub fn new(
+        key: &RunSessionKey,
+        mut process: Process,
+        logs: Option<Process>,
+        mut relaunch: Option<Relaunch>,
+        broadcast: Weak<Broadcast>,
+    ) -> Result<Self> {
+        let key = key.clone();
+        let target = key.target.clone();
+        let logs = logs
+            .map(|logs| Self::stream_logs(logs, broadcast.clone()))
+            .transpose()?;
+        let logs_abort = logs.as_ref().and_then(|logs| logs.aborter());
         let mut stream = process.spawn_and_stream()?;
         let abort = process.aborter().unwrap();
+        let stopped: Arc<AtomicBool> = Default::default();
+        let relaunched: Arc<std::sync::Mutex<Option<Arc<Notify>>>> = Default::default();
+        let (is_stopped, current) = (stopped.clone(), relaunched.clone());
+
+        if let Some(broadcast) = broadcast.upgrade() {
+            broadcast.send(None, Message::RunSessionStarted(key.clone()));
+        }
 
         let inner: _ = tokio::spawn(async move {
+            // Kept alive while its output is streamed
+            let mut _relaunched_process: Option<Process> = None;
             // TODO: find a better way to close this!
             //
             // Right now it just wait till the user try print something
@@ -120,6 +406,12 @@ impl RunHandler {
                     None => {
                         tracing::warn!("No client instance listening, closing runner ..");
                         abort.notify_waiters();
+                        if let Some(abort) = current.lock().unwrap().take() {
+                            abort.notify_waiters();
+                        }
+                        if let Some(abort) = logs_abort.as_ref() {
+                            abort.notify_one();
+                        }
                         break;
                     }
                 };
@@ -137,12 +429,35 @@ impl RunHandler {
                     // TODO: this should be skipped when user re-run the app
                     Exit(code) => {
                         let success = &code == "0";
+                        let crashed = !success && !is_stopped.load(Ordering::SeqCst);
+                        let relaunched = match relaunch.as_mut() {
+                            Some(relaunch) if crashed => {
+                                relaunch.next(&target, &code, broadcast).await
+                            }
+                            _ => None,
+                        };
+                        if let Some(mut process) = relaunched {
+                            match process.spawn_and_stream() {
+                                Ok(relaunched) => {
+                                    *current.lock().unwrap() = process.aborter();
+                                    _relaunched_process = Some(process);
+                                    stream = relaunched;
+                                    continue;
+                                }
+                                Err(err) => broadcast.error(format!("[{target}] {err}")),
+                            }
+                        }
+
                         if success {
                             broadcast.log_info("Device Disconnected");
                         } else {
                             broadcast.log_error("Device Disconnected");
                         }
                         broadcast.finish_current_task(success);
+                        broadcast.send(None, Message::RunSessionEnded(key.clone()));
+                        if let Some(abort) = logs_abort.as_ref() {
+                            abort.notify_one();
+                        }
                         tracing::info!("[{target}] Runner Closed");
                         break;
                     }
@@ -154,7 +469,53 @@ impl RunHandler {
             Ok(())
         });
 
-        Ok(Self { process, inner })
+        Ok(Self {
+            process,
+            inner,
+            logs,
+            stopped,
+            relaunched,
+        })
+    }
+
+    /// Spawn app log stream and pipe its output to broadcast log
+    fn stream_logs(mut logs: Process, broadcast: Weak<Broadcast>) -> Result<Process> {
+        let mut stream = logs.spawn_and_stream()?;
+
+        tokio::spawn(async move {
+            while let Some(output) = stream.next().await {
+                let broadcast = match broadcast.upgrade() {
+                    Some(broadcast) => broadcast,
+                    None => break,
+                };
+
+                use process_stream::ProcessItem::*;
+                match output {
+                    Output(msg) => {
+                        if !msg.starts_with("Filtering the log data") {
+                            broadcast.log_info(msg);
+                        }
+                    }
+                    Error(msg) => broadcast.log_error(msg),
+                    Exit(_) => break,
+                }
+            }
+        });
+
+        Ok(logs)
+    }
+
+    /// Abort running process and its log stream
+    pub fn abort(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.process.abort();
+        if let Some(abort) = self.relaunched.lock().unwrap().take() {
+            abort.notify_waiters();
+        }
+        self.inner.abort();
+        if let Some(logs) = self.logs.as_ref() {
+            logs.abort();
+        }
     }
 
     /// Get a reference to the run service handler's process.
@@ -172,5 +533,40 @@ impl RunHandler {
 
 #[async_trait]
 pub trait Runner {
-    async fn run<'a>(&self, task: &Task) -> Result<Process>;
+    async fn run<'a>(&self, task: &Task, options: &LaunchOptions) -> Result<Process>;
+
+    /// Process streaming logs of the running app, if supported by the runner.
+    fn log_stream(&self) -> Option<Process> {
+        None
+    }
+
+    /// Bring the device running the app to front, if supported by the runner.
+    ///
+    /// Failures should only be reported as warnings, as the app is already running.
+    async fn focus(&self, _task: &Task) {}
+}
+
+#[test]
+fn test_keep_on_build_failure() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let key = RunSessionKey {
+            target: "App".into(),
+            device: None,
+        };
+        let running = || {
+            let process: Process = vec!["sleep", "10"].into();
+            Some(RunHandler::new(&key, process, None, None, Weak::new()).unwrap())
+        };
+
+        let mut handler = running();
+        assert!(keep_on_build_failure(&mut handler, false));
+        assert!(!handler.as_ref().unwrap().inner().is_finished());
+
+        assert!(!keep_on_build_failure(&mut handler, true));
+        assert!(handler.is_none());
+
+        // Nothing to keep
+        assert!(!keep_on_build_failure(&mut None, false));
+    });
 }
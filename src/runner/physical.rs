@@ -0,0 +1,81 @@
+use super::*;
+use crate::*;
+use process_stream::Process;
+use std::path::PathBuf;
+use tokio::process::Command;
+use xclog::XCBuildSettings;
+
+/// Physical Device runner
+pub struct PhysicalRunner {
+    pub device: PhysicalDevice,
+    pub app_id: String,
+    pub output_dir: PathBuf,
+}
+
+#[async_trait::async_trait]
+impl Runner for PhysicalRunner {
+    async fn run<'a>(&self, task: &Task, options: &LaunchOptions) -> Result<Process> {
+        self.install(task).await?;
+        self.launch(task, options).await
+    }
+}
+
+impl PhysicalRunner {
+    pub fn new(device: PhysicalDevice, info: &XCBuildSettings) -> Self {
+        Self {
+            device,
+            app_id: info.product_bundle_identifier.clone(),
+            output_dir: info.metal_library_output_dir.clone(),
+        }
+    }
+
+    pub async fn install<'a>(&self, task: &Task) -> Result<()> {
+        task.info(format!("[{}] Installing {}", self.device.name, self.app_id));
+
+        let output = Command::new("xcrun")
+            .envs(crate::xcodebuild::envs())
+            .args(["devicectl", "device", "install", "app", "--device"])
+            .arg(&self.device.udid)
+            .arg(&self.output_dir)
+            .output()
+            .await?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let error = Error::Run(format!("[{}] {}", self.device.name, stderr.trim()));
+            task.error(error.to_string());
+            Err(error)
+        }
+    }
+
+    pub async fn launch<'a>(&self, task: &Task, options: &LaunchOptions) -> Result<Process> {
+        task.info(format!("[{}] Launching {}", self.device.name, self.app_id));
+        let mut process = Process::new("xcrun");
+        crate::xcodebuild::set_envs(&mut process);
+
+        process.args(&[
+            "devicectl",
+            "device",
+            "process",
+            "launch",
+            "--terminate-existing",
+            "--console",
+            "--device",
+            &self.device.udid,
+        ]);
+
+        if !options.env.is_empty() {
+            let env = serde_json::to_string(&options.env)?;
+            process.args(&["--environment-variables", &env]);
+        }
+
+        process.arg(&self.app_id);
+        process.args(&options.args);
+
+        task.info(format!("[{}]", self.device.name));
+
+        Ok(process)
+    }
+}
@@ -0,0 +1,120 @@
+use crate::RunService;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use typescript_type_def::TypeDef;
+
+/// Key of a run session, a project target running on a device
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, TypeDef)]
+pub struct RunSessionKey {
+    pub target: String,
+    /// Device udid, None when running a binary
+    pub device: Option<String>,
+}
+
+impl std::fmt::Display for RunSessionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let device = self.device.as_deref().unwrap_or("Bin");
+        write!(f, "{}:{device}", self.target)
+    }
+}
+
+/// Active run sessions of a project, indexed by target and device
+#[derive(Default)]
+pub struct RunSessions {
+    inner: HashMap<RunSessionKey, RunService>,
+}
+
+impl RunSessions {
+    /// Get run service of a given session
+    pub fn get(&self, key: &RunSessionKey) -> Option<&RunService> {
+        self.inner.get(key)
+    }
+
+    /// Find session of a run service with a given key, i.e. `RunRequest` formatted as string
+    pub fn find(&self, service_key: &str) -> Option<RunSessionKey> {
+        self.inner
+            .iter()
+            .find(|(_, service)| service.key == service_key)
+            .map(|(key, _)| key.clone())
+    }
+
+    /// Whether a given session is still running
+    pub async fn is_active(&self, key: &RunSessionKey) -> bool {
+        match self.inner.get(key) {
+            Some(service) => service.is_running().await,
+            None => false,
+        }
+    }
+
+    /// Track a new session, replacing a previous session with the same key
+    pub fn insert(&mut self, service: RunService) -> Option<RunService> {
+        self.inner.insert(service.session_key(), service)
+    }
+
+    /// List running sessions, forgetting the ones that have ended
+    pub async fn list(&mut self) -> Vec<RunSessionKey> {
+        let mut ended = vec![];
+        for (key, service) in self.inner.iter() {
+            if !service.is_running().await {
+                ended.push(key.clone());
+            }
+        }
+
+        for key in ended {
+            self.inner.remove(&key);
+        }
+
+        self.inner.keys().cloned().collect()
+    }
+
+    /// Stop a given session, returns false if no session exists with the given key
+    pub async fn stop(&mut self, key: &RunSessionKey) -> bool {
+        match self.inner.remove(key) {
+            Some(service) => {
+                service.stop().await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop all sessions, returning their keys
+    pub async fn stop_all(&mut self) -> Vec<RunSessionKey> {
+        let keys = self.inner.keys().cloned().collect::<Vec<_>>();
+        for key in keys.iter() {
+            self.stop(key).await;
+        }
+        keys
+    }
+
+    /// Stop sessions started by a given client
+    pub async fn stop_client(&mut self, client: u32) -> Vec<RunSessionKey> {
+        let keys = self
+            .inner
+            .iter()
+            .filter(|(_, service)| service.client == Some(client))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for key in keys.iter() {
+            self.stop(key).await;
+        }
+
+        keys
+    }
+}
+
+#[test]
+fn test_run_session_key_display() {
+    let key = RunSessionKey {
+        target: "App".into(),
+        device: Some("A1".into()),
+    };
+    assert_eq!(key.to_string(), "App:A1");
+
+    let key = RunSessionKey {
+        target: "Cli".into(),
+        device: None,
+    };
+    assert_eq!(key.to_string(), "Cli:Bin");
+}
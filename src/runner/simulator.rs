@@ -1,6 +1,7 @@
 use super::*;
 use crate::*;
 use process_stream::Process;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tap::Pipe;
 use tokio::process::Command;
@@ -15,15 +16,56 @@ pub struct SimulatorRunner {
 
 #[async_trait::async_trait]
 impl Runner for SimulatorRunner {
-    async fn run<'a>(&self, task: &Task) -> Result<Process> {
+    async fn run<'a>(&self, task: &Task, options: &LaunchOptions) -> Result<Process> {
         self.boot(task).await?;
         self.install(task).await?;
-        let process = self.launch(task).await;
+        let process = self.launch(task, options).await;
         process
     }
+
+    fn log_stream(&self) -> Option<Process> {
+        let predicate = format!("subsystem == \"{}\"", self.app_id);
+        let mut process = Process::new("xcrun");
+        crate::xcodebuild::set_envs(&mut process);
+        process.args(&[
+            "simctl",
+            "spawn",
+            &self.device.udid,
+            "log",
+            "stream",
+            "--style",
+            "compact",
+            "--level",
+            "debug",
+            "--predicate",
+            &predicate,
+        ]);
+        Some(process)
+    }
+
+    async fn focus(&self, task: &Task) {
+        if !cfg!(target_os = "macos") {
+            return;
+        }
+
+        let udid = &self.device.udid;
+        let open = Command::new("open")
+            .args(["-a", "Simulator", "--args", "-CurrentDeviceUDID", udid])
+            .status();
+
+        match tokio::time::timeout(Self::FOCUS_TIMEOUT, open).await {
+            Ok(Ok(status)) if status.success() => {}
+            Ok(Ok(status)) => task.warn(format!("[Simulator] Failed to focus: {status}")),
+            Ok(Err(err)) => task.warn(format!("[Simulator] Failed to focus: {err}")),
+            Err(_) => task.warn("[Simulator] Timed out focusing"),
+        }
+    }
 }
 
 impl SimulatorRunner {
+    /// Max time to wait for Simulator.app to be brought to front
+    const FOCUS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
     pub fn new(device: Device, info: &XCBuildSettings) -> Self {
         Self {
             device,
@@ -49,12 +91,11 @@ impl SimulatorRunner {
             _ => {}
         }
 
-        task.info(self.booting_msg());
-        if let Err(e) = self.device.boot() {
-            let err: Error = e.into();
-            let err_msg = err.to_string();
-            if !err_msg.contains("current state Booted") {
-                // task.log_error(err_msg);
+        if !self.device.is_booted().await {
+            task.info(self.booting_msg());
+            if let Err(err) = self.device.boot().await {
+                task.error(err.to_string());
+                return Err(err);
             }
         }
         Ok(())
@@ -69,9 +110,10 @@ impl SimulatorRunner {
         Ok(())
     }
 
-    pub async fn launch<'a>(&self, task: &Task) -> Result<Process> {
+    pub async fn launch<'a>(&self, task: &Task, options: &LaunchOptions) -> Result<Process> {
         task.info(self.launching_msg());
         let mut process = Process::new("xcrun");
+        crate::xcodebuild::set_envs(&mut process);
         let args = &[
             "simctl",
             "launch",
@@ -82,6 +124,8 @@ impl SimulatorRunner {
         ];
 
         process.args(args);
+        process.args(&options.args);
+        process.envs(Self::launch_env(&options.env));
 
         task.info(self.connected_msg());
 
@@ -98,6 +142,15 @@ impl SimulatorRunner {
         }
     }
 
+    /// Environment variables to forward to the launched app.
+    ///
+    /// simctl only passes variables prefixed with `SIMCTL_CHILD_` to the launched process.
+    pub fn launch_env(env: &HashMap<String, String>) -> HashMap<String, String> {
+        env.iter()
+            .map(|(key, value)| (format!("SIMCTL_CHILD_{key}"), value.clone()))
+            .collect()
+    }
+
     fn booting_msg(&self) -> String {
         format!("[{}] Booting", self.device.name)
     }
@@ -114,3 +167,12 @@ impl SimulatorRunner {
         format!("[{}]", self.device.name)
     }
 }
+
+#[test]
+fn test_launch_env() {
+    let env = HashMap::from([("FOO".to_string(), "bar".to_string())]);
+    let launch_env = SimulatorRunner::launch_env(&env);
+
+    assert_eq!(launch_env.get("SIMCTL_CHILD_FOO"), Some(&"bar".to_string()));
+    assert_eq!(launch_env.len(), 1);
+}
@@ -0,0 +1,70 @@
+use crate::{LaunchOptions, Result, Runner, Task};
+use process_stream::Process;
+use std::path::PathBuf;
+
+/// Swift package executable product runner, using `swift run`
+pub struct SwiftRunner {
+    pub root: PathBuf,
+    pub product: String,
+    pub configuration: Option<String>,
+}
+
+impl SwiftRunner {
+    fn args(&self, options: &LaunchOptions) -> Vec<String> {
+        // Product is built beforehand, to report build output as a build task
+        let mut args = vec!["run".to_string(), "--skip-build".into()];
+        if let Some(ref configuration) = self.configuration {
+            args.extend_from_slice(&["-c".into(), configuration.to_lowercase()]);
+        }
+        args.push(self.product.clone());
+        if !options.args.is_empty() {
+            args.push("--".into());
+            args.extend(options.args.iter().cloned());
+        }
+        args
+    }
+}
+
+#[async_trait::async_trait]
+impl Runner for SwiftRunner {
+    async fn run<'a>(&self, task: &Task, options: &LaunchOptions) -> Result<Process> {
+        task.info(format!("[SwiftPM] Running {}", self.product));
+
+        let mut process = Process::new("/usr/bin/swift");
+        process.args(&self.args(options));
+        process.envs(&options.env);
+        process.current_dir(&self.root);
+
+        Ok(process)
+    }
+}
+
+#[test]
+fn test_swift_run_args() {
+    let runner = SwiftRunner {
+        root: "/tmp/Server".into(),
+        product: "server".into(),
+        configuration: Some("Release".into()),
+    };
+    let options = LaunchOptions {
+        args: vec!["--port".into(), "8080".into()],
+        ..Default::default()
+    };
+    assert_eq!(
+        runner.args(&options),
+        vec![
+            "run",
+            "--skip-build",
+            "-c",
+            "release",
+            "server",
+            "--",
+            "--port",
+            "8080"
+        ]
+    );
+    assert_eq!(
+        runner.args(&LaunchOptions::default()),
+        vec!["run", "--skip-build", "-c", "release", "server"]
+    );
+}
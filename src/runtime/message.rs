@@ -1,7 +1,13 @@
-use crate::server::{BuildRequest, RunRequest};
+use crate::server::{BuildRequest, CleanRequest, RegisterResponse, RunRequest};
+use crate::server::{StopRequest, TestRequest, WatchTargetRequest};
+use crate::ProjectDetails;
+use crate::Result;
+use crate::{Broadcast, BuildSettings};
 use crate::{Event, PathExt};
-use std::{collections::HashSet, path::PathBuf};
-use tokio::sync::mpsc;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock, Weak};
+use tokio::sync::{mpsc, oneshot};
 
 /// Project Runime Message
 #[derive(Debug)]
@@ -16,6 +22,40 @@ pub enum PRMessage {
     Run(RunRequest),
     /// Process Build Request
     Build(BuildRequest),
+    /// Process Clean Request
+    Clean(CleanRequest),
+    /// Process Test Request
+    Test(TestRequest),
+    /// Process Stop Request, replying with whether something was stopped
+    Stop(StopRequest, oneshot::Sender<Result<()>>),
+    /// Update compile commands of changed sources with ones logged by builds, if any
+    BuildCompileCommands(Option<Vec<serde_json::Value>>, Vec<PathBuf>),
+    /// Reply with project state returned to registering clients
+    GetState(oneshot::Sender<RegisterResponse>),
+    /// Regenerate project, replying whether it can be generated before generating it
+    Generate(oneshot::Sender<Result<()>>),
+    /// Regenerate compile commands, replying whether the project has any before generating them
+    GenerateCompileCommands(oneshot::Sender<Result<()>>),
+    /// Pause acting on file changes
+    Pause,
+    /// Resume acting on file changes, processing the last change made while paused if true
+    Resume(bool),
+    /// Start or stop watching a target
+    WatchTarget(WatchTargetRequest),
+    /// Update clients with workspace schemes listed by xcodebuild
+    SchemesListed,
+    /// Start building a target of a multi-target build with extra xcodebuild flags, replying with
+    /// the build result receiver
+    StartBuild(
+        BuildSettings,
+        Vec<String>,
+        oneshot::Sender<Result<mpsc::Receiver<Result<()>>>>,
+    ),
+    /// Move project to a new root, replying with the new broadcaster and project state
+    Relocate(
+        PathBuf,
+        oneshot::Sender<Result<(Arc<Broadcast>, RegisterResponse)>>,
+    ),
 }
 
 #[derive(Debug)]
@@ -24,23 +64,34 @@ pub struct PRMessageSender {
     root: PathBuf,
     /// Message Broadcaster address
     broadcaster_adderss: PathBuf,
+    /// Message Broadcaster, used to shut it down with the daemon
+    broadcaster: Weak<Broadcast>,
     /// PRMessage sender
     sender: mpsc::UnboundedSender<PRMessage>,
     /// Connect Cilents
     clients: HashSet<u32>,
+    /// Platform of each project target, kept up to date by the runtime
+    platforms: Arc<RwLock<HashMap<String, String>>>,
+    /// Project details, kept up to date by the runtime
+    details: Arc<RwLock<Option<ProjectDetails>>>,
 }
 
 impl PRMessageSender {
     pub fn new(
         root: &PathBuf,
-        baddress: &PathBuf,
+        broadcaster: &Arc<Broadcast>,
         sender: &mpsc::UnboundedSender<PRMessage>,
+        platforms: &Arc<RwLock<HashMap<String, String>>>,
+        details: &Arc<RwLock<Option<ProjectDetails>>>,
     ) -> Self {
         Self {
             root: root.clone(),
-            broadcaster_adderss: baddress.clone(),
+            broadcaster_adderss: broadcaster.address().clone(),
+            broadcaster: Arc::downgrade(broadcaster),
             sender: sender.clone(),
             clients: HashSet::default(),
+            platforms: platforms.clone(),
+            details: details.clone(),
         }
     }
 
@@ -66,6 +117,22 @@ impl PRMessageSender {
         }
     }
 
+    /// Disconnect clients whose process no longer exists, returning their ids
+    pub fn disconnect_dead(&mut self) -> Vec<u32> {
+        let dead = self
+            .clients
+            .iter()
+            .filter(|id| !crate::pid::is_alive(**id))
+            .copied()
+            .collect::<Vec<_>>();
+
+        for id in dead.iter() {
+            self.disconnect(*id);
+        }
+
+        dead
+    }
+
     pub fn send(&self, message: PRMessage) {
         if let Err(e) = self.sender.send(message) {
             tracing::error!("Failed to send {e:#?}");
@@ -76,10 +143,49 @@ impl PRMessageSender {
         self.sender.is_closed()
     }
 
+    /// Point to a relocated runtime root and broadcaster
+    pub fn relocate(&mut self, root: &PathBuf, broadcaster: &Arc<Broadcast>) {
+        self.root = root.clone();
+        self.broadcaster_adderss = broadcaster.address().clone();
+        self.broadcaster = Arc::downgrade(broadcaster);
+    }
+
     pub fn broadcaster_adderss(&self) -> &PathBuf {
         &self.broadcaster_adderss
     }
 
+    /// Get message broadcaster, if the runtime is still alive
+    pub fn broadcaster(&self) -> Option<Arc<Broadcast>> {
+        self.broadcaster.upgrade()
+    }
+
+    /// Get platform of a given target, or platforms of all project targets if target is unset or
+    /// unknown (e.g. a scheme)
+    pub fn platforms(&self, target: Option<&str>) -> Vec<String> {
+        let platforms = match self.platforms.read() {
+            Ok(platforms) => platforms,
+            Err(_) => return vec![],
+        };
+        if let Some(platform) = target.and_then(|target| platforms.get(target)) {
+            return vec![platform.clone()];
+        }
+
+        let mut all = platforms.values().cloned().collect::<Vec<_>>();
+        all.sort();
+        all.dedup();
+        all
+    }
+
+    /// Get project targets, schemes and configurations, as last shared by the runtime
+    pub fn details(&self) -> Option<ProjectDetails> {
+        self.details.read().ok().and_then(|details| details.clone())
+    }
+
+    /// Get number of connected clients
+    pub fn clients(&self) -> u32 {
+        self.clients.len() as u32
+    }
+
     pub fn contains(&self, value: &u32) -> bool {
         self.clients.contains(value)
     }
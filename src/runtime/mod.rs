@@ -2,8 +2,10 @@ mod message;
 pub use message::*;
 
 use crate::{server::*, *};
-use std::{path::PathBuf, sync::Arc};
-use tokio::sync::{mpsc, Notify};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tokio::sync::{mpsc, oneshot, Notify};
 use tracing::{info, instrument};
 
 /// ProjectRuntime
@@ -12,6 +14,8 @@ pub struct ProjectRuntime {
     name: String,
     /// Project Data
     project: ProjectImpl,
+    /// Project configuration
+    config: ProjectConfig,
     /// Client Broadcaster
     broadcaster: Arc<Broadcast>,
     /// Receiver to receive PRMessages,
@@ -22,10 +26,22 @@ pub struct ProjectRuntime {
     watcher_subscribers: WatchSubscribers,
     /// Build/Run requests subscribed to changes
     watcher_state: WatcherState,
+    /// Active run sessions
+    sessions: RunSessions,
     /// Connect clients id
     clients: u32,
     /// Notifer to notify listeners that this runtime is no longer active
     abort: Arc<Notify>,
+    /// Platform of each project target, shared with PRMessageSender
+    platforms: Arc<RwLock<HashMap<String, String>>>,
+    /// Project details, shared with PRMessageSender and refreshed after every message
+    details: Arc<RwLock<Option<ProjectDetails>>>,
+    /// Project targets and schemes, as last sent to clients
+    buildables: HashMap<String, TargetInfo>,
+    /// Whether acting on file changes is paused
+    paused: bool,
+    /// Last file change made while paused
+    paused_event: Option<Event>,
 }
 
 impl ProjectRuntime {
@@ -33,28 +49,43 @@ impl ProjectRuntime {
     pub async fn new(root: PathBuf) -> Result<(Self, PRMessageSender)> {
         info!("[Initializing] ------------------------");
         let (sender, receiver) = mpsc::unbounded_channel::<PRMessage>();
-        let broadcaster = Arc::new(Broadcast::new(&root).await?);
+        let config = ProjectConfig::load(&root).await;
+        let broadcaster = Arc::new(Broadcast::new(&root, broadcast_config(&config)).await?);
+        project::run_load_hook(&root, &broadcaster).await?;
         let project = project::project(&root, &broadcaster).await?;
-        let rsender = PRMessageSender::new(&root, broadcaster.address(), &sender);
+        let platforms = Arc::new(RwLock::new(project.targets_platform()));
+        let details = Arc::new(RwLock::new(None));
+        let rsender = PRMessageSender::new(&root, &broadcaster, &sender, &platforms, &details);
         let name = project.name().to_string();
         let watcher_subscribers = WatchSubscribers::new(&name);
+        let buildables = project.buildables();
         let runtime = Self {
             name,
             clients: Default::default(),
             abort: Default::default(),
             watcher_state: WatcherState::new(),
             watcher_subscribers,
+            sessions: Default::default(),
             broadcaster,
             project,
+            config,
+            platforms,
+            details,
+            buildables,
+            paused: false,
+            paused_event: None,
             receiver,
             sender,
         };
+        runtime.share_details();
         Ok((runtime, rsender))
     }
 
     /// Start Runtime Loop
     #[instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name))]
     pub async fn start(mut self, id: u32) {
+        self.ensure_pods().await;
+
         if let Err(err) = self
             .project
             .ensure_setup(None.as_ref(), &self.broadcaster)
@@ -63,17 +94,17 @@ impl ProjectRuntime {
             self.broadcaster.error(format!("[{}]  {err}", self.name));
         };
 
-        tokio::spawn(
-            Watcher::new(
-                &self.name,
-                &self.watcher_state,
-                &self.sender,
-                &self.abort,
-                self.project.root(),
-                self.project.watchignore(),
-            )
-            .start(),
-        );
+        for (pattern, err) in self.config.invalid_globs() {
+            let file = ProjectConfig::FILE_NAME;
+            let msg = format!(
+                "[{}] Ignoring invalid glob `{pattern}` in {file}: {err}",
+                self.name
+            );
+            self.broadcaster.warn(msg);
+        }
+        self.check_xcode_version();
+        self.list_schemes();
+        self.start_watcher();
         self.on_connect(id);
 
         info!("[Initialized] -------------------------");
@@ -82,7 +113,10 @@ impl ProjectRuntime {
                 PRMessage::Connect(id) => self.on_connect(id),
                 PRMessage::Disconnect(id) => {
                     info!("Disconnected [{id}]");
-                    self.clients -= 1;
+                    self.clients = self.clients.saturating_sub(1);
+                    for key in self.sessions.stop_client(id).await {
+                        self.broadcaster.send(None, Message::RunSessionEnded(key));
+                    }
                     self.broadcaster.send(Some(id), Message::Disconnect);
                     if self.clients.eq(&0) {
                         self.broadcaster.abort();
@@ -94,11 +128,109 @@ impl ProjectRuntime {
                 PRMessage::FSEvent(event) => self.on_fs_event(event).await,
                 PRMessage::Run(req) => self.on_run(req).await,
                 PRMessage::Build(req) => self.on_build(req).await,
+                PRMessage::Clean(req) => self.on_clean(req).await,
+                PRMessage::Test(req) => self.on_test(req).await,
+                PRMessage::Stop(req, reply) => {
+                    reply.send(self.on_stop(req).await).ok();
+                }
+                PRMessage::BuildCompileCommands(commands, changed) => {
+                    self.on_build_compile_commands(commands, changed).await
+                }
+                PRMessage::GetState(reply) => {
+                    let sessions = self.sessions.list().await;
+                    reply.send(self.state(sessions)).ok();
+                }
+                PRMessage::Generate(reply) => self.on_generate(reply).await,
+                PRMessage::GenerateCompileCommands(reply) => {
+                    self.on_generate_compile_commands(reply).await
+                }
+                PRMessage::Pause => self.set_paused(true),
+                PRMessage::Resume(catch_up) => {
+                    self.set_paused(false);
+                    match self.paused_event.take() {
+                        Some(event) if catch_up => self.on_fs_event(event).await,
+                        _ => {}
+                    }
+                }
+                PRMessage::WatchTarget(req) => self.on_watch_target(req).await,
+                PRMessage::SchemesListed => self.set_client_project_state(None),
+                PRMessage::StartBuild(settings, flags, reply) => {
+                    reply.send(self.start_build(&settings, &flags)).ok();
+                }
+                PRMessage::Relocate(root, reply) => {
+                    reply.send(self.on_relocate(root).await).ok();
+                }
             }
+            self.share_details();
         }
         info!("[Dropped]");
     }
 
+    fn start_watcher(&self) {
+        tokio::spawn(
+            Watcher::new(
+                &self.name,
+                &self.watcher_state,
+                &self.sender,
+                &self.abort,
+                self.project.root(),
+                self.project.watchignore(),
+                &self.config,
+            )
+            .start(),
+        );
+    }
+
+    /// Move project to a new root, e.g. after its directory was renamed.
+    ///
+    /// Clients, watched requests and pause state are kept, while the broadcast socket is recreated
+    /// for the new root and running apps are stopped, as their build products are cached by root.
+    #[instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name))]
+    async fn on_relocate(&mut self, root: PathBuf) -> Result<(Arc<Broadcast>, RegisterResponse)> {
+        info!("Relocating to {root:?}");
+        let config = ProjectConfig::load(&root).await;
+        let broadcaster = Arc::new(Broadcast::new(&root, broadcast_config(&config)).await?);
+        let project = match project::project(&root, &broadcaster).await {
+            Ok(project) => project,
+            Err(err) => {
+                broadcaster.shutdown(SHUTDOWN_TIMEOUT).await;
+                return Err(Error::Relocate(root, err.to_string()));
+            }
+        };
+
+        for key in self.sessions.stop_all().await {
+            self.broadcaster.send(None, Message::RunSessionEnded(key));
+        }
+
+        let previous = std::mem::replace(&mut self.broadcaster, broadcaster);
+        let address = self.broadcaster.address().clone();
+        previous.warn(format!(
+            "[{}] Moved to {root:?}, register it again",
+            self.name
+        ));
+        tokio::spawn(async move { previous.shutdown(SHUTDOWN_TIMEOUT).await });
+
+        self.abort.notify_waiters();
+        self.abort = Default::default();
+        self.name = project.name().to_string();
+        self.project = project;
+        self.config = config;
+        self.watcher_subscribers.relocate(&root);
+        self.start_watcher();
+        self.set_client_project_state(None);
+        self.list_schemes();
+
+        // Compile commands reference sources by absolute path
+        let broadcast = self.broadcaster.clone();
+        if let Err(err) = self.project.update_compile_database(&broadcast).await {
+            broadcast.error(format!("[{}] {err}", self.name));
+        }
+        broadcast.reload_lsp_server();
+        info!("Relocated, broadcasting on {address:?}");
+
+        Ok((broadcast, self.state(vec![])))
+    }
+
     fn on_connect(&mut self, id: u32) {
         info!("Connected [{id}]");
         self.clients += 1;
@@ -110,7 +242,16 @@ impl ProjectRuntime {
 
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
     async fn on_fs_event(&mut self, event: Event) {
-        let name = &self.name;
+        let name = self.name.clone();
+
+        // Builds started before the change don't include it
+        self.broadcaster.shared_builds().invalidate();
+
+        if self.paused {
+            info!("Paused, skipping {event}");
+            self.paused_event = Some(event);
+            return;
+        }
 
         info!("Processing {event}");
         if event.is_create_event()
@@ -118,33 +259,123 @@ impl ProjectRuntime {
             || event.is_content_update_event()
             || event.is_rename_event() && !event.is_seen()
         {
+            let pods_installed = event.file_name() == "Podfile" && self.ensure_pods().await;
             let ensure_setup = self.project.ensure_setup(Some(&event), &self.broadcaster);
             match ensure_setup.await {
                 Err(e) => self.broadcaster.error(format!("[{name}] {e}")),
-                Ok(true) => self.set_client_project_state(None),
+                Ok(false) if pods_installed => {
+                    // Pods add targets and headers to the workspace
+                    let update = self.project.update_compile_database(&self.broadcaster);
+                    if let Err(e) = update.await {
+                        self.broadcaster.error(format!("[{name}] {e}"));
+                    }
+                    self.set_client_project_state(None);
+                }
+                Ok(true) => {
+                    self.set_client_project_state(None);
+                    self.list_schemes();
+                }
                 _ => {}
             };
         }
 
-        self.watcher_subscribers
+        if event.is_content_update_event() && event.path().ends_with("project.pbxproj") {
+            match self.project.reload(&self.broadcaster).await {
+                Ok(true) => {
+                    self.set_client_project_state(None);
+                    self.list_schemes();
+                }
+                Ok(false) => {}
+                Err(e) => self.broadcaster.error(format!("[{name}] {e}")),
+            }
+        }
+
+        let triggered = self
+            .watcher_subscribers
             .trigger(&mut self.project, &event, &self.broadcaster)
             .await;
 
+        let is_source_update =
+            event.is_content_update_event() && event.file_kind() == FileKind::Source;
+        if self.config.incremental_compile && is_source_update {
+            let changed = vec![event.path().clone()];
+            if triggered.is_empty() {
+                self.on_build_compile_commands(None, changed).await;
+            } else {
+                // Builds triggered by the change supersede running xcodebuild again to update
+                // compile commands, which are taken from the builds once they all finished
+                let (broadcast, sender) = (self.broadcaster.clone(), self.sender.clone());
+                tokio::spawn(async move {
+                    let commands = broadcast.coordinator().wait(&triggered).await;
+                    sender
+                        .send(PRMessage::BuildCompileCommands(commands, changed))
+                        .ok();
+                });
+            }
+        }
+
         info!("Processed {event}");
 
         self.watcher_state.update_debounce();
     }
 
+    /// Update compile commands of changed sources from commands logged by builds, if any, or
+    /// with an incremental build otherwise
+    async fn on_build_compile_commands(
+        &mut self,
+        commands: Option<Vec<serde_json::Value>>,
+        changed: Vec<PathBuf>,
+    ) {
+        let (project, broadcast) = (&self.project, &self.broadcaster);
+        let update = match commands {
+            Some(commands) => {
+                project
+                    .merge_compile_database(commands, &changed, broadcast)
+                    .await
+            }
+            None => {
+                project
+                    .update_compile_database_incremental(&changed, broadcast)
+                    .await
+            }
+        };
+
+        if let Err(e) = update {
+            self.broadcaster.error(format!("[{}] {e}", self.name));
+        }
+    }
+
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
     async fn on_run(&mut self, req: RunRequest) {
         info!("Running {}", req.settings.target);
-        let is_watch = if !req.operation.is_stop() {
-            req.operation.is_watch()
-        } else {
+        let (is_watch, is_stop) = (req.operation.is_watch(), req.operation.is_stop());
+        if is_stop {
             self.watcher_subscribers.remove(&req).await;
-            return;
+        }
+        let exclusive = req.exclusive;
+        let service = match req.into_service().await {
+            Ok(service) => service,
+            Err(err) => {
+                self.broadcaster.error(format!("[{}] {err}", self.name));
+                return;
+            }
         };
-        let service = req.into_service();
+
+        let key = service.session_key();
+        if self.sessions.is_active(&key).await {
+            if exclusive && !is_stop {
+                let msg = format!("[{}] {key} is already running", self.name);
+                self.broadcaster.error(msg);
+                return;
+            }
+            self.sessions.stop(&key).await;
+            self.broadcaster.send(None, Message::RunSessionEnded(key));
+        }
+
+        if is_stop {
+            return;
+        }
+
         let event = Event::default();
         let res = service.trigger(&mut self.project, &event, &self.broadcaster);
         if let Err(err) = res.await {
@@ -152,6 +383,7 @@ impl ProjectRuntime {
             self.broadcaster.error(msg);
         }
         info!("Ran {}", service.settings.target);
+        self.sessions.insert(service.clone());
         if is_watch {
             self.watcher_subscribers.add(service);
         }
@@ -159,12 +391,31 @@ impl ProjectRuntime {
 
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
     async fn on_build(&mut self, req: BuildRequest) {
+        let mut requests = req.split(self.project.targets());
+        let req = match requests.len() {
+            0 => {
+                return self
+                    .broadcaster
+                    .error(format!("[{}] No targets to build", self.name))
+            }
+            _ if requests[0].dry_run => return self.on_dry_run(requests).await,
+            1 => requests.remove(0),
+            n if requests[0].run => {
+                let msg = format!("[{}] Can't build and run {n} targets at once", self.name);
+                return self.broadcaster.error(msg);
+            }
+            _ => return self.on_build_many(requests).await,
+        };
+
         let is_watch = if !req.operation.is_stop() {
             req.operation.is_watch()
         } else {
             self.watcher_subscribers.remove(&req).await;
             return;
         };
+        if req.run {
+            return self.on_build_and_run(req, is_watch).await;
+        }
 
         info!("Building {}", req.settings.target);
         let event = Event::default();
@@ -179,16 +430,493 @@ impl ProjectRuntime {
         }
     }
 
+    /// Build and launch the built target, again after every successful build when watching
+    async fn on_build_and_run(&mut self, req: BuildRequest, is_watch: bool) {
+        let watchable = match req.run_service().await {
+            Ok(run) => BuildAndRun { build: req, run },
+            Err(err) => return self.broadcaster.error(format!("[{}] {err}", self.name)),
+        };
+
+        let key = watchable.run.session_key();
+        if self.sessions.is_active(&key).await {
+            self.sessions.stop(&key).await;
+            self.broadcaster.send(None, Message::RunSessionEnded(key));
+        }
+
+        info!("Building and running {}", watchable.target());
+        let event = Event::default();
+        let res = watchable.trigger(&mut self.project, &event, &self.broadcaster);
+        if let Err(err) = res.await {
+            let msg = format!("[{}] failed to start runner {err}", self.name);
+            self.broadcaster.error(msg);
+        }
+        self.sessions.insert(watchable.run.clone());
+        if is_watch {
+            self.watcher_subscribers.add(watchable);
+        }
+    }
+
+    /// Warn, without blocking, when the project expects a newer Xcode than the active one, as
+    /// builds would otherwise fail with obscure errors
+    fn check_xcode_version(&self) {
+        let xcodeproj = match self.project.xcodeproj() {
+            Some(xcodeproj) => xcodeproj.clone(),
+            None => return,
+        };
+        let (name, broadcast) = (self.name.clone(), self.broadcaster.clone());
+        tokio::spawn(async move {
+            let expected = match expected_xcode_version(&xcodeproj) {
+                Some(expected) => expected,
+                None => return,
+            };
+            let active = match tokio::task::spawn_blocking(xcodebuild::version).await {
+                Ok(Some(active)) if active < expected => active,
+                _ => return,
+            };
+            let version = |(major, minor): (u32, u32)| format!("{major}.{minor}");
+            let (expected, active) = (version(expected), version(active));
+            broadcast.warn(format!(
+                "[{name}] Project expects Xcode {expected} or newer, \
+                 but Xcode {active} is active, builds may fail"
+            ));
+        });
+    }
+
+    /// Start or stop watching a target, leaving other watchers untouched
+    async fn on_watch_target(&mut self, req: WatchTargetRequest) {
+        let (name, target) = (self.name.clone(), req.target.clone());
+        if !self.buildables.contains_key(&target) {
+            let err = Error::Lookup(target, "target".into());
+            return self.broadcaster.error(format!("[{name}] {err}"));
+        }
+
+        let watched = self
+            .watcher_subscribers
+            .target_keys(&target, TaskKind::Build);
+
+        match (req.enable, watched.is_empty()) {
+            (true, false) => {
+                return self
+                    .broadcaster
+                    .info(format!("[{name}] Already watching {target}"))
+            }
+            (false, true) => {
+                return self
+                    .broadcaster
+                    .info(format!("[{name}] Not watching {target}"))
+            }
+            (true, true) => self.watcher_subscribers.add(req.build_request()),
+            (false, false) => {
+                for key in watched {
+                    self.watcher_subscribers.remove(&key).await;
+                }
+            }
+        }
+
+        info!("Set watching {target}: {}", req.enable);
+        let message = Message::SetWatching {
+            watching: req.enable,
+            settings: req.build_request().settings,
+        };
+        self.broadcaster.send(None, message);
+        self.set_client_project_state(None);
+    }
+
+    /// Report commands builds would run, without running them
+    async fn on_dry_run(&self, requests: Vec<BuildRequest>) {
+        let config = ProjectConfig::load(self.project.root()).await;
+        for req in requests {
+            let target = &req.settings.target;
+            let device = match Devices::from_lookup(req.device.clone()).await {
+                Ok(device) => device,
+                Err(err) => return self.broadcaster.error(format!("[{}] {err}", self.name)),
+            };
+            let settings = config.settings_for(&req.settings);
+            match self.project.dry_run(&settings, device.as_ref()) {
+                Ok(command) => self.broadcaster.log_info(format!("[{target}] {command}")),
+                Err(err) => self.broadcaster.error(format!("[{}] {err}", self.name)),
+            }
+        }
+    }
+
+    /// Build multiple targets one after another, as concurrent xcodebuild runs fail to lock the
+    /// shared build database.
+    ///
+    /// Builds are awaited in a spawned task, which has the runtime start each one.
+    async fn on_build_many(&mut self, requests: Vec<BuildRequest>) {
+        if requests[0].operation.is_stop() {
+            for req in requests {
+                self.watcher_subscribers.remove(&req).await;
+            }
+            return;
+        }
+
+        let (total, name) = (requests.len(), self.name.clone());
+        let (broadcast, sender) = (self.broadcaster.clone(), self.sender.clone());
+        let config = ProjectConfig::load(self.project.root()).await;
+        let mut builds = vec![];
+        for req in requests {
+            let result_bundle = req.result_bundle_path();
+            let flags = req.flags(result_bundle.as_deref());
+            builds.push((config.settings_for(&req.settings), flags, result_bundle));
+            if req.operation.is_watch() {
+                self.watcher_subscribers.add(req);
+            }
+        }
+
+        info!("Building {total} targets");
+        tokio::spawn(async move {
+            let mut failed = vec![];
+
+            for (settings, flags, result_bundle) in builds {
+                let target = settings.target.clone();
+                let (reply, started) = oneshot::channel();
+                broadcast.task_started(TaskKind::Build, &target);
+                if sender
+                    .send(PRMessage::StartBuild(settings, flags, reply))
+                    .is_err()
+                {
+                    return broadcast.task_finished(TaskKind::Build, &target, false);
+                }
+
+                let result = match started.await {
+                    Ok(Ok(mut recv)) => recv
+                        .recv()
+                        .await
+                        .unwrap_or(Err(Error::Build(target.clone()))),
+                    Ok(Err(err)) => {
+                        broadcast.error(format!("[{name}] {err}"));
+                        Err(err)
+                    }
+                    // Runtime closed
+                    Err(_) => return broadcast.task_finished(TaskKind::Build, &target, false),
+                };
+                if let Some(result_bundle) = result_bundle {
+                    report_result_bundle_issues(&result_bundle, &target, &broadcast).await;
+                }
+                broadcast.task_finished(TaskKind::Build, &target, result.is_ok());
+                if result.is_err() {
+                    failed.push(target);
+                }
+            }
+
+            if failed.is_empty() {
+                broadcast.info(format!("[{name}] Built {total} targets"));
+            } else {
+                let succeeded = total - failed.len();
+                let failed = failed.join(", ");
+                let msg = format!("[{name}] Built {succeeded}/{total} targets, failed: {failed}");
+                broadcast.error(msg);
+            }
+            info!("Built {total} targets");
+        });
+    }
+
+    /// Start building a target of a multi-target build
+    fn start_build(
+        &self,
+        settings: &BuildSettings,
+        flags: &[String],
+    ) -> Result<mpsc::Receiver<Result<()>>> {
+        let build = self.project.build(settings, None, flags, &self.broadcaster);
+        build.map(|(_, recv)| recv)
+    }
+
+    #[instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name))]
+    async fn on_clean(&mut self, req: CleanRequest) {
+        let (name, target) = (&self.name, &req.settings.target);
+
+        info!("Cleaning {target}");
+        // Cleaned products need to be built again before running
+        self.broadcaster.shared_builds().invalidate();
+        let config = ProjectConfig::load(self.project.root()).await;
+        let (settings, broadcast) = (&config.settings_for(&req.settings), &self.broadcaster);
+        match self.project.clean(settings, req.deep, broadcast).await {
+            Ok(_) => self.broadcaster.info(format!("[{name}] Cleaned {target}")),
+            Err(err) => self.broadcaster.error(format!("[{name}] {err}")),
+        }
+        info!("Cleaned {target}");
+    }
+
+    #[instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name))]
+    async fn on_test(&mut self, req: TestRequest) {
+        let (name, target) = (self.name.clone(), req.settings.target.clone());
+
+        info!("Testing {target}");
+        let settings = ProjectConfig::load(self.project.root())
+            .await
+            .settings_for(&req.settings);
+        let coverage = match self.project.kind() {
+            _ if !req.coverage => None,
+            ProjectKind::Swift | ProjectKind::Bazel => {
+                let msg = format!("[{name}] Coverage is only reported for xcodebuild projects");
+                self.broadcaster.warn(msg);
+                None
+            }
+            _ => {
+                let root = self.project.root();
+                match crate::fs::get_build_cache_dir_with_config(root, &settings) {
+                    Ok(dir) => Some((PathBuf::from(format!("{dir}.xcresult")), req.coverage_files)),
+                    Err(err) => return self.broadcaster.error(format!("[{name}] {err}")),
+                }
+            }
+        };
+
+        // xcodebuild fails if the result bundle already exists
+        let flags = match coverage.as_ref() {
+            Some((result_bundle, _)) => {
+                tokio::fs::remove_dir_all(result_bundle).await.ok();
+                req.flags(result_bundle)
+            }
+            None => vec![],
+        };
+
+        let recv = Devices::from_lookup(req.device).await.and_then(|device| {
+            let (settings, broadcast) = (&settings, &self.broadcaster);
+            let only_testing = &req.only_testing;
+            self.project
+                .test_with_flags(settings, device.as_ref(), only_testing, &flags, broadcast)
+        });
+
+        let mut recv = match recv {
+            Ok(recv) => recv,
+            Err(err) => return self.broadcaster.error(format!("[{name}] {err}")),
+        };
+
+        let broadcaster = self.broadcaster.clone();
+        tokio::spawn(async move {
+            match recv.recv().await {
+                Some(Ok(_)) => broadcaster.info(format!("[{name}] Tested {target}")),
+                Some(Err(err)) => broadcaster.error(format!("[{name}] {err}")),
+                None => return,
+            }
+            info!("Tested {target}");
+
+            // Failing tests still report coverage
+            let (result_bundle, files) = match coverage {
+                Some(coverage) if coverage.0.exists() => coverage,
+                _ => return,
+            };
+            match crate::read_coverage_report(&result_bundle, files).await {
+                Ok(messages) => messages
+                    .into_iter()
+                    .for_each(|message| broadcaster.send(None, message)),
+                Err(err) => broadcaster.error(format!("[{name}] Failed to read coverage: {err}")),
+            }
+        });
+    }
+
+    #[instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name))]
+    async fn on_generate(&mut self, reply: oneshot::Sender<Result<()>>) {
+        let name = self.name.clone();
+
+        if self.project.kind() == ProjectKind::Xcodeproj {
+            let msg = format!("[{name}] Nothing to generate, project has no definition file");
+            reply.send(Err(Error::Unexpected(msg))).ok();
+            return;
+        }
+        reply.send(Ok(())).ok();
+
+        info!("Generating");
+        let broadcast = self.broadcaster.clone();
+        broadcast.task_started(TaskKind::Generate, &name);
+        let generated = self.project.generate(&broadcast).await;
+        broadcast.task_finished(TaskKind::Generate, &name, generated.is_ok());
+        if let Err(err) = generated {
+            return broadcast.error(format!("[{name}] {err}"));
+        }
+
+        if let Err(err) = self.project.update_compile_database(&broadcast).await {
+            broadcast.error(format!("[{name}] {err}"));
+        }
+        broadcast.reload_lsp_server();
+        self.set_client_project_state(None);
+        broadcast.info(format!("[{name}] Generated"));
+        info!("Generated");
+    }
+
+    /// Regenerate compile commands, within the runtime loop so they're never written concurrently
+    #[instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name))]
+    async fn on_generate_compile_commands(&mut self, reply: oneshot::Sender<Result<()>>) {
+        let name = self.name.clone();
+
+        reply.send(Ok(())).ok();
+
+        info!("Generating compile commands");
+        let broadcast = self.broadcaster.clone();
+        broadcast.task_started(TaskKind::Compile, &name);
+        let compiled = self.project.update_compile_database(&broadcast).await;
+        broadcast.task_finished(TaskKind::Compile, &name, compiled.is_ok());
+        if let Err(err) = compiled {
+            return broadcast.error(format!("[{name}] {err}"));
+        }
+
+        broadcast.reload_lsp_server();
+        let count = compile::count_commands(self.project.root()).await;
+        broadcast.info(format!("[{name}] Generated {count} compile commands"));
+        info!("Generated {count} compile commands");
+    }
+
+    async fn on_stop(&mut self, req: StopRequest) -> Result<()> {
+        let key = &req.key;
+        let is_watched = self.watcher_subscribers.contains(key);
+        let session = self.sessions.find(key);
+
+        if !is_watched && session.is_none() {
+            let kind = "watched build or running app";
+            return Err(Error::Lookup(key.clone(), kind.into()));
+        }
+
+        info!("Stopping {key}");
+        if is_watched {
+            self.watcher_subscribers.remove(key).await;
+            self.set_client_project_state(None);
+        }
+
+        if let Some(session) = session {
+            self.sessions.stop(&session).await;
+            self.broadcaster
+                .send(None, Message::RunSessionEnded(session));
+        }
+
+        self.broadcaster
+            .info(format!("[{}] Stopped {key}", self.name));
+        Ok(())
+    }
+
+    /// Install pods if the project uses CocoaPods and Podfile.lock is outdated.
+    ///
+    /// Returns true if pods were installed.
+    async fn ensure_pods(&self) -> bool {
+        let (name, root) = (&self.name, self.project.root());
+        if !self.config.pods || !project::pods::is_used(root) || !project::pods::is_outdated(root) {
+            return false;
+        }
+
+        info!("Installing pods");
+        match project::pods::install(root, name, &self.broadcaster).await {
+            Ok(_) => true,
+            Err(err) => {
+                self.broadcaster.error(format!("[{name}] {err}"));
+                false
+            }
+        }
+    }
+
+    /// Get project details from loaded project state
+    fn project_details(&self) -> ProjectDetails {
+        let mut targets = self.buildables.clone();
+        for (name, info) in targets.iter_mut() {
+            info.watching = self.watcher_subscribers.watches(name);
+        }
+
+        let mut schemes = targets
+            .iter()
+            .filter(|(_, info)| info.kind == BuildableKind::Scheme)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        schemes.sort();
+
+        let mut configurations = targets
+            .values()
+            .flat_map(|info| info.configurations.clone())
+            .collect::<Vec<_>>();
+        configurations.sort();
+        configurations.dedup();
+
+        ProjectDetails {
+            name: self.name.clone(),
+            kind: self.project.kind(),
+            targets,
+            schemes,
+            configurations,
+            watchlist: self.watcher_subscribers.keys(),
+        }
+    }
+
+    /// Share project details, so they can be read without waiting on the runtime loop
+    fn share_details(&self) {
+        if let Ok(mut details) = self.details.write() {
+            *details = Some(self.project_details());
+        }
+    }
+
+    /// Get project state returned to registering clients
+    pub fn state(&self, sessions: Vec<RunSessionKey>) -> RegisterResponse {
+        RegisterResponse {
+            address: self.broadcaster.address().clone(),
+            project: self.project_details(),
+            sessions,
+            paused: self.paused,
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        if self.paused == paused {
+            return;
+        }
+        info!(
+            "{} acting on file changes",
+            if paused { "Paused" } else { "Resumed" }
+        );
+        self.paused = paused;
+        if paused {
+            self.paused_event = None;
+        }
+        self.broadcaster.set_state(None, State::WatchPaused(paused));
+    }
+
     fn set_client_project_state(&mut self, id: Option<u32>) {
+        self.buildables = self.project.buildables();
         let info = ProjectInfo {
             watchlist: self.watcher_subscribers.keys(),
-            targets: self.project.targets().clone(),
+            targets: self.buildables.clone(),
         };
+        if let Ok(mut platforms) = self.platforms.write() {
+            *platforms = self.project.targets_platform();
+        }
         self.broadcaster.set_state(id, State::ProjectInfo(info))
     }
 
+    /// List schemes of a workspace without shared ones in the background, as xcodebuild is slow
+    /// to list them, updating clients once listed
+    fn list_schemes(&self) {
+        let workspace = match self.project.unshared_workspace() {
+            Some(workspace) => workspace,
+            None => return,
+        };
+        let (name, sender) = (self.name.clone(), self.sender.clone());
+        tokio::spawn(async move {
+            match workspace::list_schemes(&workspace).await {
+                Ok(_) => sender.send(PRMessage::SchemesListed).ok(),
+                Err(err) => {
+                    tracing::debug!("[{name}] Failed to list workspace schemes: {err}");
+                    None
+                }
+            };
+        });
+    }
+
     fn set_client_runner_state(&mut self, id: u32) {
-        self.broadcaster
-            .set_state(Some(id), State::Runners(Runners::default()));
+        let broadcaster = self.broadcaster.clone();
+        tokio::spawn(async move {
+            let runners = Runners::list().await;
+            broadcaster.set_state(Some(id), State::Runners(runners));
+        });
+    }
+}
+
+fn broadcast_config(config: &ProjectConfig) -> BroadcastConfig {
+    BroadcastConfig {
+        process_timeout: config.process_timeout(),
+        ping_interval: config.ping_interval(),
+        log_max_size: config.log_file.then(|| config.log_file_max_bytes),
+        notify_level: config.notify_level.clone(),
+        formatter: if config.pretty_output {
+            Formatter::Pretty
+        } else {
+            Formatter::Raw
+        },
+        ..BroadcastConfig::default()
     }
 }
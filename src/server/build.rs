@@ -1,7 +1,11 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Display};
-use std::{path::PathBuf, sync::Arc};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::Receiver;
 use {super::*, crate::*};
 
 /// Request to build a particular project
@@ -10,6 +14,31 @@ pub struct BuildRequest {
     pub root: PathBuf,
     pub settings: BuildSettings,
     pub operation: Operation,
+    /// Other targets to build after settings target, one at a time, using the same settings
+    #[serde(default)]
+    pub targets: Vec<String>,
+    /// Build all project targets one at a time, instead of settings target
+    #[serde(default)]
+    pub all: bool,
+    /// Only report the build command, without running it
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Device to report the build command of dry runs for, or to build and run on with `run`
+    #[serde(default)]
+    pub device: Option<DeviceLookup>,
+    /// Launch the target on `device` after every successful build, replacing the running app
+    /// without building it again. Requests with more than one target are rejected
+    #[serde(default)]
+    pub run: bool,
+    /// Report time spent in each build phase once the build finishes (xcodebuild only)
+    #[serde(default)]
+    pub timing_summary: bool,
+    /// Build with verbose xcodebuild output
+    #[serde(default)]
+    pub verbose: bool,
+    /// Report diagnostics from a temporary result bundle, on top of build output (xcodebuild only)
+    #[serde(default)]
+    pub result_bundle: bool,
 }
 
 #[async_trait]
@@ -30,11 +59,142 @@ impl Display for BuildRequest {
     }
 }
 
+impl BuildRequest {
+    /// Extra xcodebuild flags requested, writing the result bundle to `result_bundle` if any
+    pub fn flags(&self, result_bundle: Option<&Path>) -> Vec<String> {
+        let mut flags = vec![];
+        if self.timing_summary {
+            flags.push("-showBuildTimingSummary".into());
+        }
+        if self.verbose {
+            flags.push("-verbose".into());
+        }
+        if let Some(result_bundle) = result_bundle {
+            flags.push("-resultBundlePath".into());
+            flags.push(result_bundle.display().to_string());
+        }
+        flags
+    }
+
+    /// Unique temporary path to write the build result bundle to, if requested.
+    ///
+    /// xcodebuild fails if the result bundle already exists, so every build gets its own.
+    pub fn result_bundle_path(&self) -> Option<PathBuf> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        self.result_bundle.then(|| {
+            let count = COUNT.fetch_add(1, Ordering::Relaxed);
+            let pid = std::process::id();
+            let name = format!("xbase-{}-{pid}-{count}.xcresult", self.settings.target);
+            std::env::temp_dir().join(name)
+        })
+    }
+
+    /// Split into a request per target to build, in requested order
+    pub fn split(self, project_targets: &HashMap<String, TargetInfo>) -> Vec<BuildRequest> {
+        let mut targets = if self.all {
+            let mut targets = project_targets.keys().cloned().collect::<Vec<_>>();
+            targets.sort();
+            targets
+        } else {
+            let mut targets = vec![self.settings.target.clone()];
+            targets.extend(self.targets.iter().cloned());
+            targets
+        };
+
+        let mut seen = HashSet::new();
+        targets.retain(|target| seen.insert(target.clone()));
+
+        if targets.len() <= 1 && !self.all {
+            return vec![self];
+        }
+
+        targets
+            .into_iter()
+            .map(|target| BuildRequest {
+                root: self.root.clone(),
+                settings: BuildSettings {
+                    target,
+                    ..self.settings.clone()
+                },
+                operation: self.operation.clone(),
+                targets: vec![],
+                all: false,
+                dry_run: self.dry_run,
+                device: self.device.clone(),
+                run: self.run,
+                timing_summary: self.timing_summary,
+                verbose: self.verbose,
+                result_bundle: self.result_bundle,
+            })
+            .collect()
+    }
+
+    /// Start building for a given device, returning a receiver of the build result, sent once
+    /// it's reported to clients
+    async fn start(
+        &self,
+        p: &mut ProjectImpl,
+        device: Option<&Destination>,
+        b: &Arc<Broadcast>,
+    ) -> Result<Receiver<Result<()>>> {
+        let target = self.settings.target.clone();
+        let settings = ProjectConfig::load(p.root())
+            .await
+            .settings_for(&self.settings);
+        b.task_started(TaskKind::Build, &target);
+
+        let result_bundle = self.result_bundle_path();
+        let flags = self.flags(result_bundle.as_deref());
+        let mut recv = match p.build(&settings, device, &flags, b) {
+            Ok((_, recv)) => recv,
+            Err(err) => {
+                b.task_finished(TaskKind::Build, &target, false);
+                return Err(err);
+            }
+        };
+
+        let (send, built) = tokio::sync::mpsc::channel(1);
+        let broadcast = b.clone();
+        tokio::spawn(async move {
+            let result = recv
+                .recv()
+                .await
+                .unwrap_or_else(|| Err(Error::Build(format!("{target} build failed"))));
+            if let Some(result_bundle) = result_bundle {
+                report_result_bundle_issues(&result_bundle, &target, &broadcast).await;
+            }
+            broadcast.task_finished(TaskKind::Build, &target, result.is_ok());
+            send.send(result).await.ok();
+        });
+
+        Ok(built)
+    }
+
+    /// Get run service launching the target on requested device after successful builds
+    pub async fn run_service(&self) -> Result<RunService> {
+        let name = self
+            .device
+            .as_ref()
+            .map_or("Bin".into(), |d| d.name.clone());
+        let key = format!("{}:Run:{name}:{}", self.root.display(), self.settings);
+        let device = Devices::from_lookup(self.device.clone()).await?;
+        let (root, settings) = (self.root.clone(), self.settings.clone());
+
+        Ok(RunService::new(
+            device,
+            root,
+            settings,
+            Default::default(),
+            key,
+            None,
+        ))
+    }
+}
+
 #[async_trait]
 impl Watchable for BuildRequest {
     async fn trigger(&self, p: &mut ProjectImpl, _: &Event, b: &Arc<Broadcast>) -> Result<()> {
-        p.build(&self.settings, None, b)?;
-        Ok(())
+        self.start(p, None, b).await.map(|_| ())
     }
 
     /// A function that controls whether a a Watchable should restart
@@ -49,4 +209,134 @@ impl Watchable for BuildRequest {
 
     /// Drop watchable for watching a given file system
     async fn discard(&self) {}
+
+    fn target(&self) -> &str {
+        &self.settings.target
+    }
+
+    fn kind(&self) -> TaskKind {
+        TaskKind::Build
+    }
+
+    fn relocate(&mut self, root: &Path) {
+        self.root = root.to_path_buf();
+    }
+}
+
+/// Watched build launching the built target with a run service after every successful build
+pub struct BuildAndRun {
+    pub build: BuildRequest,
+    pub run: RunService,
+}
+
+impl Display for BuildAndRun {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.build)
+    }
+}
+
+#[async_trait]
+impl Watchable for BuildAndRun {
+    async fn trigger(&self, p: &mut ProjectImpl, _: &Event, b: &Arc<Broadcast>) -> Result<()> {
+        let Self { build, run } = self;
+        let device = run.device.as_ref();
+        let built = build.start(p, device, b).await?;
+
+        // Product of the build above is launched as is, without building it again
+        let config = ProjectConfig::load(p.root()).await;
+        let settings = config.settings_for(&build.settings);
+        b.task_started(TaskKind::Run, build.target());
+        let runner = match p.get_runner(&settings, device, false, b).await {
+            Ok((runner, _, _)) => runner,
+            Err(err) => {
+                b.task_finished(TaskKind::Run, build.target(), false);
+                return Err(err);
+            }
+        };
+        let stop_on_failure = config.stop_run_on_build_failure;
+        run.launch_when_built(runner.into(), built, stop_on_failure, b);
+
+        Ok(())
+    }
+
+    async fn should_trigger(&self, event: &Event) -> bool {
+        self.build.should_trigger(event).await
+    }
+
+    async fn should_discard(&self, event: &Event) -> bool {
+        self.build.should_discard(event).await
+    }
+
+    /// Drop watchable, stopping the running app
+    async fn discard(&self) {
+        self.run.discard().await;
+    }
+
+    fn target(&self) -> &str {
+        self.build.target()
+    }
+
+    fn kind(&self) -> TaskKind {
+        TaskKind::Build
+    }
+
+    fn relocate(&mut self, root: &Path) {
+        self.build.relocate(root);
+        self.run.relocate(root);
+    }
+}
+
+#[test]
+fn test_split_build_request() {
+    let request = |targets: Vec<&str>, all: bool| BuildRequest {
+        root: "/tmp/Demo".into(),
+        settings: BuildSettings {
+            target: "App".into(),
+            configuration: None,
+            scheme: None,
+            sdk: None,
+            destination: None,
+            xcconfig: None,
+            overrides: vec![],
+        },
+        operation: Operation::Once,
+        targets: targets.into_iter().map(String::from).collect(),
+        all,
+        dry_run: false,
+        device: None,
+        run: false,
+        timing_summary: false,
+        verbose: false,
+        result_bundle: false,
+    };
+    let target_names = |requests: Vec<BuildRequest>| {
+        requests
+            .into_iter()
+            .map(|r| r.settings.target)
+            .collect::<Vec<_>>()
+    };
+    let project_targets = ["Kit", "App"]
+        .into_iter()
+        .map(|name| {
+            let info = TargetInfo {
+                platform: "iOS".into(),
+                configurations: vec![],
+                kind: BuildableKind::Target,
+                watching: false,
+                product_type: None,
+                bundle_id: None,
+            };
+            (name.to_string(), info)
+        })
+        .collect();
+
+    let single = request(vec![], false).split(&project_targets);
+    assert_eq!(single.len(), 1);
+    assert!(single[0].targets.is_empty());
+
+    let multi = request(vec!["Kit", "App", "Widget"], false).split(&project_targets);
+    assert_eq!(target_names(multi), vec!["App", "Kit", "Widget"]);
+
+    let all = request(vec![], true).split(&project_targets);
+    assert_eq!(target_names(all), vec!["App", "Kit"]);
 }
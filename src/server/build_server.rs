@@ -0,0 +1,36 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to rewrite buildServer.json of a particular project with current build server path
+/// and project languages
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct RegenerateBuildServerRequest {
+    pub root: PathBuf,
+}
+
+#[async_trait]
+impl RequestHandler<()> for RegenerateBuildServerRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        let broadcaster = runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+            .broadcaster();
+
+        compile::ensure_build_server_config(&self.root, true).await?;
+
+        if let Some(broadcaster) = broadcaster {
+            broadcaster.info(format!(
+                "[{}] Regenerated buildServer.json",
+                self.root.name().unwrap()
+            ));
+            broadcaster.reload_lsp_server();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+/// Request to get where build cache and compile database are and their size on disk, e.g. to see
+/// what a deep clean would remove
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct GetCacheInfoRequest {
+    pub root: PathBuf,
+}
+
+/// Cached file or directory
+#[derive(Debug, Serialize, TypeDef)]
+pub struct CachePath {
+    pub path: PathBuf,
+    pub exists: bool,
+    /// Total size in bytes, 0 if missing
+    pub size: u64,
+    /// Seconds since epoch of the last modification of any of its files, if any
+    pub modified_secs: Option<u64>,
+}
+
+/// Project cache paths returned by `GetCacheInfoRequest`
+#[derive(Debug, Serialize, TypeDef)]
+pub struct CacheInfo {
+    /// Build cache root, where targets are built to
+    pub build_cache: CachePath,
+    /// Compile database paths
+    pub compile_database: Vec<CachePath>,
+}
+
+impl CachePath {
+    async fn read(path: PathBuf) -> Self {
+        let lookup = path.clone();
+        let usage = tokio::task::spawn_blocking(move || crate::fs::disk_usage(&lookup))
+            .await
+            .ok()
+            .flatten();
+        let modified_secs = usage
+            .and_then(|(_, modified)| modified?.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| modified.as_secs());
+
+        Self {
+            path,
+            exists: usage.is_some(),
+            size: usage.map_or(0, |(size, _)| size),
+            modified_secs,
+        }
+    }
+}
+
+#[async_trait]
+impl RequestHandler<CacheInfo> for GetCacheInfoRequest {
+    async fn handle(self) -> Result<CacheInfo> {
+        tracing::trace!("{:#?}", self);
+        let build_cache = crate::fs::get_build_cache_dir(&self.root)?;
+        let build_cache = CachePath::read(PathBuf::from(build_cache)).await;
+
+        let mut compile_database = vec![];
+        let config = ProjectConfig::load(&self.root).await;
+        for path in config.compile_database_paths() {
+            compile_database.push(CachePath::read(self.root.join(path)).await);
+        }
+
+        Ok(CacheInfo {
+            build_cache,
+            compile_database,
+        })
+    }
+}
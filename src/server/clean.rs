@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+use {super::*, crate::*};
+
+/// Request to clean build products of a particular project
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct CleanRequest {
+    pub root: PathBuf,
+    pub settings: BuildSettings,
+    /// Whether to also remove build cache root and compile database
+    #[serde(default)]
+    pub deep: bool,
+}
+
+#[async_trait]
+impl RequestHandler<()> for CleanRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
+            .map(|r| r.send(PRMessage::Clean(self)))
+    }
+}
+
+impl Display for CleanRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:Clean:{}", self.root.display(), self.settings)
+    }
+}
@@ -0,0 +1,33 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to get the compile command of a single source file from the project compile database
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct GetCompileCommandRequest {
+    pub root: PathBuf,
+    /// Source file, relative to root unless absolute
+    pub file: PathBuf,
+}
+
+#[async_trait]
+impl RequestHandler<serde_json::Value> for GetCompileCommandRequest {
+    async fn handle(self) -> Result<serde_json::Value> {
+        tracing::trace!("{:#?}", self);
+        let file = self.root.join(&self.file);
+        let not_found = || Error::NoCompileCommand(file.clone());
+
+        let path = project::compile::database_path(&self.root).await;
+        let content = tokio::fs::read(path).await.map_err(|_| not_found())?;
+        let commands = serde_json::from_slice::<Vec<serde_json::Value>>(&content)?;
+
+        // Compile commands reference files by their resolved path
+        let resolved = std::fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+        project::compile::find_command(&commands, &file)
+            .or_else(|| project::compile::find_command(&commands, &resolved))
+            .cloned()
+            .ok_or_else(not_found)
+    }
+}
@@ -0,0 +1,29 @@
+use super::*;
+use crate::runtime::PRMessage;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to regenerate compile commands (`.compile`) of a particular project, without building
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct GenerateCompileCommandsRequest {
+    pub root: PathBuf,
+}
+
+#[async_trait]
+impl RequestHandler<()> for GenerateCompileCommandsRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        let (send, recv) = tokio::sync::oneshot::channel();
+
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+            .send(PRMessage::GenerateCompileCommands(send));
+
+        recv.await
+            .map_err(|_| Error::SendError("Project runtime closed".into()))?
+    }
+}
@@ -0,0 +1,44 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to list run destinations compatible with a particular project
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct GetDevicesRequest {
+    pub root: PathBuf,
+    /// Only list destinations that can run a given target, of any project target otherwise
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+#[async_trait]
+impl RequestHandler<Vec<DeviceInfo>> for GetDevicesRequest {
+    async fn handle(self) -> Result<Vec<DeviceInfo>> {
+        tracing::trace!("{:#?}", self);
+        let (platforms, broadcast) = runtimes()
+            .await
+            .get(&self.root)
+            .map(|r| (r.platforms(self.target.as_deref()), r.broadcaster()))
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?;
+
+        let devices = DeviceInfo::list()
+            .await?
+            .into_iter()
+            .filter(|device| platforms.contains(&device.platform))
+            .collect::<Vec<_>>();
+
+        // macOS targets run without a device
+        let needs_device = platforms.iter().any(|platform| platform != "macOS");
+        if let Some(broadcast) = broadcast.filter(|_| devices.is_empty() && needs_device) {
+            broadcast.warn(format!(
+                "No {} simulators or devices available, \
+                 install simulator runtimes from Xcode > Settings > Platforms",
+                platforms.join(", ")
+            ));
+        }
+
+        Ok(devices)
+    }
+}
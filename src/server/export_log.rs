@@ -0,0 +1,31 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to write recent project messages, e.g. a failed build log, to a plain text file
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct ExportLogRequest {
+    pub root: PathBuf,
+    /// File to write to, relative to root unless absolute
+    pub path: PathBuf,
+}
+
+#[async_trait]
+impl RequestHandler<()> for ExportLogRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        let broadcast = runtimes()
+            .await
+            .get(&self.root)
+            .and_then(|r| r.broadcaster())
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?;
+
+        let path = self.root.join(&self.path);
+        let count = broadcast.export_log(&path).await?;
+        broadcast.info(format!("Exported {count} messages to {}", path.display()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to list broadcast sockets of all registered projects, e.g. to tail them all at once
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct ListBroadcastsRequest {}
+
+/// Broadcast socket of a registered project
+#[derive(Debug, Serialize, TypeDef)]
+pub struct BroadcastInfo {
+    pub root: PathBuf,
+    pub name: String,
+    /// Socket address to connect to
+    pub address: PathBuf,
+    /// Number of registered clients
+    pub clients: u32,
+    /// Number of connected listeners
+    pub listeners: u32,
+    /// Whether a build or any other task is currently running
+    pub busy: bool,
+}
+
+#[async_trait]
+impl RequestHandler<Vec<BroadcastInfo>> for ListBroadcastsRequest {
+    async fn handle(self) -> Result<Vec<BroadcastInfo>> {
+        let runtimes = runtimes()
+            .await
+            .iter()
+            .map(|(root, runtime)| (root.clone(), runtime.clients(), runtime.broadcaster()))
+            .collect::<Vec<_>>();
+
+        let mut broadcasts = vec![];
+        for (root, clients, broadcast) in runtimes {
+            // Runtime is shutting down
+            let broadcast = match broadcast {
+                Some(broadcast) => broadcast,
+                None => continue,
+            };
+
+            broadcasts.push(BroadcastInfo {
+                name: root.as_path().name().unwrap_or_default(),
+                root,
+                address: broadcast.address().clone(),
+                clients,
+                listeners: broadcast.listeners_count().await as u32,
+                busy: broadcast.is_busy(),
+            });
+        }
+
+        Ok(broadcasts)
+    }
+}
@@ -1,9 +1,25 @@
 mod build;
+mod build_server;
+mod cache_info;
+mod clean;
+mod compile_command;
+mod compile_commands;
+mod devices;
 mod drop;
+mod export_log;
+mod generate;
+mod list_broadcasts;
+mod pause;
+mod project_info;
 mod register;
+mod relocate;
 mod request;
 mod response;
 mod run;
+mod status;
+mod stop;
+mod test;
+mod watch_target;
 
 use std::os::unix::prelude::AsRawFd;
 use tokio::net::unix::{ReadHalf, WriteHalf};
@@ -12,7 +28,12 @@ use tokio_util::codec::{BytesCodec, FramedRead, FramedWrite};
 use tracing::instrument;
 use typescript_type_def::TypeDef;
 
-pub use {build::*, drop::*, register::*, request::*, response::*, run::*};
+pub use {
+    build::*, build_server::*, cache_info::*, clean::*, compile_command::*, compile_commands::*,
+    devices::*, drop::*, export_log::*, generate::*, list_broadcasts::*, pause::*, project_info::*,
+    register::*, relocate::*, request::*, response::*, run::*, status::*, stop::*, test::*,
+    watch_target::*,
+};
 
 /// Stream of Requests to read Requests from
 struct RequestStream;
@@ -30,7 +51,7 @@ pub trait RequestHandler<T: serde::Serialize> {
 type F<T, F> = SymmetricallyFramed<F, T, SymmetricalJson<T>>;
 
 impl RequestStream {
-    fn new<'a>(r: ReadHalf<'a>) -> F<Request, FramedRead<ReadHalf<'a>, BytesCodec>> {
+    fn new<'a>(r: ReadHalf<'a>) -> F<RequestEnvelope, FramedRead<ReadHalf<'a>, BytesCodec>> {
         let transport = FramedRead::new(r, BytesCodec::default());
         F::new(transport, SymmetricalJson::default())
     }
@@ -58,12 +79,15 @@ pub async fn handle(mut stream: tokio::net::UnixStream) {
 
     loop {
         match reader.try_next().await {
-            Ok(Some(request)) => {
+            Ok(Some(RequestEnvelope {
+                id: request_id,
+                request,
+            })) => {
                 if let Request::Register(r) = &request {
                     id = r.id;
                     roots.push(r.root.clone())
                 };
-                let response = request.handle().await;
+                let response = request.handle().await.with_id(request_id);
                 let send_res = writer.send(response).await;
                 send_res.map_err(|err| error!("Send Error: {err}")).ok();
             }
@@ -0,0 +1,49 @@
+use super::*;
+use crate::runtime::PRMessage;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to pause acting on file changes of a particular project, e.g. during a rebase.
+///
+/// Files are still watched, but builds and runs aren't triggered until resumed.
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct PauseRequest {
+    pub root: PathBuf,
+}
+
+/// Request to resume acting on file changes of a paused project
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct ResumeRequest {
+    pub root: PathBuf,
+    /// Process the last change made while paused, e.g. to rebuild watched targets once.
+    ///
+    /// Changes made while paused are ignored otherwise.
+    #[serde(default)]
+    pub catch_up: bool,
+}
+
+#[async_trait]
+impl RequestHandler<()> for PauseRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
+            .map(|r| r.send(PRMessage::Pause))
+    }
+}
+
+#[async_trait]
+impl RequestHandler<()> for ResumeRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
+            .map(|r| r.send(PRMessage::Resume(self.catch_up)))
+    }
+}
@@ -0,0 +1,25 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to get targets, schemes and configurations of a particular project
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct GetProjectInfoRequest {
+    pub root: PathBuf,
+}
+
+#[async_trait]
+impl RequestHandler<ProjectDetails> for GetProjectInfoRequest {
+    async fn handle(self) -> Result<ProjectDetails> {
+        tracing::trace!("{:#?}", self);
+
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+            .details()
+            .ok_or_else(|| Error::SendError("Project runtime closed".into()))
+    }
+}
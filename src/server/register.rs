@@ -1,9 +1,10 @@
 use super::*;
-use crate::runtime::ProjectRuntime;
+use crate::runtime::{PRMessage, ProjectRuntime};
 use crate::*;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::{path::PathBuf, time::Duration};
+use tokio::sync::oneshot;
 
 /// Register a project root
 #[derive(Debug, Serialize, Deserialize, TypeDef)]
@@ -12,27 +13,47 @@ pub struct RegisterRequest {
     pub root: PathBuf,
 }
 
+/// Project state returned on registration, to restore client state on reconnect
+#[derive(Debug, Serialize, TypeDef)]
+pub struct RegisterResponse {
+    /// Address of the broadcast socket to read project messages from
+    pub address: PathBuf,
+    /// Project targets and schemes, flagged if watched, and watched requests keys
+    pub project: ProjectDetails,
+    /// Active run sessions
+    pub sessions: Vec<RunSessionKey>,
+    /// Whether acting on file changes is paused
+    pub paused: bool,
+}
+
 #[async_trait]
-impl RequestHandler<PathBuf> for RegisterRequest {
-    async fn handle(self) -> Result<PathBuf> {
+impl RequestHandler<RegisterResponse> for RegisterRequest {
+    /// Register client, or return current project state if the client is already registered
+    async fn handle(self) -> Result<RegisterResponse> {
         let RegisterRequest { id, root } = self;
+        let root = fs::find_project_root(&root)?;
         let mut runtimes = runtimes().await;
         tracing::trace!("{:#?}", runtimes);
 
         if let Some(runtime) = runtimes.get_mut(&root) {
-            if runtime.contains(&id) {
-                return Err(Error::Unexpected(
-                    "Trying to adding a connected client!".into(),
-                ));
-            }
+            let (send, recv) = oneshot::channel();
+            runtime.send(PRMessage::GetState(send));
 
-            let address = runtime.broadcaster_adderss().clone();
-            tokio::spawn(async move {
-                tokio::time::sleep(Duration::from_millis(100)).await;
-                runtimes.get_mut(&root).unwrap().connect(id);
-            });
+            if !runtime.contains(&id) {
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    if let Some(runtime) = runtimes.get_mut(&root) {
+                        runtime.connect(id);
+                    }
+                });
+            } else {
+                tracing::debug!("[{id}] Already registered");
+                drop(runtimes);
+            }
 
-            return Ok(address);
+            return recv
+                .await
+                .map_err(|_| Error::SendError("Project runtime closed".into()));
         }
 
         let (rloop, mut runtime) = match ProjectRuntime::new(root.clone()).await {
@@ -43,7 +64,7 @@ impl RequestHandler<PathBuf> for RegisterRequest {
             }
         };
 
-        let address = runtime.broadcaster_adderss().clone();
+        let state = rloop.state(vec![]);
 
         tokio::spawn(async move {
             tokio::time::sleep(Duration::from_millis(100)).await;
@@ -53,6 +74,6 @@ impl RequestHandler<PathBuf> for RegisterRequest {
             rloop.start(id).await;
         });
 
-        Ok(address)
+        Ok(state)
     }
 }
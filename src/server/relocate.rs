@@ -0,0 +1,65 @@
+use super::*;
+use crate::runtime::PRMessage;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::sync::oneshot;
+
+/// Request to move a registered project to a new root, e.g. after renaming its directory,
+/// keeping connected clients, watched requests and pause state.
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct RelocateRequest {
+    /// Root the project was registered with
+    pub old_root: PathBuf,
+    /// Root the project is now at
+    pub new_root: PathBuf,
+}
+
+#[async_trait]
+impl RequestHandler<RegisterResponse> for RelocateRequest {
+    /// Relocate project runtime, returning project state with the new broadcast address
+    async fn handle(self) -> Result<RegisterResponse> {
+        tracing::trace!("{:#?}", self);
+        let RelocateRequest { old_root, new_root } = self;
+        let relocate_err = |reason: &str| Error::Relocate(new_root.clone(), reason.into());
+        let new_root = match tokio::fs::canonicalize(&new_root).await {
+            Ok(root) if crate::fs::find_project_root(&root).ok().as_ref() == Some(&root) => root,
+            Ok(_) => return Err(relocate_err("no project found at its root")),
+            Err(err) => return Err(relocate_err(&err.to_string())),
+        };
+
+        let mut runtime = {
+            let mut runtimes = runtimes().await;
+            if runtimes.contains_key(&new_root) {
+                let reason = "a project is already registered there";
+                return Err(Error::Relocate(new_root, reason.into()));
+            }
+            runtimes
+                .remove(&old_root)
+                .ok_or_else(|| Error::UnknownProject(old_root.clone()))?
+        };
+
+        let (send, recv) = oneshot::channel();
+        runtime.send(PRMessage::Relocate(new_root.clone(), send));
+        let relocated = recv
+            .await
+            .map_err(|_| Error::SendError("Project runtime closed".into()))
+            .and_then(|relocated| relocated);
+
+        let mut runtimes = runtimes().await;
+        match relocated {
+            Ok((broadcaster, state)) => {
+                runtime.relocate(&new_root, &broadcaster);
+                runtimes.insert(new_root, runtime);
+                Ok(state)
+            }
+            Err(err) => {
+                if !runtime.is_closed() {
+                    runtimes.insert(old_root, runtime);
+                }
+                Err(err)
+            }
+        }
+    }
+}
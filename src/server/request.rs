@@ -1,5 +1,6 @@
 use super::*;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use tap::Pipe;
 
 /// Requests clinets can make
@@ -14,15 +15,116 @@ pub enum Request {
     Run(RunRequest),
     /// Drop projects at a given roots
     Drop(DropRequest),
+    /// Clean Project build products
+    Clean(CleanRequest),
+    /// Get available simulators and connected devices for a project
+    GetDevices(GetDevicesRequest),
+    /// Stop a watched build or a running app
+    Stop(StopRequest),
+    /// Run Project tests
+    Test(TestRequest),
+    /// Rewrite project buildServer.json
+    RegenerateBuildServer(RegenerateBuildServerRequest),
+    /// Get project targets, schemes and configurations
+    GetProjectInfo(GetProjectInfoRequest),
+    /// Regenerate project, e.g. after project.yml changed while the daemon was down
+    Generate(GenerateRequest),
+    /// Regenerate compile commands without building
+    GenerateCompileCommands(GenerateCompileCommandsRequest),
+    /// Get daemon and registered projects status
+    Status(StatusRequest),
+    /// Pause acting on project file changes
+    Pause(PauseRequest),
+    /// Resume acting on project file changes
+    Resume(ResumeRequest),
+    /// Start or stop watching a project target
+    WatchTarget(WatchTargetRequest),
+    /// Write recent project messages to a file
+    ExportLog(ExportLogRequest),
+    /// List broadcast sockets of all registered projects
+    ListBroadcasts(ListBroadcastsRequest),
+    /// Get compile command of a source file
+    GetCompileCommand(GetCompileCommandRequest),
+    /// Get build cache and compile database paths and sizes
+    GetCacheInfo(GetCacheInfoRequest),
+    /// Move a registered project to a new root, e.g. after renaming its directory
+    Relocate(RelocateRequest),
+}
+
+/// Request read from clients, with an optional id echoed back in its response.
+///
+/// Ids let clients match responses to requests pipelined over a single connection.
+#[derive(Debug, Deserialize)]
+pub struct RequestEnvelope {
+    #[serde(default)]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub request: Request,
 }
 
 impl Request {
-    pub async fn handle(self) -> Response {
+    pub async fn handle(mut self) -> Response {
+        crate::reap_dead_clients().await;
+        self.resolve_roots();
         match self {
             Request::Register(req) => req.handle().await.pipe(Response::new),
             Request::Build(req) => req.handle().await.pipe(Response::new),
             Request::Run(req) => req.handle().await.pipe(Response::new),
             Request::Drop(req) => req.handle().await.pipe(Response::new),
+            Request::Clean(req) => req.handle().await.pipe(Response::new),
+            Request::GetDevices(req) => req.handle().await.pipe(Response::new),
+            Request::Stop(req) => req.handle().await.pipe(Response::new),
+            Request::Test(req) => req.handle().await.pipe(Response::new),
+            Request::RegenerateBuildServer(req) => req.handle().await.pipe(Response::new),
+            Request::GetProjectInfo(req) => req.handle().await.pipe(Response::new),
+            Request::Generate(req) => req.handle().await.pipe(Response::new),
+            Request::GenerateCompileCommands(req) => req.handle().await.pipe(Response::new),
+            Request::Status(req) => req.handle().await.pipe(Response::new),
+            Request::Pause(req) => req.handle().await.pipe(Response::new),
+            Request::Resume(req) => req.handle().await.pipe(Response::new),
+            Request::WatchTarget(req) => req.handle().await.pipe(Response::new),
+            Request::ExportLog(req) => req.handle().await.pipe(Response::new),
+            Request::ListBroadcasts(req) => req.handle().await.pipe(Response::new),
+            Request::GetCompileCommand(req) => req.handle().await.pipe(Response::new),
+            Request::GetCacheInfo(req) => req.handle().await.pipe(Response::new),
+            Request::Relocate(req) => req.handle().await.pipe(Response::new),
+        }
+    }
+
+    /// Resolve roots of requests made from a project subdirectory to the project root.
+    ///
+    /// Registration resolves its root itself, failing if no project is found.
+    fn resolve_roots(&mut self) {
+        let resolve = |root: &mut PathBuf| {
+            if let Ok(resolved) = crate::fs::find_project_root(&*root) {
+                *root = resolved;
+            }
+        };
+        match self {
+            Request::Register(_) | Request::Status(_) | Request::ListBroadcasts(_) => {}
+            Request::Build(BuildRequest { root, .. })
+            | Request::Run(RunRequest { root, .. })
+            | Request::Clean(CleanRequest { root, .. })
+            | Request::GetDevices(GetDevicesRequest { root, .. })
+            | Request::Stop(StopRequest { root, .. })
+            | Request::Test(TestRequest { root, .. })
+            | Request::RegenerateBuildServer(RegenerateBuildServerRequest { root, .. })
+            | Request::GetProjectInfo(GetProjectInfoRequest { root, .. })
+            | Request::Pause(PauseRequest { root })
+            | Request::Resume(ResumeRequest { root, .. })
+            | Request::WatchTarget(WatchTargetRequest { root, .. })
+            | Request::ExportLog(ExportLogRequest { root, .. })
+            | Request::Generate(GenerateRequest { root })
+            | Request::GenerateCompileCommands(GenerateCompileCommandsRequest { root })
+            | Request::GetCacheInfo(GetCacheInfoRequest { root }) => resolve(root),
+            Request::Drop(DropRequest { roots, .. }) => roots.iter_mut().for_each(resolve),
+            // New root is validated on relocation, it must be the project root itself
+            Request::Relocate(RelocateRequest { old_root, .. }) => resolve(old_root),
+            // File is relative to the root it was requested with
+            Request::GetCompileCommand(GetCompileCommandRequest { root, file }) => {
+                *file = root.join(&*file);
+                resolve(root)
+            }
         }
     }
 }
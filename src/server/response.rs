@@ -1,11 +1,17 @@
-use crate::{types::Result, ServerError};
-use serde::Serialize;
+use crate::{server::RequestEnvelope, types::Result, ServerError};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use typescript_type_def::TypeDef;
 
 /// Server Response
-#[derive(Default, Debug, Serialize, TypeDef)]
+#[derive(Default, Debug, Serialize, Deserialize, TypeDef)]
 pub struct Response {
+    /// Id of the request responded to, if the request had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    /// Response envelope version, set along with `id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    v: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -21,4 +27,40 @@ impl Response {
         };
         response
     }
+
+    /// Response envelope version
+    pub const VERSION: u32 = 1;
+
+    /// Echo request id, to match responses to pipelined requests
+    pub fn with_id(mut self, id: Option<u64>) -> Response {
+        if id.is_some() {
+            self.id = id;
+            self.v = Some(Self::VERSION);
+        }
+        self
+    }
+}
+
+#[test]
+fn test_response_envelope() {
+    let request: RequestEnvelope =
+        serde_json::from_str(r#"{"id": 7, "method": "drop", "args": {"roots": ["/tmp/Demo"]}}"#)
+            .unwrap();
+    assert!(matches!(request.request, crate::server::Request::Drop(_)));
+
+    let response = Response::new(Ok("ok")).with_id(request.id);
+    let json = serde_json::to_string(&response).unwrap();
+    let response: Response = serde_json::from_str(&json).unwrap();
+    assert_eq!(response.id, Some(7));
+    assert_eq!(response.v, Some(Response::VERSION));
+    assert_eq!(response.data, Some("ok".into()));
+
+    // Requests without id get responses without envelope fields
+    let request: RequestEnvelope =
+        serde_json::from_str(r#"{"method": "drop", "args": {"roots": []}}"#).unwrap();
+    let response = Response::new(Ok(())).with_id(request.id);
+    assert_eq!(
+        serde_json::to_string(&response).unwrap(),
+        r#"{"data":null}"#
+    );
 }
@@ -3,6 +3,7 @@ use crate::runtime::PRMessage;
 use crate::{runner::*, *};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::{self, Display};
 use std::path::PathBuf;
 
@@ -13,7 +14,30 @@ pub struct RunRequest {
     pub settings: BuildSettings,
     #[serde(default)]
     pub device: Option<DeviceLookup>,
+    /// Watch rebuilds and relaunches on file changes, replacing the running app once rebuilt
     pub operation: Operation,
+    /// Environment variables to launch the app with
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Arguments to launch the app with
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Id of the client making the request, if its run sessions should stop when it disconnects
+    #[serde(default)]
+    pub id: Option<u32>,
+    /// Reject the request if the target is already running on the device, instead of restarting
+    #[serde(default)]
+    pub exclusive: bool,
+    /// Bring Simulator.app to front after launching, ignored for physical devices
+    #[serde(default)]
+    pub focus_simulator: bool,
+    /// Times to relaunch the app if it exits with non-zero code without being stopped, e.g. on
+    /// flaky simulator startups. Capped to 5, 0 disables relaunching.
+    #[serde(default)]
+    pub relaunch_on_crash: u32,
+    /// Build before launching even if nothing changed since the last successful build
+    #[serde(default)]
+    pub force_build: bool,
 }
 
 #[async_trait]
@@ -41,11 +65,28 @@ impl Display for RunRequest {
 }
 
 impl RunRequest {
-    pub fn into_service(self) -> RunService {
+    pub async fn into_service(self) -> Result<RunService> {
         let key = self.to_string();
-        let Self { settings, root, .. } = self;
-        let device = Devices::from_lookup(self.device);
+        let Self {
+            settings,
+            root,
+            env,
+            args,
+            id,
+            focus_simulator,
+            relaunch_on_crash,
+            force_build,
+            ..
+        } = self;
+        let device = Devices::from_lookup(self.device).await?;
+        let options = LaunchOptions {
+            env,
+            args,
+            focus_simulator,
+            relaunch_on_crash,
+            force_build,
+        };
 
-        RunService::new(device, root, settings, key)
+        Ok(RunService::new(device, root, settings, options, key, id))
     }
 }
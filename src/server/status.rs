@@ -0,0 +1,115 @@
+use super::*;
+use crate::runtime::PRMessage;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Request to get daemon and registered projects status, e.g. for health checks
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct StatusRequest {}
+
+/// Daemon status.
+///
+/// Fields are only ever added, removals or changes bump [`StatusResponse::VERSION`].
+#[derive(Debug, Serialize, TypeDef)]
+pub struct StatusResponse {
+    /// Response layout version
+    pub version: u32,
+    /// Daemon version
+    pub daemon_version: String,
+    /// Seconds since the daemon started
+    pub uptime_secs: u64,
+    /// Registered projects
+    pub projects: Vec<ProjectStatus>,
+}
+
+/// Status of a registered project
+#[derive(Debug, Serialize, TypeDef)]
+pub struct ProjectStatus {
+    pub root: PathBuf,
+    pub name: String,
+    /// Number of registered clients
+    pub clients: u32,
+    /// Whether the project runtime replied in time, false while busy (e.g. generating)
+    pub responsive: bool,
+    /// Watched build and run requests keys, empty if not responsive
+    pub watchlist: Vec<String>,
+    /// Active run sessions, empty if not responsive
+    pub sessions: Vec<RunSessionKey>,
+    /// Whether acting on file changes is paused, false if not responsive
+    pub paused: bool,
+    /// Broadcast socket status
+    pub broadcast: BroadcastStatus,
+}
+
+/// Status of a project broadcast socket
+#[derive(Debug, Serialize, TypeDef)]
+pub struct BroadcastStatus {
+    pub address: PathBuf,
+    /// Whether the broadcast server is running and its socket exists
+    pub healthy: bool,
+    /// Number of connected listeners
+    pub listeners: u32,
+}
+
+impl StatusResponse {
+    pub const VERSION: u32 = 1;
+}
+
+/// Max time to wait for each project runtime to reply
+const RUNTIME_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[async_trait]
+impl RequestHandler<StatusResponse> for StatusRequest {
+    async fn handle(self) -> Result<StatusResponse> {
+        let mut pending = vec![];
+
+        for (root, runtime) in runtimes().await.iter() {
+            let (send, recv) = tokio::sync::oneshot::channel();
+            runtime.send(PRMessage::GetState(send));
+            let broadcast = runtime.broadcaster();
+            pending.push((root.clone(), runtime.clients(), broadcast, recv));
+        }
+
+        let mut projects = vec![];
+        for (root, clients, broadcast, recv) in pending {
+            let state = tokio::time::timeout(RUNTIME_TIMEOUT, recv).await;
+            let state = state.ok().and_then(|state| state.ok());
+            let broadcast = match broadcast {
+                Some(broadcast) => BroadcastStatus {
+                    address: broadcast.address().clone(),
+                    healthy: broadcast.is_healthy(),
+                    listeners: broadcast.listeners_count().await as u32,
+                },
+                None => BroadcastStatus {
+                    address: Default::default(),
+                    healthy: false,
+                    listeners: 0,
+                },
+            };
+
+            projects.push(ProjectStatus {
+                name: root.as_path().name().unwrap_or_default(),
+                root,
+                clients,
+                responsive: state.is_some(),
+                watchlist: state
+                    .as_ref()
+                    .map(|state| state.project.watchlist.clone())
+                    .unwrap_or_default(),
+                paused: state.as_ref().map_or(false, |state| state.paused),
+                sessions: state.map(|state| state.sessions).unwrap_or_default(),
+                broadcast,
+            });
+        }
+
+        Ok(StatusResponse {
+            version: StatusResponse::VERSION,
+            daemon_version: env!("CARGO_PKG_VERSION").into(),
+            uptime_secs: crate::uptime().as_secs(),
+            projects,
+        })
+    }
+}
@@ -0,0 +1,31 @@
+use super::*;
+use crate::runtime::PRMessage;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to stop a watched build or a running app of a particular project
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct StopRequest {
+    pub root: PathBuf,
+    /// Key of the build or run request, i.e. `BuildRequest`/`RunRequest` formatted as string
+    pub key: String,
+}
+
+#[async_trait]
+impl RequestHandler<()> for StopRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        let (send, recv) = tokio::sync::oneshot::channel();
+
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+            .send(PRMessage::Stop(self, send));
+
+        recv.await
+            .map_err(|_| Error::SendError("Project runtime closed".into()))?
+    }
+}
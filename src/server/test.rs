@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use {super::*, crate::*};
+
+/// Request to run tests of a particular project
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct TestRequest {
+    pub root: PathBuf,
+    pub settings: BuildSettings,
+    #[serde(default)]
+    pub device: Option<DeviceLookup>,
+    /// Test identifiers to run, e.g. `AppTests/AppTests/testFoo`, all tests if empty
+    #[serde(default)]
+    pub only_testing: Vec<String>,
+    /// Report line coverage of tested targets once tests finish (xcodebuild only), slows tests
+    #[serde(default)]
+    pub coverage: bool,
+    /// Include coverage of each source file, if coverage is reported
+    #[serde(default)]
+    pub coverage_files: bool,
+}
+
+#[async_trait]
+impl RequestHandler<()> for TestRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
+            .map(|r| r.send(PRMessage::Test(self)))
+    }
+}
+
+impl TestRequest {
+    /// Extra xcodebuild flags requested, writing the result bundle coverage is read from to
+    /// `result_bundle`
+    pub fn flags(&self, result_bundle: &Path) -> Vec<String> {
+        if !self.coverage {
+            return vec![];
+        }
+        vec![
+            "-enableCodeCoverage".into(),
+            "YES".into(),
+            "-resultBundlePath".into(),
+            result_bundle.display().to_string(),
+        ]
+    }
+}
+
+impl Display for TestRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:Test:{}", self.root.display(), self.settings)
+    }
+}
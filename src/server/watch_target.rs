@@ -0,0 +1,54 @@
+use super::*;
+use crate::runtime::PRMessage;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to start or stop watching a particular target, without building other targets
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct WatchTargetRequest {
+    pub root: PathBuf,
+    pub target: String,
+    /// Watch target if true, stop watching it otherwise
+    pub enable: bool,
+}
+
+#[async_trait]
+impl RequestHandler<()> for WatchTargetRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
+            .map(|r| r.send(PRMessage::WatchTarget(self)))
+    }
+}
+
+impl WatchTargetRequest {
+    /// Watched build request of target, built with project config settings
+    pub fn build_request(&self) -> BuildRequest {
+        BuildRequest {
+            root: self.root.clone(),
+            settings: BuildSettings {
+                target: self.target.clone(),
+                configuration: None,
+                scheme: None,
+                sdk: None,
+                destination: None,
+                xcconfig: None,
+                overrides: vec![],
+            },
+            operation: Operation::Watch,
+            targets: vec![],
+            all: false,
+            dry_run: false,
+            device: None,
+            run: false,
+            timing_summary: false,
+            verbose: false,
+            result_bundle: false,
+        }
+    }
+}
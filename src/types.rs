@@ -1,13 +1,14 @@
 use crate::error::*;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::{collections::HashMap, fmt::Display};
 use strum::{Display as EnumDisplay, EnumString};
 use typescript_type_def::TypeDef;
-use xcodeproj::pbxproj::PBXTargetInfo;
+use xcodeproj::pbxproj::{PBXProductType, PBXTargetInfo};
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Debug, Serialize, TypeDef)]
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
 pub struct ProjectInfo {
     /// Get watched configurations for given root
     pub watchlist: Vec<String>,
@@ -28,10 +29,94 @@ pub enum Operation {
 pub struct BuildSettings {
     /// Target to build
     pub target: String,
-    /// Configuration to build with, default Debug
-    pub configuration: String,
+    /// Configuration to build with, xcodebuild default if unset
+    #[serde(default)]
+    pub configuration: Option<String>,
     /// Scheme to build with
     pub scheme: Option<String>,
+    /// SDK to build with, e.g. `iphoneos`, derived from target platform if unset
+    #[serde(default)]
+    pub sdk: Option<String>,
+    /// Destination to build for, e.g. `platform=iOS Simulator,name=iPhone 14`
+    #[serde(default)]
+    pub destination: Option<String>,
+    /// xcconfig file to override build settings with, relative to project root
+    #[serde(default)]
+    pub xcconfig: Option<PathBuf>,
+    /// Build settings to override, passed as `KEY=VALUE`, e.g. `("ONLY_ACTIVE_ARCH", "YES")`
+    #[serde(default)]
+    pub overrides: Vec<(String, String)>,
+}
+
+/// Apple platform with its sdks and simulator runtime
+#[derive(Debug)]
+pub struct Platform {
+    /// Platform name as in target info, e.g. `iOS`
+    pub name: &'static str,
+    /// Prefix of simulator runtime identifiers, identified as `<prefix><version>`, e.g.
+    /// `com.apple.CoreSimulator.SimRuntime.iOS-17-0`
+    pub runtime: Option<&'static str>,
+    /// SDK to build for devices with, e.g. `iphoneos`
+    pub sdk: &'static str,
+    /// SDK to build for simulators with, e.g. `iphonesimulator`
+    pub simulator_sdk: Option<&'static str>,
+}
+
+/// Platforms targets can be built for, visionOS runtimes are named `xrOS`
+pub static PLATFORMS: [Platform; 5] = [
+    Platform {
+        name: "iOS",
+        runtime: Some("com.apple.CoreSimulator.SimRuntime.iOS-"),
+        sdk: "iphoneos",
+        simulator_sdk: Some("iphonesimulator"),
+    },
+    Platform {
+        name: "watchOS",
+        runtime: Some("com.apple.CoreSimulator.SimRuntime.watchOS-"),
+        sdk: "watchos",
+        simulator_sdk: Some("watchsimulator"),
+    },
+    Platform {
+        name: "tvOS",
+        runtime: Some("com.apple.CoreSimulator.SimRuntime.tvOS-"),
+        sdk: "appletvos",
+        simulator_sdk: Some("appletvsimulator"),
+    },
+    Platform {
+        name: "visionOS",
+        runtime: Some("com.apple.CoreSimulator.SimRuntime.xrOS-"),
+        sdk: "xros",
+        simulator_sdk: Some("xrsimulator"),
+    },
+    Platform {
+        name: "macOS",
+        runtime: None,
+        sdk: "macosx",
+        simulator_sdk: None,
+    },
+];
+
+impl Platform {
+    /// Get platform by name, e.g. `iOS`
+    pub fn named(name: &str) -> Option<&'static Platform> {
+        PLATFORMS.iter().find(|platform| platform.name == name)
+    }
+
+    /// Get platform of a simulator runtime identifier
+    pub fn of_runtime(runtime: &str) -> Option<&'static Platform> {
+        PLATFORMS.iter().find(|platform| {
+            platform
+                .runtime
+                .map_or(false, |prefix| runtime.starts_with(prefix))
+        })
+    }
+
+    /// Platforms with simulators
+    pub fn simulated() -> impl Iterator<Item = &'static Platform> {
+        PLATFORMS
+            .iter()
+            .filter(|platform| platform.runtime.is_some())
+    }
 }
 
 /// Target specfic information
@@ -39,6 +124,71 @@ pub struct BuildSettings {
 pub struct TargetInfo {
     pub platform: String,
     pub configurations: Vec<String>,
+    /// Whether it's a target or a workspace scheme
+    #[serde(default)]
+    pub kind: BuildableKind,
+    /// Whether the target is built or ran on file changes
+    #[serde(default)]
+    pub watching: bool,
+    /// Kind of product the target builds, unset if unknown, e.g. for schemes
+    #[serde(default)]
+    pub product_type: Option<ProductType>,
+    /// Bundle identifier of the target product, unset if not set literally in the project
+    #[serde(default)]
+    pub bundle_id: Option<String>,
+}
+
+/// Kind of a buildable unit
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub enum BuildableKind {
+    Target,
+    Scheme,
+}
+
+impl Default for BuildableKind {
+    fn default() -> Self {
+        Self::Target
+    }
+}
+
+/// Kind of product a target builds
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub enum ProductType {
+    Application,
+    AppExtension,
+    Framework,
+    StaticLibrary,
+    DynamicLibrary,
+    Bundle,
+    UnitTest,
+    UiTest,
+    /// Command line tool
+    Tool,
+    Other,
+}
+
+impl ProductType {
+    /// Whether the product can be ran on its own
+    pub fn is_runnable(&self) -> bool {
+        matches!(self, Self::Application | Self::Tool | Self::Other)
+    }
+}
+
+impl From<&PBXProductType> for ProductType {
+    fn from(product_type: &PBXProductType) -> Self {
+        match product_type {
+            PBXProductType::Application | PBXProductType::Watch2App => Self::Application,
+            PBXProductType::AppExtension | PBXProductType::Watch2Extension => Self::AppExtension,
+            PBXProductType::Framework | PBXProductType::StaticFramework => Self::Framework,
+            PBXProductType::StaticLibrary => Self::StaticLibrary,
+            PBXProductType::DynamicLibrary => Self::DynamicLibrary,
+            PBXProductType::Bundle => Self::Bundle,
+            PBXProductType::UnitTestBundle => Self::UnitTest,
+            PBXProductType::UiTestBundle => Self::UiTest,
+            PBXProductType::CommandLineTool => Self::Tool,
+            _ => Self::Other,
+        }
+    }
 }
 
 impl From<PBXTargetInfo> for TargetInfo {
@@ -46,20 +196,67 @@ impl From<PBXTargetInfo> for TargetInfo {
         Self {
             platform: info.platform.to_string(),
             configurations: info.configurations,
+            kind: BuildableKind::Target,
+            watching: false,
+            product_type: None,
+            bundle_id: None,
         }
     }
 }
 
-/// Device Lookup information to run built project with
+/// Tool a project is generated or described with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub enum ProjectKind {
+    /// Plain checked-in xcodeproj
+    Xcodeproj,
+    XcodeGen,
+    Tuist,
+    Swift,
+    Bazel,
+}
+
+/// Project information returned by `GetProjectInfoRequest`
+#[derive(Clone, Debug, Serialize, TypeDef)]
+pub struct ProjectDetails {
+    /// Project name
+    pub name: String,
+    /// Tool the project is generated or described with
+    pub kind: ProjectKind,
+    /// Targets and workspace schemes, flagged if watched
+    pub targets: HashMap<String, TargetInfo>,
+    /// Workspace schemes
+    pub schemes: Vec<String>,
+    /// Build configurations available across targets
+    pub configurations: Vec<String>,
+    /// Watched build and run requests keys
+    pub watchlist: Vec<String>,
+}
+
+/// Device Lookup information to run built project with, either id (udid) or name can be used.
 #[derive(Clone, Default, Debug, Serialize, Deserialize, TypeDef)]
+#[serde(default)]
 pub struct DeviceLookup {
     pub name: String,
     pub id: String,
+    /// Whether the device is a connected physical device rather than a simulator
+    pub physical: bool,
 }
 
 impl DeviceLookup {
     pub fn new(name: String, id: String) -> Self {
-        Self { name, id }
+        Self {
+            name,
+            id,
+            physical: false,
+        }
+    }
+
+    pub fn physical(name: String, id: String) -> Self {
+        Self {
+            name,
+            id,
+            physical: true,
+        }
     }
 }
 
@@ -71,22 +268,130 @@ impl Default for Operation {
 
 impl Display for BuildSettings {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "-configuration {}", self.configuration)?;
-
-        if let Some(ref scheme) = self.scheme {
-            write!(f, " -scheme {scheme}")?;
-        }
-        write!(f, " -target {}", self.target)?;
-        Ok(())
+        let args = self.to_args().into_iter().chain(self.override_args());
+        write!(f, "{}", args.collect::<Vec<_>>().join(" "))
     }
 }
 
 impl BuildSettings {
+    /// Configurations accepted for targets with unknown configurations
+    pub const DEFAULT_CONFIGURATIONS: [&'static str; 2] = ["Debug", "Release"];
+
     pub fn to_args(&self) -> Vec<String> {
-        self.to_string()
-            .split_whitespace()
-            .map(ToString::to_string)
-            .collect::<Vec<String>>()
+        let mut args = vec![];
+
+        if let Some(ref configuration) = self.configuration {
+            args.extend(["-configuration".into(), configuration.clone()]);
+        }
+
+        if let Some(ref scheme) = self.scheme {
+            args.extend(["-scheme".into(), scheme.clone()]);
+        }
+
+        args.extend(["-target".into(), self.target.clone()]);
+
+        if let Some(ref sdk) = self.sdk {
+            args.extend(["-sdk".into(), sdk.clone()]);
+        }
+
+        if let Some(ref destination) = self.destination {
+            args.extend(["-destination".into(), destination.clone()]);
+        }
+
+        // Explicit -configuration/-sdk still take precedence over values set in xcconfig
+        if let Some(ref xcconfig) = self.xcconfig {
+            args.extend(["-xcconfig".into(), xcconfig.to_string_lossy().to_string()]);
+        }
+
+        args
+    }
+
+    /// Get build setting overrides as `KEY=VALUE` arguments, after any other xcodebuild argument
+    /// so that they take precedence
+    pub fn override_args(&self) -> Vec<String> {
+        self.overrides
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect()
+    }
+
+    /// Ensure build setting override keys are valid build setting names, e.g. `ONLY_ACTIVE_ARCH`,
+    /// so that they can't be mistaken for other xcodebuild arguments
+    pub fn validate_overrides(&self) -> Result<()> {
+        let is_valid = |key: &str| {
+            let mut chars = key.chars();
+            chars
+                .next()
+                .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+
+        match self.overrides.iter().find(|(key, _)| !is_valid(key)) {
+            Some((key, _)) => Err(Error::Build(format!("Invalid build setting name `{key}`"))),
+            None => Ok(()),
+        }
+    }
+
+    /// Ensure xcconfig file, when set, exists and is readable from a given project root
+    pub fn validate_xcconfig(&self, root: &Path) -> Result<()> {
+        let xcconfig = match self.xcconfig.as_ref() {
+            Some(xcconfig) => root.join(xcconfig),
+            None => return Ok(()),
+        };
+
+        if xcconfig.is_dir() {
+            return Err(Error::Build(format!(
+                "xcconfig `{}` is a directory",
+                xcconfig.display()
+            )));
+        }
+
+        std::fs::File::open(&xcconfig).map(|_| ()).map_err(|err| {
+            Error::Build(format!(
+                "Can't read xcconfig `{}`: {err}",
+                xcconfig.display()
+            ))
+        })
+    }
+
+    /// Get SDK to build targets of a given platform with when no device is selected
+    pub fn default_sdk(platform: &str) -> Option<&'static str> {
+        Platform::named(platform).map(|p| p.simulator_sdk.unwrap_or(p.sdk))
+    }
+
+    /// Ensure sdk and destination, when set, can build targets of a given platform
+    pub fn validate_platform(&self, platform: &str) -> Result<()> {
+        let info = match Platform::named(platform) {
+            Some(info) => info,
+            None => return Ok(()),
+        };
+        let sdks = [Some(info.sdk), info.simulator_sdk];
+        let simulator = info.simulator_sdk.map(|_| format!("{platform} Simulator"));
+        let destinations = [Some(platform.to_string()), simulator];
+        let target = &self.target;
+
+        if let Some(ref sdk) = self.sdk {
+            // SDK may be versioned, e.g. `iphoneos16.4`
+            if !sdks.iter().flatten().any(|s| sdk.starts_with(s)) {
+                return Err(Error::Build(format!(
+                    "SDK `{sdk}` can't build {target} ({platform} target)"
+                )));
+            }
+        }
+
+        let destination_platform = self.destination.as_deref().and_then(|destination| {
+            destination.split(',').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                key.trim().ends_with("platform").then(|| value.trim())
+            })
+        });
+
+        match destination_platform {
+            Some(value) if !destinations.iter().flatten().any(|d| d == value) => Err(Error::Build(
+                format!("Destination platform `{value}` can't run {target} ({platform} target)"),
+            )),
+            _ => Ok(()),
+        }
     }
 }
 
@@ -116,3 +421,82 @@ impl Operation {
         matches!(self, Self::Once)
     }
 }
+
+#[test]
+fn test_build_settings_platform() {
+    let mut settings = BuildSettings {
+        target: "App".into(),
+        configuration: Some("Debug".into()),
+        scheme: None,
+        sdk: Some("iphoneos16.4".into()),
+        destination: Some("platform=iOS Simulator,name=iPhone 14".into()),
+        xcconfig: None,
+        overrides: vec![],
+    };
+    assert_eq!(
+        settings.to_args(),
+        vec![
+            "-configuration",
+            "Debug",
+            "-target",
+            "App",
+            "-sdk",
+            "iphoneos16.4",
+            "-destination",
+            "platform=iOS Simulator,name=iPhone 14"
+        ]
+    );
+    assert!(settings.validate_platform("iOS").is_ok());
+    assert!(settings.validate_platform("macOS").is_err());
+
+    settings.sdk = None;
+    settings.destination = Some("generic/platform=macOS".into());
+    assert!(settings.validate_platform("macOS").is_ok());
+    assert!(settings.validate_platform("tvOS").is_err());
+
+    settings.destination = Some("platform=visionOS Simulator,name=Apple Vision Pro".into());
+    assert!(settings.validate_platform("visionOS").is_ok());
+    assert!(settings.validate_platform("iOS").is_err());
+    assert_eq!(BuildSettings::default_sdk("visionOS"), Some("xrsimulator"));
+    assert_eq!(BuildSettings::default_sdk("macOS"), Some("macosx"));
+}
+
+#[test]
+fn test_build_settings_xcconfig() {
+    let root = std::env::temp_dir().join("xbase_test_xcconfig");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(
+        root.join("Local.xcconfig"),
+        "SWIFT_ACTIVE_COMPILATION_CONDITIONS = LOCAL",
+    )
+    .unwrap();
+
+    let mut settings = BuildSettings {
+        target: "App".into(),
+        configuration: Some("Release".into()),
+        scheme: None,
+        sdk: None,
+        destination: None,
+        xcconfig: Some("Local.xcconfig".into()),
+        overrides: vec![],
+    };
+    assert_eq!(
+        settings.to_args(),
+        vec![
+            "-configuration",
+            "Release",
+            "-target",
+            "App",
+            "-xcconfig",
+            "Local.xcconfig"
+        ]
+    );
+    assert!(settings.validate_xcconfig(&root).is_ok());
+
+    settings.xcconfig = Some("Missing.xcconfig".into());
+    assert!(settings.validate_xcconfig(&root).is_err());
+    settings.xcconfig = Some(".".into());
+    assert!(settings.validate_xcconfig(&root).is_err());
+
+    std::fs::remove_dir_all(&root).ok();
+}
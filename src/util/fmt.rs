@@ -17,3 +17,71 @@ pub fn as_section(content: String) -> String {
 pub fn separator() -> String {
     ".".repeat(73)
 }
+
+/// Strip ANSI escape codes (e.g. colors) from content.
+///
+/// Returns stripped content and the original content if it had any escape codes.
+pub fn strip_ansi(content: String) -> (String, Option<String>) {
+    if !content.contains('\u{1b}') {
+        return (content, None);
+    }
+    (strip_ansi_escapes::strip_str(&content), Some(content))
+}
+
+/// Format time as an RFC 3339 UTC timestamp with milliseconds, e.g. `2023-10-15T09:41:05.120Z`
+pub fn timestamp(time: std::time::SystemTime) -> String {
+    let since_epoch = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let (secs, millis) = (since_epoch.as_secs() as i64, since_epoch.subsec_millis());
+    let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // Civil date from days since epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{millis:03}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[test]
+fn test_timestamp() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let time = UNIX_EPOCH + Duration::from_millis(1_697_362_865_120);
+    assert_eq!(timestamp(time), "2023-10-15T09:41:05.120Z");
+    assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400);
+    assert_eq!(timestamp(leap_day), "2000-02-29T00:00:00.000Z");
+}
+
+#[test]
+fn test_strip_ansi() {
+    // swiftc -color-diagnostics output
+    let colored = concat!(
+        "\u{1b}[1m/tmp/Demo/main.swift:3:5: \u{1b}[0m",
+        "\u{1b}[0;1;31merror: \u{1b}[0m",
+        "\u{1b}[1mcannot find 'foo' in scope\u{1b}[0m"
+    );
+    let (content, raw) = strip_ansi(colored.to_string());
+    assert_eq!(
+        content,
+        "/tmp/Demo/main.swift:3:5: error: cannot find 'foo' in scope"
+    );
+    assert_eq!(raw.as_deref(), Some(colored));
+
+    let (content, raw) = strip_ansi("Compiling Demo main.swift".to_string());
+    assert_eq!(content, "Compiling Demo main.swift");
+    assert_eq!(raw, None);
+}
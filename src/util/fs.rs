@@ -1,7 +1,7 @@
 //! Functions to query/access filesystem
 use crate::BuildSettings;
 use anyhow::Result;
-use std::{fmt::Debug, path::Path};
+use std::{fmt::Debug, path::Path, time::SystemTime};
 use tap::Pipe;
 use tokio::fs;
 
@@ -44,8 +44,10 @@ pub fn _get_build_cache_dir<P: AsRef<Path> + Debug>(
 
         if let Some(config) = config {
             let target = &config.target;
-            let config = config.configuration.to_string();
-            Some(format!("{base}/{target}_{config}",).replace(" ", "_"))
+            let config = config.configuration.as_deref().unwrap_or("Default");
+            // Only the appended name, base must match the one without config
+            let name = format!("{target}_{config}").replace(' ', "_");
+            Some(format!("{base}/{name}"))
         } else {
             Some(base)
         }
@@ -70,53 +72,280 @@ pub fn which(cmd: &str) -> Result<String> {
     Ok(which::which(cmd)?.to_str().unwrap().to_string())
 }
 
-/// Read .gitignore from root and return vec of glob patterns if the .gitignore eixists.
-pub async fn gitignore_to_glob_patterns<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
-    let gitignore_path = path.as_ref().join(".gitignore");
-    if !gitignore_path.exists() {
-        return Ok(Default::default());
+/// Files marking a project root
+pub const PROJECT_ROOT_MARKERS: [&str; 7] = [
+    "project.yml",
+    "Project.swift",
+    "Package.swift",
+    "Podfile",
+    "WORKSPACE",
+    "WORKSPACE.bazel",
+    "MODULE.bazel",
+];
+
+/// Whether a directory contains a project root marker or an xcodeproj
+fn is_project_root(dir: &Path) -> bool {
+    if PROJECT_ROOT_MARKERS.iter().any(|m| dir.join(m).exists()) {
+        return true;
     }
-    let content = tokio::fs::read_to_string(gitignore_path).await?;
-    Ok(gitignore_content_to_glob_patterns(content))
-}
-
-pub fn gitignore_content_to_glob_patterns(content: String) -> Vec<String> {
-    content
-        .split("\n")
-        .filter(|s| !s.is_empty() && !s.starts_with("#"))
-        .flat_map(|s| {
-            if s.starts_with("!") {
-                None // TODO(watchignore): support ! patterns
-            } else {
-                Some(("", s))
-            }
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.path().extension().map_or(false, |ext| ext == "xcodeproj"))
         })
-        .filter(|&(_, s)| s.chars().next() != Some('/'))
-        .map(|(pat, s)| {
-            if s != "/" && !s.starts_with("**") {
-                (pat, format!("**/{s}"))
-            } else {
-                (pat, format!("{s}"))
+        .unwrap_or_default()
+}
+
+/// Find the nearest project root containing path, resolving symlinks.
+///
+/// Stops at filesystem boundaries and at `$HOME`.
+pub fn find_project_root<P: AsRef<Path>>(path: P) -> crate::Result<std::path::PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let not_found = || crate::Error::NoProjectRoot(path.as_ref().to_path_buf());
+    let path = std::fs::canonicalize(path.as_ref()).map_err(|_| not_found())?;
+    let start = if path.is_dir() {
+        &path
+    } else {
+        path.parent().ok_or_else(not_found)?
+    };
+    let device = std::fs::metadata(start)?.dev();
+    let home = dirs::home_dir().and_then(|home| std::fs::canonicalize(home).ok());
+
+    for dir in start.ancestors() {
+        if std::fs::metadata(dir).map_or(true, |m| m.dev() != device) {
+            break;
+        }
+        if is_project_root(dir) {
+            return Ok(dir.to_path_buf());
+        }
+        if Some(dir) == home.as_deref() {
+            break;
+        }
+    }
+
+    Err(not_found())
+}
+
+/// Glob patterns derived from .gitignore files, relative to project root.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct GitIgnorePatterns {
+    /// Patterns of paths to ignore
+    pub ignore: Vec<String>,
+    /// Negated (`!`) patterns of paths to keep even if they match `ignore`
+    pub keep: Vec<String>,
+}
+
+impl GitIgnorePatterns {
+    pub fn extend(&mut self, other: GitIgnorePatterns) {
+        self.ignore.extend(other.ignore);
+        self.keep.extend(other.keep);
+    }
+}
+
+/// Read .gitignore files in root and its sub directories and return their glob patterns.
+pub async fn gitignore_to_glob_patterns<P: AsRef<Path>>(root: P) -> Result<GitIgnorePatterns> {
+    let root = root.as_ref();
+    let mut patterns = GitIgnorePatterns::default();
+
+    for entry in wax::walk("**/.gitignore", root)?.flatten() {
+        let path = entry.into_path();
+        let prefix = path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(root).ok())
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let content = fs::read_to_string(&path).await?;
+        patterns.extend(gitignore_content_to_glob_patterns(&content, &prefix));
+    }
+
+    Ok(patterns)
+}
+
+/// Read .gitignore in root, without nested ones, and return its patterns of paths to ignore.
+pub async fn root_gitignore_patterns<P: AsRef<Path>>(root: P) -> Vec<String> {
+    match fs::read_to_string(root.as_ref().join(".gitignore")).await {
+        Ok(content) => gitignore_content_to_glob_patterns(&content, "").ignore,
+        Err(_) => vec![],
+    }
+}
+
+/// Convert .gitignore content to glob patterns, prefix is the .gitignore directory relative to
+/// project root.
+pub fn gitignore_content_to_glob_patterns(content: &str, prefix: &str) -> GitIgnorePatterns {
+    let prefix = if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{prefix}/")
+    };
+    let mut patterns = GitIgnorePatterns::default();
+
+    for line in content.lines().map(str::trim_end) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+
+        let line = line.trim_end_matches('/');
+        if line.is_empty() {
+            continue;
+        }
+
+        // Patterns with a slash are relative to .gitignore directory, others match at any level.
+        let pattern = if line.contains('/') {
+            format!("{prefix}{}", line.trim_start_matches('/'))
+        } else {
+            format!("{prefix}**/{line}")
+        };
+
+        let target = if negated {
+            &mut patterns.keep
+        } else {
+            &mut patterns.ignore
+        };
+
+        target.extend([pattern.clone(), format!("{pattern}/**")]);
+    }
+
+    patterns
+}
+
+/// Whether any file in roots was modified at or after `since`, skipping hidden directories and
+/// files matching ignore globs relative to their root.
+///
+/// Directories whose whole content is ignored, e.g. by `**/build/**`, aren't descended into.
+///
+/// Files that can't be read count as modified.
+pub fn modified_since(roots: &[std::path::PathBuf], since: SystemTime, ignore: &[String]) -> bool {
+    let ignore = match wax::any::<wax::Glob, _>(ignore.iter().map(String::as_str)) {
+        Ok(ignore) => ignore,
+        Err(_) => return true,
+    };
+    roots
+        .iter()
+        .any(|root| dir_modified_since(root, root, since, &ignore))
+}
+
+fn dir_modified_since(root: &Path, dir: &Path, since: SystemTime, ignore: &wax::Any) -> bool {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return true,
+    };
+
+    for entry in entries {
+        let (path, file_type) = match entry.and_then(|e| Ok((e.path(), e.file_type()?))) {
+            Ok(entry) => entry,
+            Err(_) => return true,
+        };
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+
+        // Symlinked directories aren't followed, to avoid cycles
+        if file_type.is_dir() {
+            let is_hidden = relative
+                .file_name()
+                .map_or(false, |name| name.to_string_lossy().starts_with('.'));
+            if !is_hidden
+                && !is_dir_ignored(relative, ignore)
+                && dir_modified_since(root, &path, since, ignore)
+            {
+                return true;
             }
-        })
-        .flat_map(|(pat, s)| {
-            let pattern = format!("{pat}{s}");
-            vec![
-                pattern.clone(),
-                if pattern.ends_with("/") {
-                    format!("{pattern}**")
-                } else {
-                    format!("{pattern}/**")
-                },
-            ]
-        })
-        .collect::<Vec<String>>()
+        } else if !wax::Pattern::is_match(ignore, &*relative.to_string_lossy()) {
+            match std::fs::metadata(&path).and_then(|m| m.modified()) {
+                Ok(modified) if modified < since => {}
+                _ => return true,
+            }
+        }
+    }
+
+    false
+}
+
+/// Whether ignore globs match any path nested in a directory, relative to root
+fn is_dir_ignored(relative: &Path, ignore: &wax::Any) -> bool {
+    let nested = relative.join("_").join("_");
+    wax::Pattern::is_match(ignore, &*nested.to_string_lossy())
+}
+
+/// Get total size in bytes of a file or directory and the last time any of its files was
+/// modified, None if it doesn't exist.
+///
+/// Symlinks are counted but not followed, and unreadable entries are skipped.
+pub fn disk_usage(path: &Path) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    let mut usage = (0, None);
+    add_disk_usage(path, &metadata, &mut usage);
+    Some(usage)
+}
+
+fn add_disk_usage(
+    path: &Path,
+    metadata: &std::fs::Metadata,
+    usage: &mut (u64, Option<SystemTime>),
+) {
+    if !metadata.is_dir() {
+        usage.0 += metadata.len();
+        if let Ok(modified) = metadata.modified() {
+            usage.1 = usage.1.max(Some(modified));
+        }
+        return;
+    }
+
+    for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+        if let Ok(metadata) = entry.metadata() {
+            add_disk_usage(&entry.path(), &metadata, usage);
+        }
+    }
+}
+
+/// Write content to a file atomically.
+///
+/// Content is written to a temporary file in the same directory then renamed into place, so
+/// readers never observe a partial file. Writing runs on a blocking thread that completes even if
+/// the returned future is dropped (e.g. on cancellation), and the temporary file is removed if
+/// writing fails.
+pub async fn write_atomic<P, C>(path: P, content: C) -> std::io::Result<()>
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]> + Send + 'static,
+{
+    let path = path.as_ref().to_path_buf();
+    tokio::task::spawn_blocking(move || write_atomic_sync(&path, content.as_ref()))
+        .await
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
+}
+
+fn write_atomic_sync(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    // Unique per write, so concurrent writes of the same file don't share a temporary file
+    static WRITES: AtomicU32 = AtomicU32::new(0);
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "no file name"))?
+        .to_string_lossy();
+    let tmp = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name.trim_start_matches('.'),
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = std::fs::write(&tmp, content).and_then(|_| std::fs::rename(&tmp, path));
+    if result.is_err() {
+        std::fs::remove_file(&tmp).ok();
+    }
+    result
 }
 
 #[test]
 fn test_gitignore_patterns() {
-    let gitignore_patterns =
-        gitignore_content_to_glob_patterns(String::from(".build.log\n.compile"));
+    let gitignore_patterns = gitignore_content_to_glob_patterns(".build.log\n.compile", "").ignore;
     assert_eq!(
         gitignore_patterns,
         vec![
@@ -129,3 +358,152 @@ fn test_gitignore_patterns() {
 
     println!("{gitignore_patterns:#?}");
 }
+
+#[test]
+fn test_gitignore_negated_patterns() {
+    let patterns = gitignore_content_to_glob_patterns("# build\nbuild/\n!build/keep\n", "App");
+    assert_eq!(
+        patterns,
+        GitIgnorePatterns {
+            ignore: vec!["App/**/build".into(), "App/**/build/**".into()],
+            keep: vec!["App/build/keep".into(), "App/build/keep/**".into()],
+        }
+    );
+}
+
+#[test]
+fn test_root_gitignore_patterns() {
+    let root = std::env::temp_dir().join(format!("xbase-gitignore-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("App")).unwrap();
+    std::fs::write(root.join(".gitignore"), "Pods/\n!Pods/keep\n").unwrap();
+    std::fs::write(root.join("App").join(".gitignore"), "generated/\n").unwrap();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let patterns = runtime.block_on(root_gitignore_patterns(&root));
+    assert_eq!(patterns, vec!["**/Pods", "**/Pods/**"]);
+
+    let missing = runtime.block_on(root_gitignore_patterns(root.join("App/generated")));
+    assert!(missing.is_empty());
+
+    std::fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_find_project_root() {
+    let root = std::env::temp_dir().join(format!("xbase-root-{}", std::process::id()));
+    let nested = root.join("Sources").join("App");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::create_dir_all(root.join("App.xcodeproj")).unwrap();
+
+    let root = std::fs::canonicalize(&root).unwrap();
+    assert_eq!(find_project_root(&nested).unwrap(), root);
+    assert_eq!(find_project_root(&root).unwrap(), root);
+
+    std::fs::write(nested.join("Package.swift"), "").unwrap();
+    assert_eq!(
+        find_project_root(&nested).unwrap(),
+        root.join("Sources/App")
+    );
+
+    std::fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_write_atomic() {
+    use futures::FutureExt;
+
+    let root = std::env::temp_dir().join("xbase_test_write_atomic");
+    std::fs::remove_dir_all(&root).ok();
+    std::fs::create_dir_all(&root).unwrap();
+    let path = root.join(".compile");
+    std::fs::write(&path, "[]").unwrap();
+    let content = format!("[{}]", vec!["{}"; 100_000].join(","));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        // Cancelled right after starting, e.g. on daemon shutdown
+        drop(write_atomic(path.clone(), content.clone()).now_or_never());
+    });
+    // Waits for the blocking write to finish
+    drop(runtime);
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+    let entries = std::fs::read_dir(&root).unwrap().count();
+    assert_eq!(entries, 1, "temporary file left behind");
+
+    let missing = root.join("missing").join(".compile");
+    assert!(write_atomic_sync(&missing, b"[]").is_err());
+    assert!(!missing.exists());
+}
+
+#[test]
+fn test_build_cache_dir_with_spaces() {
+    let root = Path::new("/Users/me/Mobile Documents/Café App");
+    let config = BuildSettings {
+        target: "My App".into(),
+        configuration: Some("Debug".into()),
+        scheme: None,
+        sdk: None,
+        destination: None,
+        xcconfig: None,
+        overrides: vec![],
+    };
+
+    let base = get_build_cache_dir(root).unwrap();
+    assert!(base.ends_with("Xbase/Mobile Documents_Café App"));
+    assert_eq!(
+        get_build_cache_dir_with_config(root, &config).unwrap(),
+        format!("{base}/My_App_Debug")
+    );
+}
+
+#[test]
+fn test_modified_since() {
+    let root = std::env::temp_dir().join(format!("xbase-modified-{}", std::process::id()));
+    std::fs::remove_dir_all(&root).ok();
+    std::fs::create_dir_all(root.join("Sources")).unwrap();
+    std::fs::create_dir_all(root.join("build")).unwrap();
+    std::fs::write(root.join("Sources").join("App.swift"), "").unwrap();
+
+    let roots = vec![root.clone()];
+    let ignore = vec!["**/build/**".to_string()];
+    let since = SystemTime::now() + std::time::Duration::from_secs(1);
+    assert!(!modified_since(&roots, since, &ignore));
+
+    // Build products and hidden files written after the build started are ignored
+    let since = SystemTime::now() - std::time::Duration::from_secs(1);
+    std::fs::create_dir_all(root.join(".git")).unwrap();
+    std::fs::write(root.join(".git").join("index"), "").unwrap();
+    std::fs::write(root.join("build").join("App"), "").unwrap();
+    std::fs::remove_file(root.join("Sources").join("App.swift")).unwrap();
+    assert!(!modified_since(&roots, since, &ignore));
+
+    std::fs::write(root.join("Sources").join("App.swift"), "").unwrap();
+    assert!(modified_since(&roots, since, &ignore));
+    assert!(modified_since(&[root.join("missing")], since, &ignore));
+
+    let ignore = wax::any::<wax::Glob, _>(["**/build/**", "**/*.o", "Pods/*"]).unwrap();
+    assert!(is_dir_ignored(Path::new("build"), &ignore));
+    assert!(is_dir_ignored(Path::new("App/build"), &ignore));
+    assert!(!is_dir_ignored(Path::new("Sources"), &ignore));
+    assert!(!is_dir_ignored(Path::new("Pods"), &ignore));
+
+    std::fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_disk_usage() {
+    let root = std::env::temp_dir().join(format!("xbase-disk-usage-{}", std::process::id()));
+    std::fs::remove_dir_all(&root).ok();
+    std::fs::create_dir_all(root.join("Build/Products")).unwrap();
+    std::fs::write(root.join("Build/Products/App"), [0; 100]).unwrap();
+    std::fs::write(root.join("info.plist"), [0; 20]).unwrap();
+
+    let (size, modified) = disk_usage(&root).unwrap();
+    assert_eq!(size, 120);
+    assert!(modified.is_some());
+    assert_eq!(disk_usage(&root.join("info.plist")).unwrap().0, 20);
+    assert_eq!(disk_usage(&root.join("missing")), None);
+
+    std::fs::remove_dir_all(&root).ok();
+}
@@ -5,5 +5,7 @@ pub mod extensions;
 pub mod fmt;
 pub mod fs;
 pub mod pid;
+pub mod regex;
 pub mod tracing_setup;
+pub mod xcodebuild;
 pub use extensions::*;
@@ -22,6 +22,14 @@ pub fn is_valid_pid(pid: &i32, cb: impl FnOnce()) -> bool {
     }
 }
 
+/// Check if a process with a given pid is still alive, using `kill(pid, 0)`.
+///
+/// EPERM means the process exists but belongs to another user.
+pub fn is_alive(pid: u32) -> bool {
+    let res = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    res == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
 /// Get process pid by name.
 ///
 /// If an error occured during searching  an error will be returned,
@@ -48,6 +56,12 @@ where
     Err(crate::Error::Lookup("Process".into(), format!("{name}")))
 }
 
+#[test]
+fn test_is_alive() {
+    assert!(is_alive(std::process::id()));
+    assert!(!is_alive(i32::MAX as u32));
+}
+
 #[test]
 fn test_get_by_name() {
     let existing_process = get_pid_by_name("DockHelper");
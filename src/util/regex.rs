@@ -0,0 +1,121 @@
+//! Matchers of xcodebuild log entries
+
+use serde_json::{json, Value};
+
+/// Whether a log line starts the compilation of a swift module
+pub fn matches_compile_swift_sources(line: &str) -> bool {
+    line.starts_with("CompileSwiftSources ")
+}
+
+/// Whether a log line starts the compilation of an Objective-C/C translation unit
+pub fn matches_compile_c(line: &str) -> bool {
+    line.starts_with("CompileC ")
+}
+
+/// Get compile command of an Objective-C/C translation unit from a `CompileC` log entry at
+/// cursor, i.e. the `CompileC` line followed by its indented `cd` and clang invocation lines.
+pub fn clang_unit(lines: &[String], cursor: usize) -> Option<Value> {
+    let header = lines.get(cursor)?;
+    if !matches_compile_c(header) {
+        return None;
+    }
+    // `CompileC <object> <source> normal <arch> <language> <compiler> (in target ...)`
+    let header = header.split(" (in target '").next()?;
+    let mut file = shell_words::split(header).ok()?.get(2).cloned();
+    let mut directory = None;
+    let mut arguments = None;
+
+    for line in lines[cursor + 1..].iter() {
+        if !line.starts_with(char::is_whitespace) {
+            break;
+        }
+        let words = match shell_words::split(line.trim()) {
+            Ok(words) if !words.is_empty() => words,
+            _ => continue,
+        };
+        if words[0] == "cd" {
+            directory = words.get(1).cloned();
+        } else if words[0].ends_with("clang") || words[0].ends_with("clang++") {
+            if let Some(idx) = words.iter().position(|arg| arg == "-c") {
+                file = words.get(idx + 1).cloned().or(file);
+            }
+            arguments = Some(words);
+        }
+    }
+
+    Some(json!({
+        "directory": directory?,
+        "file": file?,
+        "arguments": arguments?,
+    }))
+}
+
+/// Parser of `CompileC` entries streamed from build output
+#[derive(Debug, Default)]
+pub struct ClangUnitParser {
+    /// Lines of the current `CompileC` entry
+    entry: Vec<String>,
+}
+
+impl ClangUnitParser {
+    /// Feed a line of output, returns compile command of previous entry once it's complete
+    pub fn feed(&mut self, line: &str) -> Option<Value> {
+        if !self.entry.is_empty() && line.starts_with(char::is_whitespace) {
+            self.entry.push(line.to_string());
+            return None;
+        }
+
+        let unit = self.finish();
+        if matches_compile_c(line) {
+            self.entry.push(line.to_string());
+        }
+        unit
+    }
+
+    /// Get compile command of last entry, once output has ended
+    pub fn finish(&mut self) -> Option<Value> {
+        let entry = std::mem::take(&mut self.entry);
+        clang_unit(&entry, 0)
+    }
+}
+
+#[test]
+fn test_clang_unit() {
+    let log = include_str!("../../tests/fixtures/mixed_build.log")
+        .lines()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let cursor = |prefix: &str| log.iter().position(|l| l.starts_with(prefix)).unwrap();
+
+    assert_eq!(log.iter().filter(|l| matches_compile_c(l)).count(), 3);
+    assert!(clang_unit(&log, cursor("CompileSwiftSources ")).is_none());
+    assert!(matches_compile_swift_sources(
+        &log[cursor("CompileSwiftSources ")]
+    ));
+
+    let unit = clang_unit(&log, cursor("CompileC ")).unwrap();
+    assert_eq!(unit["directory"], "/Users/dev/Projects/Mixed App");
+    assert_eq!(
+        unit["file"],
+        "/Users/dev/Projects/Mixed App/Mixed/Greeter.m"
+    );
+    assert_eq!(unit["arguments"][1], "-x");
+    assert_eq!(unit["arguments"][2], "objective-c");
+
+    let mut parser = ClangUnitParser::default();
+    let mut units = log
+        .iter()
+        .flat_map(|line| parser.feed(line))
+        .collect::<Vec<_>>();
+    units.extend(parser.finish());
+    let files = units.iter().map(|u| u["file"].as_str().unwrap());
+    assert_eq!(
+        files.collect::<Vec<_>>(),
+        vec![
+            "/Users/dev/Projects/Mixed App/Mixed/Greeter.m",
+            "/Users/dev/Projects/Mixed App/Mixed/Formatter.mm",
+            "/Users/dev/Projects/Mixed App/Mixed/main.c",
+        ]
+    );
+    assert_eq!(units[1]["arguments"][2], "objective-c++");
+}
@@ -0,0 +1,166 @@
+//! Daemon-wide xcodebuild executable and arguments
+use once_cell::sync::Lazy;
+use process_stream::ProcessExt;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Fallback xcodebuild, used when `xcrun --find xcodebuild` fails
+const DEFAULT_XCODEBUILD: &str = "/usr/bin/xcodebuild";
+
+static CONFIG: Lazy<DaemonConfig> = Lazy::new(DaemonConfig::load);
+static XCODEBUILD: Lazy<PathBuf> = Lazy::new(|| {
+    CONFIG
+        .xcodebuild_path
+        .clone()
+        .or_else(find_xcodebuild)
+        .unwrap_or_else(|| DEFAULT_XCODEBUILD.into())
+});
+
+/// Daemon configuration, read from `~/.config/xbase/config.json`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Path to xcodebuild executable, resolved with `xcrun --find xcodebuild` if unset
+    pub xcodebuild_path: Option<PathBuf>,
+    /// Arguments passed to every xcodebuild invocation, e.g. `-quiet`
+    pub xcodebuild_args: Vec<String>,
+}
+
+impl DaemonConfig {
+    /// Load daemon configuration, falling back to defaults when missing or invalid
+    fn load() -> Self {
+        let path = match dirs::home_dir() {
+            Some(home) => home.join(".config/xbase/config.json"),
+            None => return Self::default(),
+        };
+        let content = match std::fs::read(&path) {
+            Ok(content) => content,
+            Err(_) => return Self::default(),
+        };
+
+        serde_json::from_slice(&content).unwrap_or_else(|err| {
+            tracing::warn!("Invalid {path:?}: {err}, using defaults");
+            Self::default()
+        })
+    }
+}
+
+/// Resolve xcodebuild of the selected Xcode
+fn find_xcodebuild() -> Option<PathBuf> {
+    let output = std::process::Command::new("/usr/bin/xcrun")
+        .args(["--find", "xcodebuild"])
+        .output()
+        .ok()?;
+    let path = String::from_utf8(output.stdout).ok()?;
+    (output.status.success() && !path.trim().is_empty()).then(|| path.trim().into())
+}
+
+/// Get path to xcodebuild executable
+pub fn path() -> &'static Path {
+    XCODEBUILD.as_path()
+}
+
+/// Get version of the xcodebuild executable as `(major, minor)`, e.g. `(15, 0)` for 15.0.1.
+///
+/// Runs `xcodebuild -version` once, blocking.
+pub fn version() -> Option<(u32, u32)> {
+    *VERSION
+}
+
+static VERSION: Lazy<Option<(u32, u32)>> = Lazy::new(|| {
+    let output = std::process::Command::new(path())
+        .envs(envs())
+        .arg("-version")
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| parse_version(&String::from_utf8_lossy(&output.stdout)))?
+});
+
+/// Parse `xcodebuild -version` output, e.g. `Xcode 15.0.1\nBuild version 15A507`
+fn parse_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.lines().next()?.strip_prefix("Xcode ")?;
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+    Some((major, minor))
+}
+
+/// Get arguments to pass to every xcodebuild invocation
+pub fn global_args() -> &'static [String] {
+    &CONFIG.xcodebuild_args
+}
+
+/// Get Developer directory containing an xcodebuild executable, if any.
+///
+/// e.g. `/Applications/Xcode-beta.app/Contents/Developer`
+fn developer_dir(xcodebuild: &Path) -> Option<&Path> {
+    xcodebuild
+        .ancestors()
+        .find(|dir| dir.ends_with("Contents/Developer"))
+}
+
+/// Developer directory of the configured xcodebuild, unless already set in the environment
+static DEVELOPER_DIR: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    if CONFIG.xcodebuild_path.is_none() || std::env::var_os("DEVELOPER_DIR").is_some() {
+        return None;
+    }
+    developer_dir(path()).map(Path::to_path_buf)
+});
+
+/// Get environment to spawn xcodebuild and xcrun with, so their shims use the configured Xcode.
+///
+/// Set on each spawned process, as changing the daemon environment once threads are running
+/// is unsound.
+pub fn envs() -> Vec<(&'static str, &'static Path)> {
+    DEVELOPER_DIR
+        .iter()
+        .map(|dir| ("DEVELOPER_DIR", dir.as_path()))
+        .collect()
+}
+
+/// Set [`envs`] on a process, e.g. xclog spawning `/usr/bin/xcodebuild`
+pub fn set_envs<P: ProcessExt>(process: &mut P) {
+    process.get_command().envs(envs());
+}
+
+/// Ensure xcodebuild is executable
+pub fn setup() -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let xcodebuild = path();
+    let metadata = std::fs::metadata(xcodebuild)
+        .map_err(|err| anyhow::anyhow!("xcodebuild at {xcodebuild:?}: {err}"))?;
+    if !metadata.is_file() || metadata.permissions().mode() & 0o111 == 0 {
+        anyhow::bail!("xcodebuild at {xcodebuild:?} is not executable");
+    }
+
+    tracing::info!("Using {xcodebuild:?} {:?}", global_args());
+    Ok(())
+}
+
+#[test]
+fn test_developer_dir() {
+    let xcodebuild =
+        Path::new("/Applications/Xcode-beta.app/Contents/Developer/usr/bin/xcodebuild");
+    assert_eq!(
+        developer_dir(xcodebuild),
+        Some(Path::new("/Applications/Xcode-beta.app/Contents/Developer"))
+    );
+    assert_eq!(developer_dir(Path::new("/usr/bin/xcodebuild")), None);
+}
+
+#[test]
+fn test_parse_version() {
+    assert_eq!(
+        parse_version("Xcode 15.0.1\nBuild version 15A507\n"),
+        Some((15, 0))
+    );
+    assert_eq!(
+        parse_version("Xcode 16\nBuild version 16A242d"),
+        Some((16, 0))
+    );
+    assert_eq!(parse_version("xcode-select: error"), None);
+}
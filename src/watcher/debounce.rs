@@ -0,0 +1,88 @@
+use super::Event;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Coalesce bursts of file system events into a single event per path.
+#[derive(Debug)]
+pub struct Debouncer {
+    /// Time to wait after the last event before flushing pending events
+    window: Duration,
+    pending: HashMap<PathBuf, Event>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Default::default(),
+        }
+    }
+
+    /// Get debounce window
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Whether there are no pending events
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Add an event, coalescing it with pending event of the same path
+    pub fn push(&mut self, event: Event) {
+        let path = event.path().clone();
+        match self.pending.remove(&path) {
+            // Created and removed within the window, nothing to report
+            Some(pending) if pending.is_create_event() && event.is_remove_event() => {
+                tracing::trace!("{event} cancelled out {pending}");
+            }
+            // Keep reporting it as created
+            Some(pending) if pending.is_create_event() => {
+                self.pending.insert(path, pending);
+            }
+            _ => {
+                self.pending.insert(path, event);
+            }
+        }
+    }
+
+    /// Take pending events
+    pub fn flush(&mut self) -> Vec<Event> {
+        self.pending.drain().map(|(_, event)| event).collect()
+    }
+}
+
+#[test]
+fn test_debouncer_coalesce() {
+    use super::WatcherState;
+    use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind};
+    use notify::{Event as NotifyEvent, EventKind};
+
+    let ignore = wax::any::<wax::Glob, _>(vec!["**/.git/**"]).unwrap();
+    let state = WatcherState::new();
+    std::thread::sleep(Duration::from_millis(5));
+
+    let event = |kind: EventKind, path: &str| {
+        let event = NotifyEvent::new(kind).add_path(path.into());
+        Event::new("/tmp/Demo".as_ref(), &ignore, &state, event).unwrap()
+    };
+    let modified = || EventKind::Modify(ModifyKind::Data(DataChange::Content));
+
+    let mut debouncer = Debouncer::new(Duration::from_millis(150));
+    debouncer.push(event(modified(), "/tmp/Demo/main.swift"));
+    debouncer.push(event(modified(), "/tmp/Demo/main.swift"));
+    debouncer.push(event(modified(), "/tmp/Demo/main.swift"));
+    assert_eq!(debouncer.flush().len(), 1);
+    assert!(debouncer.is_empty());
+
+    debouncer.push(event(
+        EventKind::Create(CreateKind::File),
+        "/tmp/Demo/tmp.swift",
+    ));
+    debouncer.push(event(
+        EventKind::Remove(RemoveKind::File),
+        "/tmp/Demo/tmp.swift",
+    ));
+    assert!(debouncer.flush().is_empty());
+}
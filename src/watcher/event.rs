@@ -3,7 +3,7 @@ use super::WatcherState;
 use notify::{Event as NotifyEvent, EventKind as NotifyEventKind};
 use std::{
     fmt,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 use wax::Any;
@@ -11,6 +11,8 @@ use wax::Any;
 #[derive(Default, Debug)]
 pub struct Event {
     path: PathBuf,
+    /// All paths of the event, i.e. source and destination of renames
+    paths: Vec<PathBuf>,
     file_name: String,
     kind: EventKind,
     last_path: Arc<Mutex<PathBuf>>,
@@ -34,8 +36,90 @@ impl Default for EventKind {
     }
 }
 
+/// What kind of file an event path is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// Compiled source file, e.g. `.swift` or `.m`
+    Source,
+    /// Header file, e.g. `.h`
+    Header,
+    /// Bundled resource, e.g. storyboards, assets and localized strings
+    Resource,
+    /// Project definition or build configuration, e.g. `project.yml` or `.xcconfig`
+    Project,
+    /// Anything else
+    Other,
+}
+
+impl FileKind {
+    /// Extensions of header files
+    const HEADER_EXTENSIONS: [&'static str; 3] = ["h", "hh", "hpp"];
+    /// Extensions of resource files
+    const RESOURCE_EXTENSIONS: [&'static str; 13] = [
+        "storyboard",
+        "xib",
+        "strings",
+        "stringsdict",
+        "plist",
+        "json",
+        "png",
+        "jpg",
+        "pdf",
+        "ttf",
+        "otf",
+        "xcstrings",
+        "intentdefinition",
+    ];
+    /// Extensions of project definition and build configuration files
+    const PROJECT_EXTENSIONS: [&'static str; 4] = ["pbxproj", "xcconfig", "xcscheme", "bazel"];
+    /// Names of project definition files
+    const PROJECT_FILES: [&'static str; 8] = [
+        "project.yml",
+        "Project.swift",
+        "Package.swift",
+        "Podfile",
+        "Podfile.lock",
+        "BUILD",
+        "WORKSPACE",
+        ".xbase.json",
+    ];
+
+    /// Classify a file by its name and extension
+    pub fn of(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        let in_bundle = |bundle: &str| {
+            path.components()
+                .any(|c| c.as_os_str().to_string_lossy().ends_with(bundle))
+        };
+
+        if Self::PROJECT_FILES.contains(&name) || Self::PROJECT_EXTENSIONS.contains(&ext) {
+            Self::Project
+        } else if crate::compile::SOURCE_EXTENSIONS.contains(&ext) {
+            Self::Source
+        } else if Self::HEADER_EXTENSIONS.contains(&ext) {
+            Self::Header
+        } else if Self::RESOURCE_EXTENSIONS.contains(&ext)
+            || in_bundle(".xcassets")
+            || in_bundle(".lproj")
+        {
+            Self::Resource
+        } else {
+            Self::Other
+        }
+    }
+}
+
 impl Event {
+    /// Create event from a notify event, skipping paths matching ignore globs relative to root
     pub fn new<'a>(
+        root: &Path,
         ignore: &'a Any<'a>,
         state: &WatcherState,
         mut event: NotifyEvent,
@@ -43,10 +127,6 @@ impl Event {
         use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind};
         use NotifyEventKind::*;
 
-        if event.paths.len() > 1 {
-            tracing::error!("More than one path! {:#?}", event)
-        }
-
         let kind = match event.kind {
             Create(CreateKind::File) => EventKind::FileCreated,
             Create(CreateKind::Folder) => EventKind::FolderCreated,
@@ -57,7 +137,8 @@ impl Event {
             kind => EventKind::Other(kind),
         };
 
-        let path = event.paths.swap_remove(0);
+        let paths = std::mem::take(&mut event.paths);
+        let path = paths[0].clone();
         let file_name = match path.file_name() {
             Some(name) => name.to_string_lossy().to_string(),
             None => {
@@ -69,7 +150,8 @@ impl Event {
         let is_match = wax::Pattern::is_match;
 
         // Skip ignore paths
-        if is_match(ignore, &*path.to_string_lossy()) {
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        if is_match(ignore, &*relative.to_string_lossy()) {
             tracing::trace!(r#""{file_name}" ignored"#);
             return None;
         }
@@ -82,6 +164,7 @@ impl Event {
 
         let event = Self {
             path,
+            paths,
             file_name,
             kind,
             last_path: state.last_path(),
@@ -119,7 +202,7 @@ impl Event {
     /// Returns `true` if the watch event kind is [`EventKind::FileRemoved`] or
     /// [`EventKind::FolderRemoved`].
     pub fn is_remove_event(&self) -> bool {
-        matches!(self.kind, EventKind::FileRemoved) || matches!(self.kind, EventKind::FolderCreated)
+        matches!(self.kind, EventKind::FileRemoved) || matches!(self.kind, EventKind::FolderRemoved)
     }
 
     /// Returns `true` if the watch event kind is [`EventKind::FileRenamed`].
@@ -151,6 +234,28 @@ impl Event {
         &self.path
     }
 
+    /// Get all paths changed by the event, i.e. both source and destination of renames
+    #[must_use]
+    pub fn changed_paths(&self) -> &[PathBuf] {
+        if self.paths.is_empty() {
+            std::slice::from_ref(&self.path)
+        } else {
+            &self.paths
+        }
+    }
+
+    /// Get the event's path extension, if any
+    #[must_use]
+    pub fn extension(&self) -> Option<&str> {
+        self.path.extension().and_then(|ext| ext.to_str())
+    }
+
+    /// Get what kind of file the event's path is
+    #[must_use]
+    pub fn file_kind(&self) -> FileKind {
+        FileKind::of(&self.path)
+    }
+
     /// Get the event's is seen.
     #[must_use]
     pub fn is_seen(&self) -> bool {
@@ -193,3 +298,22 @@ impl fmt::Display for Event {
         write!(f, "{:?} [{event_name}]", self.file_name)
     }
 }
+
+#[test]
+fn test_file_kind() {
+    let kind = |path: &str| FileKind::of(Path::new(path));
+
+    assert_eq!(kind("/tmp/Demo/Sources/App.swift"), FileKind::Source);
+    assert_eq!(kind("/tmp/Demo/Sources/Bridge.h"), FileKind::Header);
+    assert_eq!(kind("/tmp/Demo/Main.storyboard"), FileKind::Resource);
+    assert_eq!(
+        kind("/tmp/Demo/Assets.xcassets/AppIcon.appiconset/Contents.json"),
+        FileKind::Resource
+    );
+    assert_eq!(kind("/tmp/Demo/project.yml"), FileKind::Project);
+    assert_eq!(
+        kind("/tmp/Demo/Demo.xcodeproj/project.pbxproj"),
+        FileKind::Project
+    );
+    assert_eq!(kind("/tmp/Demo/README.md"), FileKind::Other);
+}
@@ -1,14 +1,18 @@
+mod debounce;
 mod event;
 
+use crate::util::fs::{self, GitIgnorePatterns};
 use crate::*;
 use async_trait::async_trait;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use std::{sync::Mutex, time::SystemTime};
 use tokio::sync::mpsc::{self, channel, Receiver};
 use tokio::sync::Notify;
 use tracing::{error, info, instrument, warn};
 
+pub use debounce::*;
 pub use event::*;
 
 pub struct Watcher {
@@ -16,8 +20,21 @@ pub struct Watcher {
     state: WatcherState,
     sender: mpsc::UnboundedSender<runtime::PRMessage>,
     ignore: Vec<String>,
+    /// Globs of the only paths to react to, relative to root
+    only: Vec<String>,
     abort: Arc<Notify>,
     root: PathBuf,
+    /// Directories watched along with root, see [`ProjectConfig::watch_roots`]
+    extra_roots: Vec<PathBuf>,
+    debounce: Duration,
+    gitignore: bool,
+}
+
+/// Directory watched along with project root, with its own ignore patterns
+struct ExtraRoot {
+    path: PathBuf,
+    ignore: Vec<String>,
+    gitignore: GitIgnorePatterns,
 }
 
 impl Watcher {
@@ -28,14 +45,19 @@ impl Watcher {
         abort: &Arc<Notify>,
         root: &PathBuf,
         ignore: &Vec<String>,
+        config: &ProjectConfig,
     ) -> Self {
         Self {
             name: name.clone(),
             state: state.clone(),
             sender: sender.clone(),
             ignore: ignore.clone(),
+            only: config.watch_only_globs(),
             abort: abort.clone(),
             root: root.clone(),
+            extra_roots: config.watch_roots(root),
+            debounce: config.debounce(),
+            gitignore: config.gitignore,
         }
     }
 
@@ -44,15 +66,61 @@ impl Watcher {
         let (mut rx, _w) = self.get_watcher().unwrap();
         let watchignore = self.ignore.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
         let ignore = wax::any::<wax::Glob, _>(watchignore).unwrap();
+        let only = (!self.only.is_empty())
+            .then(|| wax::any::<wax::Glob, _>(self.only.iter().map(String::as_str)).ok())
+            .flatten();
+        let gitignore_patterns = if self.gitignore {
+            fs::gitignore_to_glob_patterns(&self.root)
+                .await
+                .unwrap_or_default()
+        } else {
+            Default::default()
+        };
+        let gitignore = GitIgnore::new(&self.root, &gitignore_patterns);
+        let extra_roots = self.extra_roots().await;
+        let extra_roots = extra_roots
+            .iter()
+            .flat_map(|root| {
+                let ignore = root.ignore.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+                let ignore = wax::any::<wax::Glob, _>(ignore).ok()?;
+                let gitignore = GitIgnore::new(&root.path, &root.gitignore);
+                Some((root.path.as_path(), ignore, gitignore))
+            })
+            .collect::<Vec<_>>();
+        let mut debouncer = Debouncer::new(self.debounce);
+        let flush = tokio::time::sleep(debouncer.window());
+        tokio::pin!(flush);
 
         tracing::info!("Watching");
         loop {
             tokio::select! {
                 _ = self.abort.notified() => break,
+                _ = &mut flush, if !debouncer.is_empty() => {
+                    for event in debouncer.flush() {
+                        self.sender.send(PRMessage::FSEvent(event)).ok();
+                    }
+                }
                 event = rx.recv() => {
                     if event.is_none() { break; }
                     let event = event.unwrap();
-                    let event = match Event::new(&ignore, &self.state, event) {
+                    let extra_root = event.paths.first().and_then(|path| {
+                        extra_roots.iter().find(|(root, ..)| path.starts_with(root))
+                    });
+                    // watch_only globs are relative to project root, so they don't apply to
+                    // extra roots
+                    let (root, ignore, gitignore, only) = match extra_root {
+                        Some((root, ignore, gitignore)) => (*root, ignore, gitignore, &None),
+                        None => (self.root.as_path(), &ignore, &gitignore, &only),
+                    };
+                    if event.paths.iter().any(|path| gitignore.is_ignored(path)) {
+                        tracing::trace!("{:?} ignored by .gitignore", event.paths);
+                        continue;
+                    }
+                    if !event.paths.iter().all(|path| self.is_watched(only, path)) {
+                        tracing::trace!("{:?} not in watch_only", event.paths);
+                        continue;
+                    }
+                    let event = match Event::new(root, ignore, &self.state, event) {
                         Some(e) => e,
                         None => continue,
                     };
@@ -62,7 +130,8 @@ impl Watcher {
                         tracing::debug!("{} [ignored]", event);
                         continue;
                     }
-                    self.sender.send(PRMessage::FSEvent(event)).ok();
+                    debouncer.push(event);
+                    flush.as_mut().reset(tokio::time::Instant::now() + debouncer.window());
                 }
             }
         }
@@ -70,6 +139,40 @@ impl Watcher {
         tracing::info!("[Dropped]");
     }
 
+    /// Whether a path matches `watch_only` globs, if any.
+    ///
+    /// Project files are always watched, as changes to them require regenerating the project.
+    fn is_watched(&self, only: &Option<wax::Any>, path: &Path) -> bool {
+        let only = match only {
+            Some(_) if FileKind::of(path) == FileKind::Project => return true,
+            Some(only) => only,
+            None => return true,
+        };
+        path.strip_prefix(&self.root).map_or(false, |path| {
+            wax::Pattern::is_match(only, &*path.to_string_lossy())
+        })
+    }
+
+    /// Load ignore patterns of extra roots, from their own `.xbase.json` and .gitignore files
+    async fn extra_roots(&self) -> Vec<ExtraRoot> {
+        let mut roots = vec![];
+        for path in self.extra_roots.iter() {
+            let gitignore = if self.gitignore {
+                fs::gitignore_to_glob_patterns(path)
+                    .await
+                    .unwrap_or_default()
+            } else {
+                Default::default()
+            };
+            roots.push(ExtraRoot {
+                path: path.clone(),
+                ignore: project::generate_watchignore(path).await,
+                gitignore,
+            });
+        }
+        roots
+    }
+
     fn get_watcher(&self) -> Result<(Receiver<notify::Event>, impl notify::Watcher)> {
         use notify::{Config, RecommendedWatcher, RecursiveMode::Recursive, Watcher};
         let (tx, rx) = channel::<notify::Event>(1);
@@ -82,6 +185,10 @@ impl Watcher {
         .map_err(to_err)?;
 
         watcher.watch(&self.root, Recursive).map_err(to_err)?;
+        for root in self.extra_roots.iter() {
+            info!("Watching {root:?}");
+            watcher.watch(root, Recursive).map_err(to_err)?;
+        }
         watcher
             .configure(Config::NoticeEvents(true))
             .map_err(to_err)?;
@@ -90,6 +197,50 @@ impl Watcher {
     }
 }
 
+/// Compiled .gitignore patterns
+struct GitIgnore<'a> {
+    root: &'a Path,
+    ignore: Option<wax::Any<'a>>,
+    keep: Option<wax::Any<'a>>,
+}
+
+impl<'a> GitIgnore<'a> {
+    fn new(root: &'a Path, patterns: &'a GitIgnorePatterns) -> Self {
+        let any = |patterns: &'a Vec<String>| {
+            let patterns = patterns
+                .iter()
+                .map(AsRef::as_ref)
+                .filter(|pattern| wax::Glob::new(pattern).is_ok())
+                .collect::<Vec<&str>>();
+            if patterns.is_empty() {
+                None
+            } else {
+                wax::any::<wax::Glob, _>(patterns).ok()
+            }
+        };
+
+        Self {
+            root,
+            ignore: any(&patterns.ignore),
+            keep: any(&patterns.keep),
+        }
+    }
+
+    /// Whether a path is ignored and not negated by a `!` pattern
+    fn is_ignored(&self, path: &Path) -> bool {
+        let path = match path.strip_prefix(self.root) {
+            Ok(path) => path.to_string_lossy(),
+            Err(_) => return false,
+        };
+        let is_match = |any: &Option<wax::Any>| {
+            any.as_ref()
+                .map_or(false, |any| wax::Pattern::is_match(any, &*path))
+        };
+
+        is_match(&self.ignore) && !is_match(&self.keep)
+    }
+}
+
 /// Trait to make an object react to filesystem changes.
 #[async_trait]
 pub trait Watchable: ToString + Send + Sync + 'static {
@@ -109,6 +260,15 @@ pub trait Watchable: ToString + Send + Sync + 'static {
 
     /// Drop watchable for watching a given file system
     async fn discard(&self);
+
+    /// Target built or ran by the watchable
+    fn target(&self) -> &str;
+
+    /// Kind of task the watchable runs on file changes, i.e. build or run
+    fn kind(&self) -> TaskKind;
+
+    /// Point the watchable to a project moved to a given root
+    fn relocate(&mut self, root: &Path);
 }
 
 #[derive(Default)]
@@ -145,18 +305,50 @@ impl WatchSubscribers {
         }
     }
 
+    /// Whether a subscriber with a given key exists
+    pub fn contains(&self, key: &str) -> bool {
+        self.inner.contains_key(key)
+    }
+
     pub fn keys(&self) -> Vec<String> {
         self.inner.keys().map(ToString::to_string).collect()
     }
 
+    /// Keys of subscribers running a given kind of task for a given target
+    pub fn target_keys(&self, target: &str, kind: TaskKind) -> Vec<String> {
+        self.inner
+            .iter()
+            .filter(|(_, w)| w.target() == target && w.kind() == kind)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Whether any subscriber builds or runs a given target
+    pub fn watches(&self, target: &str) -> bool {
+        self.inner.values().any(|w| w.target() == target)
+    }
+
+    /// Point subscribers to a project moved to a given root, keyed by their new keys
+    pub fn relocate(&mut self, root: &Path) {
+        self.inner = std::mem::take(&mut self.inner)
+            .into_values()
+            .map(|mut watchable| {
+                watchable.relocate(root);
+                (watchable.to_string(), watchable)
+            })
+            .collect();
+    }
+
+    /// Trigger subscribers reacting to a given event, returning targets of triggered ones
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
     pub async fn trigger(
         &mut self,
         project: &mut ProjectImpl,
         event: &Event,
         broadcast: &Arc<Broadcast>,
-    ) {
+    ) -> Vec<String> {
         let mut discards = vec![];
+        let mut triggered = vec![];
 
         for (key, w) in self.inner.iter() {
             if w.should_discard(&event).await {
@@ -164,8 +356,9 @@ impl WatchSubscribers {
                 discards.push(key.to_string());
             } else if w.should_trigger(&event).await {
                 let trigger = w.trigger(project, event, broadcast);
-                if let Err(err) = trigger.await {
-                    error!("trigger errored for `{key}`!: {err}");
+                match trigger.await {
+                    Ok(_) => triggered.push(w.target().to_string()),
+                    Err(err) => error!("trigger errored for `{key}`!: {err}"),
                 }
             }
         }
@@ -174,6 +367,8 @@ impl WatchSubscribers {
             info!("Discarded: `{key}`");
             self.inner.remove(&key);
         }
+
+        triggered
     }
 }
 
@@ -206,3 +401,54 @@ impl WatcherState {
         self.last_path.clone()
     }
 }
+
+#[test]
+fn test_relocate_watch_subscribers() {
+    let settings = BuildSettings {
+        target: "App".into(),
+        configuration: None,
+        scheme: None,
+        sdk: None,
+        destination: None,
+        xcconfig: None,
+        overrides: vec![],
+    };
+    let build = crate::server::BuildRequest {
+        root: "/old/Demo".into(),
+        settings: settings.clone(),
+        operation: Operation::Watch,
+        targets: vec![],
+        all: false,
+        dry_run: false,
+        device: None,
+        run: false,
+        timing_summary: false,
+        verbose: false,
+        result_bundle: false,
+    };
+    let run = RunService::new(
+        None,
+        "/old/Demo".into(),
+        settings.clone(),
+        Default::default(),
+        format!("/old/Demo:Run:Bin:{settings}"),
+        None,
+    );
+
+    let mut subscribers = WatchSubscribers::new(&"Demo".to_string());
+    subscribers.add(build);
+    subscribers.add(run);
+    subscribers.relocate(Path::new("/new/Demo"));
+
+    let mut keys = subscribers.keys();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![
+            format!("/new/Demo:Build:{settings}"),
+            format!("/new/Demo:Run:Bin:{settings}"),
+        ]
+    );
+    assert!(!subscribers.contains(&format!("/old/Demo:Build:{settings}")));
+    assert_eq!(subscribers.target_keys("App", TaskKind::Run).len(), 1);
+}
@@ -30,7 +30,7 @@ export default class FolderContext implements Disposable {
 
     folderCtx.subscriptions.push(
       await ctx.server.register(uri.fsPath)
-        .then(address => Broadcast.connect(folderCtx, address, ctx))
+        .then(state => Broadcast.connect(folderCtx, state.address, ctx))
         .catch(error => {
           throw Error(`[${name}] Failed to Initialize: ${error}`);
         }));
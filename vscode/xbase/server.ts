@@ -1,5 +1,5 @@
 import net from "net";
-import type { JSONValue, Request, Response } from "./types";
+import type { JSONValue, RegisterResponse, Request, Response } from "./types";
 import { Disposable } from "vscode";
 import { spawn } from "child_process";
 import { XBASE_BIN_ROOT } from "./constants";
@@ -38,15 +38,16 @@ export default class Server implements Disposable {
   }
 
   // Register a given root
-  async register(root: string): Promise<string> {
+  async register(root: string): Promise<RegisterResponse> {
     const value = await this.request({ method: "register", args: { root, id: process.pid } })
       .catch(error => {
         throw Error(`Registeration failed: ${error}`);
       });
 
-    if (typeof value === "string") return value;
+    if (value && typeof value === "object" && "address" in value)
+      return value as unknown as RegisterResponse;
 
-    throw Error(`Expected response to be a string, got ${value}`);
+    throw Error(`Expected response to be a project state, got ${value}`);
   }
 
   // Drop a root project
@@ -13,12 +13,24 @@ export namespace TaskKind {
         done: "Generated",
         value: "Generate",
       };
+    } else if (isClean(kind)) {
+      return { processing: "Cleaning", done: "Cleaned", value: "Clean" };
+    } else if (isInstall(kind)) {
+      return { processing: "Installing", done: "Installed", value: "Install" };
+    } else if (isTest(kind)) {
+      return { processing: "Testing", done: "Tested", value: "Test" };
+    } else if (isHook(kind)) {
+      return { processing: "Preparing", done: "Prepared", value: "Hook" };
     }
   };
   export const isRun = (kind: TaskKind) => kind === "Run";
   export const isBuild = (kind: TaskKind) => kind === "Build";
   export const isGenerate = (kind: TaskKind) => kind === "Generate";
   export const isCompile = (kind: TaskKind) => kind === "Compile";
+  export const isClean = (kind: TaskKind) => kind === "Clean";
+  export const isInstall = (kind: TaskKind) => kind === "Install";
+  export const isTest = (kind: TaskKind) => kind === "Test";
+  export const isHook = (kind: TaskKind) => kind === "Hook";
 }
 
 // AUTOGENERATED
@@ -67,7 +79,23 @@ export type TaskKind =
   /**
    * Generate xcodeproj
    */
-  | "Generate";
+  | "Generate"
+  /**
+   * Clean build products
+   */
+  | "Clean"
+  /**
+   * Install dependencies (i.e. CocoaPods)
+   */
+  | "Install"
+  /**
+   * Run tests
+   */
+  | "Test"
+  /**
+   * Run project load hook (`on_load`)
+   */
+  | "Hook";
 
 /**
  * What the status of task is currently under progress?
@@ -106,6 +134,22 @@ export type BuildSettings =
      * Scheme to build with
      */
     scheme: string | null;
+    /**
+     * SDK to build with, e.g. `iphoneos`, derived from target platform if unset
+     */
+    sdk?: string | null;
+    /**
+     * Destination to build for, e.g. `platform=iOS Simulator,name=iPhone 14`
+     */
+    destination?: string | null;
+    /**
+     * xcconfig file to override build settings with, relative to project root
+     */
+    xcconfig?: string | null;
+    /**
+     * Build settings to override, passed as `KEY=VALUE`, e.g. `("ONLY_ACTIVE_ARCH", "YES")`
+     */
+    overrides?: [string, string][];
   };
 
 /**
@@ -115,13 +159,65 @@ export type DeviceLookup =
   /**
    * Device Lookup information to run built project with
    */
-  { name: string; id: string };
+  {
+    name: string;
+    id: string;
+    /**
+     * Whether the device is a connected physical device rather than a simulator
+     */
+    physical?: boolean;
+  };
+
+/**
+ * Run destination as reported by simctl or devicectl
+ */
+export type DeviceInfo =
+  /**
+   * Run destination as reported by simctl or devicectl
+   */
+  {
+    udid: string;
+    name: string;
+    /**
+     * Simulator runtime identifier, empty for physical devices
+     */
+    runtime: string;
+    /**
+     * Device state, e.g. `Booted`, `Shutdown` or `Connected`
+     */
+    state: string;
+    platform: string;
+    physical: boolean;
+  };
 
 /**
  * Represntaiton of Project runners index by Platfrom
  */
 export type Runners = Record<string, DeviceLookup[]>;
 
+/**
+ * Kind of a buildable unit
+ */
+export type BuildableKind = "Target" | "Scheme";
+
+/**
+ * Kind of product a target builds
+ */
+export type ProductType =
+  | "Application"
+  | "AppExtension"
+  | "Framework"
+  | "StaticLibrary"
+  | "DynamicLibrary"
+  | "Bundle"
+  | "UnitTest"
+  | "UiTest"
+  /**
+   * Command line tool
+   */
+  | "Tool"
+  | "Other";
+
 /**
  * Target specfic information
  */
@@ -129,7 +225,26 @@ export type TargetInfo =
   /**
    * Target specfic information
    */
-  { platform: string; configurations: string[] };
+  {
+    platform: string;
+    configurations: string[];
+    /**
+     * Whether it's a target or a workspace scheme
+     */
+    kind?: BuildableKind;
+    /**
+     * Whether the target is built or ran on file changes
+     */
+    watching?: boolean;
+    /**
+     * Kind of product the target builds, unset if unknown, e.g. for schemes
+     */
+    product_type?: ProductType | null;
+    /**
+     * Bundle identifier of the target product, unset if not set literally in the project
+     */
+    bundle_id?: string | null;
+  };
 export type ProjectInfo = {
   /**
    * Get watched configurations for given root
@@ -141,12 +256,54 @@ export type ProjectInfo = {
   targets: Record<string, TargetInfo>;
 };
 
+/**
+ * Tool a project is generated or described with
+ */
+export type ProjectKind = "Xcodeproj" | "XcodeGen" | "Tuist" | "Swift" | "Bazel";
+
+/**
+ * Project information returned by `GetProjectInfoRequest`
+ */
+export type ProjectDetails = {
+  /**
+   * Project name
+   */
+  name: string;
+  /**
+   * Tool the project is generated or described with
+   */
+  kind: ProjectKind;
+  /**
+   * Targets and workspace schemes, flagged if watched
+   */
+  targets: Record<string, TargetInfo>;
+  /**
+   * Workspace schemes
+   */
+  schemes: string[];
+  /**
+   * Build configurations available across targets
+   */
+  configurations: string[];
+  /**
+   * Watched build and run requests keys
+   */
+  watchlist: string[];
+};
+
 /**
  * State usesd to set client state
  */
 export type State =
   | { key: "runners"; value: Runners }
-  | { key: "projectInfo"; value: ProjectInfo };
+  | { key: "projectInfo"; value: ProjectInfo }
+  | {
+      /**
+       * Whether acting on file changes is paused
+       */
+      key: "watchPaused";
+      value: boolean;
+    };
 
 /**
  * Representation of Messages that clients needs to process
@@ -184,9 +341,33 @@ export type Message =
        * Update Current Task
        */
       type: "UpdateCurrentTask";
-      args: { content: string; level: ContentLevel };
+      args: {
+        /**
+         * Content with ANSI escape codes stripped
+         */
+        content: string;
+        level: ContentLevel;
+        /**
+         * Content as emitted by the task process, only set if it had ANSI escape codes (colors)
+         */
+        ansi?: string | null;
+      };
     }
   | { type: "FinishCurrentTask"; args: { status: TaskStatus } }
+  | {
+      /**
+       * Task is about to start, sent before spawning its process
+       */
+      type: "TaskStarted";
+      args: { task: TaskKind; target: string };
+    }
+  | {
+      /**
+       * Task started with [`Message::TaskStarted`] finished
+       */
+      type: "TaskFinished";
+      args: { task: TaskKind; target: string; outcome: TaskStatus };
+    }
   | {
       /**
        * Notify client that something is being watched
@@ -200,8 +381,193 @@ export type Message =
        */
       type: "SetState";
       args: State;
+    }
+  | {
+      /**
+       * Progress of current task compile units
+       */
+      type: "Progress";
+      args: {
+        task: string;
+        current: U32;
+        /**
+         * Estimated total, i.e. most compile units seen in previous successful builds of the task
+         */
+        total: U32;
+      };
+    }
+  | {
+      /**
+       * Progress of current task when the total number of compile units is unknown
+       */
+      type: "IndeterminateProgress";
+      args: { task: string; current: U32 };
+    }
+  | {
+      /**
+       * Error, warning or note reported while building a target
+       */
+      type: "Diagnostic";
+      args: { target: string; diagnostic: BuildDiagnostic };
+    }
+  | {
+      /**
+       * Summary of a test suite run
+       */
+      type: "TestResult";
+      args: {
+        suite: string;
+        passed: U32;
+        failed: U32;
+        /**
+         * Duration in seconds
+         */
+        duration: F64;
+      };
+    }
+  | {
+      /**
+       * Time spent in each build phase, reported by builds ran with timing summary
+       */
+      type: "BuildTimingSummary";
+      args: { target: string; phases: BuildPhaseTiming[] };
+    }
+  | {
+      /**
+       * Line coverage of a target, reported by test runs with coverage enabled
+       */
+      type: "Coverage";
+      args: {
+        target: string;
+        lines_covered: U32;
+        lines_total: U32;
+        /**
+         * Covered lines percentage
+         */
+        percent: F64;
+        /**
+         * Coverage of target source files, only if requested
+         */
+        files?: FileCoverage[];
+      };
+    }
+  | {
+      /**
+       * Run session started
+       */
+      type: "RunSessionStarted";
+      args: RunSessionKey;
+    }
+  | {
+      /**
+       * Run session ended, either exited or stopped
+       */
+      type: "RunSessionEnded";
+      args: RunSessionKey;
+    }
+  | {
+      /**
+       * Sent when no other message was sent within ping interval, safe to ignore.
+       *
+       * Clients not receiving any message for longer may assume the daemon is dead.
+       */
+      type: "Ping";
     };
 
+/**
+ * Severity of a diagnostic reported in build output
+ */
+export type Severity = "Error" | "Warning" | "Note";
+
+/**
+ * Time spent in a build phase, e.g. `CompileSwiftSources`
+ */
+export type BuildPhaseTiming = {
+  /**
+   * Build phase name
+   */
+  phase: string;
+  /**
+   * Number of tasks ran in phase
+   */
+  tasks: U32;
+  /**
+   * Duration in seconds
+   */
+  duration: F64;
+};
+
+/**
+ * Line coverage of a source file
+ */
+export type FileCoverage = {
+  path: string;
+  lines_covered: U32;
+  lines_total: U32;
+  /**
+   * Covered lines percentage
+   */
+  percent: F64;
+};
+
+/**
+ * Error, warning or note reported by xcodebuild/swiftc/clang in build output
+ */
+export type BuildDiagnostic =
+  /**
+   * Error, warning or note reported by xcodebuild/swiftc/clang in build output
+   */
+  {
+    /**
+     * File the diagnostic is reported for
+     */
+    file: string | null;
+    /**
+     * Line number in file
+     */
+    line: U32 | null;
+    /**
+     * Column number in line
+     */
+    column: U32 | null;
+    /**
+     * Diagnostic message
+     */
+    message: string;
+    /**
+     * Diagnostic severity
+     */
+    severity?: Severity;
+    /**
+     * Notes attached to the diagnostic, formatted as `file:line:column: note: message`
+     */
+    notes?: string[];
+  };
+
+/**
+ * Key of a run session, a project target running on a device
+ */
+export type RunSessionKey =
+  /**
+   * Key of a run session, a project target running on a device
+   */
+  {
+    target: string;
+    /**
+     * Device udid, None when running a binary
+     */
+    device: string | null;
+  };
+
+/**
+ * How messages are framed on the broadcast socket, negotiated by client handshake.
+ *
+ * - `newline` (default): each message is a json object followed by `\n`.
+ * - `length`: each message is a 4-byte big-endian payload length followed by the json payload,
+ *   so clients can read exact frames without splitting lines.
+ */
+export type Framing = "newline" | "length";
+
 /**
  * Type of operation for building/ruuning a target/scheme
  */
@@ -221,7 +587,31 @@ export type ServerError =
   /**
    * Server Error due to failure while processing a `Request
    */
-  { kind: string; msg: string };
+  { kind: string; msg: string; code?: ErrorCode };
+
+/**
+ * Stable machine-readable error code, for clients to react to specific errors
+ */
+export type ErrorCode =
+  | "setup_failed"
+  | "not_found"
+  | "build_failed"
+  | "code_signing_failed"
+  | "run_failed"
+  | "generate_failed"
+  | "generator_missing"
+  | "generator_incompatible"
+  | "pod_install_failed"
+  | "timeout"
+  | "compile_failed"
+  | "definition_parsing"
+  | "no_definition"
+  | "multiple_definitions"
+  | "no_project"
+  | "internal"
+  | "message_parse"
+  | "already_running"
+  | "unexpected";
 
 /**
  * Server Response
@@ -230,8 +620,20 @@ export type Response =
   /**
    * Server Response
    */
-  { data?: JSONValue; error?: ServerError };
+  {
+    /**
+     * Id of the request responded to, if the request had one
+     */
+    id?: U64 | null;
+    /**
+     * Response envelope version, set along with `id`
+     */
+    v?: U32 | null;
+    data?: JSONValue;
+    error?: ServerError;
+  };
 export type U32 = number;
+export type F64 = number;
 
 /**
  * Register a project root
@@ -242,6 +644,32 @@ export type RegisterRequest =
    */
   { id: U32; root: string };
 
+/**
+ * Project state returned on registration, to restore client state on reconnect
+ */
+export type RegisterResponse =
+  /**
+   * Project state returned on registration, to restore client state on reconnect
+   */
+  {
+    /**
+     * Address of the broadcast socket to read project messages from
+     */
+    address: string;
+    /**
+     * Project targets and schemes, flagged if watched, and watched requests keys
+     */
+    project: ProjectDetails;
+    /**
+     * Active run sessions
+     */
+    sessions: RunSessionKey[];
+    /**
+     * Whether acting on file changes is paused
+     */
+    paused: boolean;
+  };
+
 /**
  * Request to build a particular project
  */
@@ -249,7 +677,44 @@ export type BuildRequest =
   /**
    * Request to build a particular project
    */
-  { root: string; settings: BuildSettings; operation: Operation };
+  {
+    root: string;
+    settings: BuildSettings;
+    operation: Operation;
+    /**
+     * Other targets to build after settings target, one at a time, using the same settings
+     */
+    targets?: string[];
+    /**
+     * Build all project targets one at a time, instead of settings target
+     */
+    all?: boolean;
+    /**
+     * Only report the build command, without running it
+     */
+    dry_run?: boolean;
+    /**
+     * Device to report the build command of dry runs for, or to build and run on with `run`
+     */
+    device?: DeviceLookup | null;
+    /**
+     * Launch the target on `device` after every successful build, replacing the running app
+     * without building it again. Requests with more than one target are rejected
+     */
+    run?: boolean;
+    /**
+     * Report time spent in each build phase once the build finishes (xcodebuild only)
+     */
+    timing_summary?: boolean;
+    /**
+     * Build with verbose xcodebuild output
+     */
+    verbose?: boolean;
+    /**
+     * Report diagnostics from a temporary result bundle, on top of build output (xcodebuild only)
+     */
+    result_bundle?: boolean;
+  };
 
 /**
  * Request to Run a particular project.
@@ -262,7 +727,39 @@ export type RunRequest =
     root: string;
     settings: BuildSettings;
     device?: DeviceLookup | null;
+    /**
+     * Watch rebuilds and relaunches on file changes, replacing the running app once rebuilt
+     */
     operation: Operation;
+    /**
+     * Environment variables to launch the app with
+     */
+    env?: Record<string, string>;
+    /**
+     * Arguments to launch the app with
+     */
+    args?: string[];
+    /**
+     * Id of the client making the request, if its run sessions should stop when it disconnects
+     */
+    id?: U32 | null;
+    /**
+     * Reject the request if the target is already running on the device, instead of restarting
+     */
+    exclusive?: boolean;
+    /**
+     * Bring Simulator.app to front after launching, ignored for physical devices
+     */
+    focus_simulator?: boolean;
+    /**
+     * Times to relaunch the app if it exits with non-zero code without being stopped, e.g. on
+     * flaky simulator startups. Capped to 5, 0 disables relaunching.
+     */
+    relaunch_on_crash?: U32;
+    /**
+     * Build before launching even if nothing changed since the last successful build
+     */
+    force_build?: boolean;
   };
 
 /**
@@ -275,27 +772,263 @@ export type DropRequest =
   { id: U32; roots: string[] };
 
 /**
- * Requests clinets can make
+ * Request to clean build products of a particular project
  */
-export type Request =
-  | {
-      /**
-       * Register project root and get broadcaster reader file description
-       */
-      method: "register";
-      args: RegisterRequest;
-    }
-  | {
-      /**
-       * Build Project and get path to where to build log will be located
-       */
-      method: "build";
-      args: BuildRequest;
-    }
-  | {
-      /**
-       * Run Project and get path to where to Runtime log will be located
-       */
+export type CleanRequest =
+  /**
+   * Request to clean build products of a particular project
+   */
+  {
+    root: string;
+    settings: BuildSettings;
+    /**
+     * Whether to also remove build cache root and compile database
+     */
+    deep?: boolean;
+  };
+
+/**
+ * Request to list run destinations compatible with a particular project
+ */
+export type GetDevicesRequest =
+  /**
+   * Request to list run destinations compatible with a particular project
+   */
+  {
+    root: string;
+    /**
+     * Only list destinations that can run a given target, of any project target otherwise
+     */
+    target?: string | null;
+  };
+
+/**
+ * Request to stop a watched build or a running app of a particular project
+ */
+export type StopRequest =
+  /**
+   * Request to stop a watched build or a running app of a particular project
+   */
+  {
+    root: string;
+    /**
+     * Key of the build or run request, i.e. `BuildRequest`/`RunRequest` formatted as string
+     */
+    key: string;
+  };
+
+/**
+ * Request to run tests of a particular project
+ */
+export type TestRequest =
+  /**
+   * Request to run tests of a particular project
+   */
+  {
+    root: string;
+    settings: BuildSettings;
+    device?: DeviceLookup | null;
+    /**
+     * Test identifiers to run, e.g. `AppTests/AppTests/testFoo`, all tests if empty
+     */
+    only_testing?: string[];
+    /**
+     * Report line coverage of tested targets once tests finish (xcodebuild only), slows tests
+     */
+    coverage?: boolean;
+    /**
+     * Include coverage of each source file, if coverage is reported
+     */
+    coverage_files?: boolean;
+  };
+
+/**
+ * Request to rewrite buildServer.json of a particular project with current build server path
+ * and project languages
+ */
+export type RegenerateBuildServerRequest =
+  /**
+   * Request to rewrite buildServer.json of a particular project with current build server path
+   * and project languages
+   */
+  { root: string };
+
+/**
+ * Request to get targets, schemes and configurations of a particular project
+ */
+export type GetProjectInfoRequest =
+  /**
+   * Request to get targets, schemes and configurations of a particular project
+   */
+  { root: string };
+
+/**
+ * Request to regenerate a particular project, regardless of file changes
+ */
+export type GenerateRequest =
+  /**
+   * Request to regenerate a particular project, regardless of file changes
+   */
+  { root: string };
+
+/**
+ * Request to regenerate compile commands (`.compile`) of a particular project, without building
+ */
+export type GenerateCompileCommandsRequest =
+  /**
+   * Request to regenerate compile commands (`.compile`) of a particular project, without building
+   */
+  { root: string };
+
+/**
+ * Request to get daemon and registered projects status, e.g. for health checks
+ */
+export type StatusRequest =
+  /**
+   * Request to get daemon and registered projects status, e.g. for health checks
+   */
+  {};
+
+/**
+ * Request to pause acting on file changes of a particular project, e.g. during a rebase.
+ *
+ * Files are still watched, but builds and runs aren't triggered until resumed.
+ */
+export type PauseRequest =
+  /**
+   * Request to pause acting on file changes of a particular project, e.g. during a rebase.
+   *
+   * Files are still watched, but builds and runs aren't triggered until resumed.
+   */
+  { root: string };
+
+/**
+ * Request to resume acting on file changes of a paused project
+ */
+export type ResumeRequest =
+  /**
+   * Request to resume acting on file changes of a paused project
+   */
+  {
+    root: string;
+    /**
+     * Process the last change made while paused, e.g. to rebuild watched targets once.
+     *
+     * Changes made while paused are ignored otherwise.
+     */
+    catch_up?: boolean;
+  };
+
+/**
+ * Request to start or stop watching a particular target, without building other targets
+ */
+export type WatchTargetRequest =
+  /**
+   * Request to start or stop watching a particular target, without building other targets
+   */
+  {
+    root: string;
+    target: string;
+    /**
+     * Watch target if true, stop watching it otherwise
+     */
+    enable: boolean;
+  };
+
+/**
+ * Request to write recent project messages, e.g. a failed build log, to a plain text file
+ */
+export type ExportLogRequest =
+  /**
+   * Request to write recent project messages, e.g. a failed build log, to a plain text file
+   */
+  {
+    root: string;
+    /**
+     * File to write to, relative to root unless absolute
+     */
+    path: string;
+  };
+
+/**
+ * Request to list broadcast sockets of all registered projects, e.g. to tail them all at once
+ */
+export type ListBroadcastsRequest =
+  /**
+   * Request to list broadcast sockets of all registered projects, e.g. to tail them all at once
+   */
+  {};
+
+/**
+ * Request to get the compile command of a single source file from the project compile database
+ */
+export type GetCompileCommandRequest =
+  /**
+   * Request to get the compile command of a single source file from the project compile database
+   */
+  {
+    root: string;
+    /**
+     * Source file, relative to root unless absolute
+     */
+    file: string;
+  };
+
+/**
+ * Request to get where build cache and compile database are and their size on disk, e.g. to see
+ * what a deep clean would remove
+ */
+export type GetCacheInfoRequest =
+  /**
+   * Request to get where build cache and compile database are and their size on disk, e.g. to see
+   * what a deep clean would remove
+   */
+  {
+    root: string;
+  };
+
+/**
+ * Request to move a registered project to a new root, e.g. after renaming its directory,
+ * keeping connected clients, watched requests and pause state.
+ */
+export type RelocateRequest =
+  /**
+   * Request to move a registered project to a new root, e.g. after renaming its directory,
+   * keeping connected clients, watched requests and pause state.
+   */
+  {
+    /**
+     * Root the project was registered with
+     */
+    old_root: string;
+    /**
+     * Root the project is now at
+     */
+    new_root: string;
+  };
+
+/**
+ * Requests clinets can make
+ */
+export type Request =
+  | {
+      /**
+       * Register project root and get broadcaster reader file description
+       */
+      method: "register";
+      args: RegisterRequest;
+    }
+  | {
+      /**
+       * Build Project and get path to where to build log will be located
+       */
+      method: "build";
+      args: BuildRequest;
+    }
+  | {
+      /**
+       * Run Project and get path to where to Runtime log will be located
+       */
       method: "run";
       args: RunRequest;
     }
@@ -305,4 +1038,276 @@ export type Request =
        */
       method: "drop";
       args: DropRequest;
+    }
+  | {
+      /**
+       * Clean Project build products
+       */
+      method: "clean";
+      args: CleanRequest;
+    }
+  | {
+      /**
+       * Get available simulators and connected devices for a project
+       */
+      method: "get_devices";
+      args: GetDevicesRequest;
+    }
+  | {
+      /**
+       * Stop a watched build or a running app
+       */
+      method: "stop";
+      args: StopRequest;
+    }
+  | {
+      /**
+       * Run Project tests
+       */
+      method: "test";
+      args: TestRequest;
+    }
+  | {
+      /**
+       * Rewrite project buildServer.json
+       */
+      method: "regenerate_build_server";
+      args: RegenerateBuildServerRequest;
+    }
+  | {
+      /**
+       * Get project targets, schemes and configurations
+       */
+      method: "get_project_info";
+      args: GetProjectInfoRequest;
+    }
+  | {
+      /**
+       * Regenerate project, e.g. after project.yml changed while the daemon was down
+       */
+      method: "generate";
+      args: GenerateRequest;
+    }
+  | {
+      /**
+       * Regenerate compile commands without building
+       */
+      method: "generate_compile_commands";
+      args: GenerateCompileCommandsRequest;
+    }
+  | {
+      /**
+       * Get daemon and registered projects status
+       */
+      method: "status";
+      args: StatusRequest;
+    }
+  | {
+      /**
+       * Pause acting on project file changes
+       */
+      method: "pause";
+      args: PauseRequest;
+    }
+  | {
+      /**
+       * Resume acting on project file changes
+       */
+      method: "resume";
+      args: ResumeRequest;
+    }
+  | {
+      /**
+       * Start or stop watching a project target
+       */
+      method: "watch_target";
+      args: WatchTargetRequest;
+    }
+  | {
+      /**
+       * Write recent project messages to a file
+       */
+      method: "export_log";
+      args: ExportLogRequest;
+    }
+  | {
+      /**
+       * List broadcast sockets of all registered projects
+       */
+      method: "list_broadcasts";
+      args: ListBroadcastsRequest;
+    }
+  | {
+      /**
+       * Get compile command of a source file
+       */
+      method: "get_compile_command";
+      args: GetCompileCommandRequest;
+    }
+  | {
+      /**
+       * Get build cache and compile database paths and sizes
+       */
+      method: "get_cache_info";
+      args: GetCacheInfoRequest;
+    }
+  | {
+      /**
+       * Move a registered project to a new root, e.g. after renaming its directory
+       */
+      method: "relocate";
+      args: RelocateRequest;
     };
+
+export type U64 = number;
+
+/**
+ * Status of a project broadcast socket
+ */
+export type BroadcastStatus =
+  /**
+   * Status of a project broadcast socket
+   */
+  {
+    address: string;
+    /**
+     * Whether the broadcast server is running and its socket exists
+     */
+    healthy: boolean;
+    /**
+     * Number of connected listeners
+     */
+    listeners: U32;
+  };
+
+/**
+ * Status of a registered project
+ */
+export type ProjectStatus =
+  /**
+   * Status of a registered project
+   */
+  {
+    root: string;
+    name: string;
+    /**
+     * Number of registered clients
+     */
+    clients: U32;
+    /**
+     * Whether the project runtime replied in time, false while busy (e.g. generating)
+     */
+    responsive: boolean;
+    /**
+     * Watched build and run requests keys, empty if not responsive
+     */
+    watchlist: string[];
+    /**
+     * Active run sessions, empty if not responsive
+     */
+    sessions: RunSessionKey[];
+    /**
+     * Whether acting on file changes is paused, false if not responsive
+     */
+    paused: boolean;
+    /**
+     * Broadcast socket status
+     */
+    broadcast: BroadcastStatus;
+  };
+
+/**
+ * Daemon status.
+ *
+ * Fields are only ever added, removals or changes bump [`StatusResponse::VERSION`].
+ */
+export type StatusResponse =
+  /**
+   * Daemon status.
+   *
+   * Fields are only ever added, removals or changes bump [`StatusResponse::VERSION`].
+   */
+  {
+    /**
+     * Response layout version
+     */
+    version: U32;
+    /**
+     * Daemon version
+     */
+    daemon_version: string;
+    /**
+     * Seconds since the daemon started
+     */
+    uptime_secs: U64;
+    /**
+     * Registered projects
+     */
+    projects: ProjectStatus[];
+  };
+
+/**
+ * Broadcast socket of a registered project
+ */
+export type BroadcastInfo =
+  /**
+   * Broadcast socket of a registered project
+   */
+  {
+    root: string;
+    name: string;
+    /**
+     * Socket address to connect to
+     */
+    address: string;
+    /**
+     * Number of registered clients
+     */
+    clients: U32;
+    /**
+     * Number of connected listeners
+     */
+    listeners: U32;
+    /**
+     * Whether a build or any other task is currently running
+     */
+    busy: boolean;
+  };
+
+/**
+ * Cached file or directory
+ */
+export type CachePath =
+  /**
+   * Cached file or directory
+   */
+  {
+    path: string;
+    exists: boolean;
+    /**
+     * Total size in bytes, 0 if missing
+     */
+    size: U64;
+    /**
+     * Seconds since epoch of the last modification of any of its files, if any
+     */
+    modified_secs: U64 | null;
+  };
+
+/**
+ * Project cache paths returned by `GetCacheInfoRequest`
+ */
+export type CacheInfo =
+  /**
+   * Project cache paths returned by `GetCacheInfoRequest`
+   */
+  {
+    /**
+     * Build cache root, where targets are built to
+     */
+    build_cache: CachePath;
+    /**
+     * Compile database paths
+     */
+    compile_database: CachePath[];
+  };
@@ -53,5 +53,8 @@ export async function isSupportedProjectRoot(root: string): Promise<boolean> {
   return (await pathExists(root, "Project.swift")
     || await pathExists(root, "project.yml")
     || await pathExists(root, "Package.swift")
+    || await pathExists(root, "WORKSPACE")
+    || await pathExists(root, "WORKSPACE.bazel")
+    || await pathExists(root, "MODULE.bazel")
     || (await glob("*.xcodeproj", { cwd: root })).length !== 0);
 }